@@ -29,6 +29,8 @@ CREATE TABLE IF NOT EXISTS core_user
     "updatedBy" uuid,
     "deletedAt" timestamp(6) with time zone,
     "deletedBy" uuid,
+    -- Set by admins, policy conditions can refer to them
+    attributes jsonb NOT NULL DEFAULT '{}'::jsonb,
     CONSTRAINT core_user_pkey PRIMARY KEY (id),
     CONSTRAINT "core_user_roleId_fkey" FOREIGN KEY ("roleId")
         REFERENCES core_role (id) MATCH SIMPLE
@@ -50,6 +52,7 @@ CREATE TABLE IF NOT EXISTS core_model
     picture character varying COLLATE pg_catalog."default",
     "isPublished" boolean NOT NULL DEFAULT false,
     "enableDataSharing" boolean NOT NULL DEFAULT false,
+    "telemetryRetentionDays" integer,
     "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     "createdBy" uuid,
     "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
@@ -125,6 +128,25 @@ CREATE TABLE IF NOT EXISTS core_policy_action
     "endPointVerb" character varying COLLATE pg_catalog."default" NOT NULL,
     "actionCount" integer NOT NULL DEFAULT 0,
     "resetFrequencyId" integer NOT NULL DEFAULT 1,
+    "maxConcurrentStreams" integer,
+    "maxStreamDuration" integer,
+    "isCacheable" boolean NOT NULL DEFAULT false,
+    "cacheTtl" integer,
+    "cacheScope" character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'twin',
+    "cacheHitsCount" boolean NOT NULL DEFAULT true,
+    "requestSchema" jsonb,
+    "responseSchema" jsonb,
+    "responseValidation" character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'off',
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    -- Burst protection on top of the quota: token_bucket or sliding_window, requests per rateLimitPeriod (second or minute)
+    "rateLimitAlgorithm" character varying COLLATE pg_catalog."default",
+    "rateLimitRequests" integer,
+    "rateLimitPeriod" character varying COLLATE pg_catalog."default",
+    "rateLimitBurst" integer,
+    -- Dates, weekdays and hours in a named timezone outside of which the action is refused
+    validity jsonb,
+    -- Rules over request body fields and user attributes, checked before the call is forwarded
+    conditions jsonb,
     "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     "createdBy" uuid NOT NULL,
     "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
@@ -223,6 +245,7 @@ CREATE TABLE IF NOT EXISTS core_policy_violation
     "policyId" uuid NOT NULL,
     "actionId" uuid NOT NULL,
     "twinId" uuid NOT NULL,
+    reason character varying COLLATE pg_catalog."default",
     "violatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     CONSTRAINT core_policy_violation_pkey PRIMARY KEY (id),
     CONSTRAINT "core_violation_userId_fkey" FOREIGN KEY ("userId")
@@ -247,6 +270,41 @@ CREATE TABLE IF NOT EXISTS core_policy_violation
         ON DELETE NO ACTION
 );
 
+-- Every block and unblock of a twin, "decidedBy" is null when the twin was blocked automatically after "blockAfter" violations
+CREATE TABLE IF NOT EXISTS core_policy_block
+(
+    id uuid NOT NULL,
+    "userId" uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    "policyId" uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    decision character varying COLLATE pg_catalog."default" NOT NULL,
+    "violationCount" integer NOT NULL DEFAULT 0,
+    reason character varying COLLATE pg_catalog."default",
+    "decidedBy" uuid,
+    "decidedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_policy_block_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_policy_block_userId_fkey" FOREIGN KEY ("userId")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_policy_block_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_policy_block_policyId_fkey" FOREIGN KEY ("policyId")
+        REFERENCES core_policy (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_policy_block_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS core_policy_block_twin_idx
+    ON core_policy_block ("twinId", "decidedAt" DESC);
+
 CREATE TABLE IF NOT EXISTS core_user_subscription
 (
     id uuid NOT NULL,
@@ -280,6 +338,212 @@ CREATE TABLE IF NOT EXISTS core_shared_model_data
         ON DELETE NO ACTION
 );
 
+CREATE TABLE IF NOT EXISTS core_owner_console_call
+(
+    id uuid NOT NULL,
+    "ownerId" uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    "statusCode" integer NOT NULL,
+    "durationMs" integer NOT NULL,
+    "errorMessage" character varying COLLATE pg_catalog."default",
+    "dataShared" boolean NOT NULL DEFAULT false,
+    "calledAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_owner_console_call_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_console_ownerId_fkey" FOREIGN KEY ("ownerId")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_console_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_console_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE TABLE IF NOT EXISTS core_twin_mqtt_binding
+(
+    id uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    "subscribeTopic" character varying COLLATE pg_catalog."default" NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    "publishTopic" character varying COLLATE pg_catalog."default",
+    qos integer NOT NULL DEFAULT 0,
+    "isActive" boolean NOT NULL DEFAULT true,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    "deletedAt" timestamp(6) with time zone,
+    "deletedBy" uuid,
+    CONSTRAINT core_twin_mqtt_binding_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_mqtt_binding_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_mqtt_binding_createdBy_fkey" FOREIGN KEY ("createdBy")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE TABLE IF NOT EXISTS core_model_telemetry_field
+(
+    id uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    name character varying COLLATE pg_catalog."default" NOT NULL,
+    "jsonPath" character varying COLLATE pg_catalog."default" NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default",
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    CONSTRAINT core_model_telemetry_field_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_telemetry_field_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+-- One OpenAPI 3 document per model entry point, uploaded by the owner or fetched from the component at publish
+CREATE TABLE IF NOT EXISTS core_model_openapi
+(
+    id uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    document jsonb NOT NULL,
+    source character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'upload',
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    CONSTRAINT core_model_openapi_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_model_openapi_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS core_model_openapi_component_idx
+    ON core_model_openapi ("modelId", COALESCE("componentAlias", ''));
+
+-- Monthly partitions are created and dropped by the telemetry retention task, the default partition catches the rest
+CREATE TABLE IF NOT EXISTS core_twin_telemetry
+(
+    id uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    "fieldName" character varying COLLATE pg_catalog."default" NOT NULL,
+    value double precision NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    "recordedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_twin_telemetry_pkey PRIMARY KEY (id, "recordedAt"),
+    CONSTRAINT "core_telemetry_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+) PARTITION BY RANGE ("recordedAt");
+
+CREATE TABLE IF NOT EXISTS core_twin_telemetry_default PARTITION OF core_twin_telemetry DEFAULT;
+
+CREATE INDEX IF NOT EXISTS core_twin_telemetry_lookup_idx
+    ON core_twin_telemetry ("twinId", "fieldName", "recordedAt");
+
+CREATE TABLE IF NOT EXISTS core_twin_shadow
+(
+    "twinId" uuid NOT NULL,
+    desired jsonb NOT NULL DEFAULT '{}'::jsonb,
+    reported jsonb NOT NULL DEFAULT '{}'::jsonb,
+    version integer NOT NULL DEFAULT 0,
+    "desiredUpdatedAt" timestamp(6) with time zone,
+    "reportedUpdatedAt" timestamp(6) with time zone,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_twin_shadow_pkey PRIMARY KEY ("twinId"),
+    CONSTRAINT "core_shadow_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE TABLE IF NOT EXISTS core_twin_schedule
+(
+    id uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    name character varying COLLATE pg_catalog."default",
+    "cronExpression" character varying COLLATE pg_catalog."default" NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    body jsonb,
+    "endsAt" timestamp(6) with time zone,
+    "nextRunAt" timestamp(6) with time zone,
+    "lastRunAt" timestamp(6) with time zone,
+    "isActive" boolean NOT NULL DEFAULT true,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    "deletedAt" timestamp(6) with time zone,
+    "deletedBy" uuid,
+    CONSTRAINT core_twin_schedule_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_schedule_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_schedule_createdBy_fkey" FOREIGN KEY ("createdBy")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE TABLE IF NOT EXISTS core_pipeline
+(
+    id uuid NOT NULL,
+    name character varying COLLATE pg_catalog."default" NOT NULL,
+    description character varying COLLATE pg_catalog."default",
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    "deletedAt" timestamp(6) with time zone,
+    "deletedBy" uuid,
+    CONSTRAINT core_pipeline_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_pipeline_createdBy_fkey" FOREIGN KEY ("createdBy")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+-- Steps run in position order, mappings may only read from the pipeline input or earlier steps
+CREATE TABLE IF NOT EXISTS core_pipeline_step
+(
+    id uuid NOT NULL,
+    "pipelineId" uuid NOT NULL,
+    "stepKey" character varying COLLATE pg_catalog."default" NOT NULL,
+    "position" integer NOT NULL,
+    "twinId" uuid NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    body jsonb,
+    mappings jsonb NOT NULL DEFAULT '[]'::jsonb,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_pipeline_step_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_pipeline_step_key_unique" UNIQUE ("pipelineId", "stepKey"),
+    CONSTRAINT "core_pipeline_step_pipelineId_fkey" FOREIGN KEY ("pipelineId")
+        REFERENCES core_pipeline (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_pipeline_step_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
 -- CREATE TABLE IF NOT EXISTS core_user_model_policy
 -- (
 --     id uuid NOT NULL,
@@ -0,0 +1,5 @@
+-- Concurrent stream limits of policy actions
+
+ALTER TABLE core_policy_action
+    ADD COLUMN IF NOT EXISTS "maxConcurrentStreams" integer,
+    ADD COLUMN IF NOT EXISTS "maxStreamDuration" integer;
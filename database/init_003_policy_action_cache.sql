@@ -0,0 +1,7 @@
+-- Response caching of policy actions
+
+ALTER TABLE core_policy_action
+    ADD COLUMN IF NOT EXISTS "isCacheable" boolean NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS "cacheTtl" integer,
+    ADD COLUMN IF NOT EXISTS "cacheScope" character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'twin',
+    ADD COLUMN IF NOT EXISTS "cacheHitsCount" boolean NOT NULL DEFAULT true;
@@ -0,0 +1,6 @@
+-- Request and response schemas of policy actions
+
+ALTER TABLE core_policy_action
+    ADD COLUMN IF NOT EXISTS "requestSchema" jsonb,
+    ADD COLUMN IF NOT EXISTS "responseSchema" jsonb,
+    ADD COLUMN IF NOT EXISTS "responseValidation" character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'off';
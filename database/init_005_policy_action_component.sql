@@ -0,0 +1,4 @@
+-- Policy actions on a single exposed component
+
+ALTER TABLE core_policy_action
+    ADD COLUMN IF NOT EXISTS "componentAlias" character varying COLLATE pg_catalog."default";
@@ -0,0 +1,29 @@
+-- Calls made from the owner console
+
+CREATE TABLE IF NOT EXISTS core_owner_console_call
+(
+    id uuid NOT NULL,
+    "ownerId" uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    "statusCode" integer NOT NULL,
+    "durationMs" integer NOT NULL,
+    "errorMessage" character varying COLLATE pg_catalog."default",
+    "dataShared" boolean NOT NULL DEFAULT false,
+    "calledAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_owner_console_call_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_console_ownerId_fkey" FOREIGN KEY ("ownerId")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_console_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_console_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
@@ -0,0 +1,28 @@
+-- MQTT topics bridged to twin endpoints
+
+CREATE TABLE IF NOT EXISTS core_twin_mqtt_binding
+(
+    id uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    "subscribeTopic" character varying COLLATE pg_catalog."default" NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    "publishTopic" character varying COLLATE pg_catalog."default",
+    qos integer NOT NULL DEFAULT 0,
+    "isActive" boolean NOT NULL DEFAULT true,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    "deletedAt" timestamp(6) with time zone,
+    "deletedBy" uuid,
+    CONSTRAINT core_twin_mqtt_binding_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_mqtt_binding_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_mqtt_binding_createdBy_fkey" FOREIGN KEY ("createdBy")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
@@ -0,0 +1,45 @@
+-- Telemetry fields, per-model retention and the partitioned telemetry table
+
+ALTER TABLE core_model
+    ADD COLUMN IF NOT EXISTS "telemetryRetentionDays" integer;
+
+CREATE TABLE IF NOT EXISTS core_model_telemetry_field
+(
+    id uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    name character varying COLLATE pg_catalog."default" NOT NULL,
+    "jsonPath" character varying COLLATE pg_catalog."default" NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default",
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    CONSTRAINT core_model_telemetry_field_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_telemetry_field_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+-- Monthly partitions are created and dropped by the telemetry retention task, the default partition catches the rest.
+-- The table is new here, so the default partition starts empty. Rows that reach it later are moved out by the
+-- retention task before it creates the partition for their month
+CREATE TABLE IF NOT EXISTS core_twin_telemetry
+(
+    id uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    "fieldName" character varying COLLATE pg_catalog."default" NOT NULL,
+    value double precision NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    "recordedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_twin_telemetry_pkey PRIMARY KEY (id, "recordedAt"),
+    CONSTRAINT "core_telemetry_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+) PARTITION BY RANGE ("recordedAt");
+
+CREATE TABLE IF NOT EXISTS core_twin_telemetry_default PARTITION OF core_twin_telemetry DEFAULT;
+
+CREATE INDEX IF NOT EXISTS core_twin_telemetry_lookup_idx
+    ON core_twin_telemetry ("twinId", "fieldName", "recordedAt");
@@ -0,0 +1,18 @@
+-- Desired and reported twin state
+
+CREATE TABLE IF NOT EXISTS core_twin_shadow
+(
+    "twinId" uuid NOT NULL,
+    desired jsonb NOT NULL DEFAULT '{}'::jsonb,
+    reported jsonb NOT NULL DEFAULT '{}'::jsonb,
+    version integer NOT NULL DEFAULT 0,
+    "desiredUpdatedAt" timestamp(6) with time zone,
+    "reportedUpdatedAt" timestamp(6) with time zone,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_twin_shadow_pkey PRIMARY KEY ("twinId"),
+    CONSTRAINT "core_shadow_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
@@ -0,0 +1,31 @@
+-- Cron schedules that call twin endpoints
+
+CREATE TABLE IF NOT EXISTS core_twin_schedule
+(
+    id uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    name character varying COLLATE pg_catalog."default",
+    "cronExpression" character varying COLLATE pg_catalog."default" NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    body jsonb,
+    "endsAt" timestamp(6) with time zone,
+    "nextRunAt" timestamp(6) with time zone,
+    "lastRunAt" timestamp(6) with time zone,
+    "isActive" boolean NOT NULL DEFAULT true,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    "deletedAt" timestamp(6) with time zone,
+    "deletedBy" uuid,
+    CONSTRAINT core_twin_schedule_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_schedule_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_schedule_createdBy_fkey" FOREIGN KEY ("createdBy")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
@@ -0,0 +1,44 @@
+-- Pipelines and their steps
+
+CREATE TABLE IF NOT EXISTS core_pipeline
+(
+    id uuid NOT NULL,
+    name character varying COLLATE pg_catalog."default" NOT NULL,
+    description character varying COLLATE pg_catalog."default",
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    "deletedAt" timestamp(6) with time zone,
+    "deletedBy" uuid,
+    CONSTRAINT core_pipeline_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_pipeline_createdBy_fkey" FOREIGN KEY ("createdBy")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+-- Steps run in position order, mappings may only read from the pipeline input or earlier steps
+CREATE TABLE IF NOT EXISTS core_pipeline_step
+(
+    id uuid NOT NULL,
+    "pipelineId" uuid NOT NULL,
+    "stepKey" character varying COLLATE pg_catalog."default" NOT NULL,
+    "position" integer NOT NULL,
+    "twinId" uuid NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    "endPoint" character varying COLLATE pg_catalog."default" NOT NULL,
+    body jsonb,
+    mappings jsonb NOT NULL DEFAULT '[]'::jsonb,
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_pipeline_step_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_pipeline_step_key_unique" UNIQUE ("pipelineId", "stepKey"),
+    CONSTRAINT "core_pipeline_step_pipelineId_fkey" FOREIGN KEY ("pipelineId")
+        REFERENCES core_pipeline (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_pipeline_step_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
@@ -0,0 +1,23 @@
+-- OpenAPI documents attached to models
+
+-- One OpenAPI 3 document per model entry point, uploaded by the owner or fetched from the component at publish
+CREATE TABLE IF NOT EXISTS core_model_openapi
+(
+    id uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    "componentAlias" character varying COLLATE pg_catalog."default",
+    document jsonb NOT NULL,
+    source character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'upload',
+    "createdAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "createdBy" uuid NOT NULL,
+    "updatedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedBy" uuid,
+    CONSTRAINT core_model_openapi_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_model_openapi_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS core_model_openapi_component_idx
+    ON core_model_openapi ("modelId", COALESCE("componentAlias", ''));
@@ -0,0 +1,36 @@
+-- Twin blocks after repeated policy violations
+
+-- Every block and unblock of a twin, "decidedBy" is null when the twin was blocked automatically after "blockAfter" violations
+CREATE TABLE IF NOT EXISTS core_policy_block
+(
+    id uuid NOT NULL,
+    "userId" uuid NOT NULL,
+    "modelId" uuid NOT NULL,
+    "policyId" uuid NOT NULL,
+    "twinId" uuid NOT NULL,
+    decision character varying COLLATE pg_catalog."default" NOT NULL,
+    "violationCount" integer NOT NULL DEFAULT 0,
+    reason character varying COLLATE pg_catalog."default",
+    "decidedBy" uuid,
+    "decidedAt" timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT core_policy_block_pkey PRIMARY KEY (id),
+    CONSTRAINT "core_policy_block_userId_fkey" FOREIGN KEY ("userId")
+        REFERENCES core_user (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_policy_block_modelId_fkey" FOREIGN KEY ("modelId")
+        REFERENCES core_model (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_policy_block_policyId_fkey" FOREIGN KEY ("policyId")
+        REFERENCES core_policy (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT "core_policy_block_twinId_fkey" FOREIGN KEY ("twinId")
+        REFERENCES core_twin (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS core_policy_block_twin_idx
+    ON core_policy_block ("twinId", "decidedAt" DESC);
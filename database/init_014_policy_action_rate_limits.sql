@@ -0,0 +1,7 @@
+-- Rate limits of policy actions, token_bucket or sliding_window, requests per rateLimitPeriod (second or minute)
+
+ALTER TABLE core_policy_action
+    ADD COLUMN IF NOT EXISTS "rateLimitAlgorithm" character varying COLLATE pg_catalog."default",
+    ADD COLUMN IF NOT EXISTS "rateLimitRequests" integer,
+    ADD COLUMN IF NOT EXISTS "rateLimitPeriod" character varying COLLATE pg_catalog."default",
+    ADD COLUMN IF NOT EXISTS "rateLimitBurst" integer;
@@ -0,0 +1,7 @@
+-- Validity windows of policy actions and the reason a call was refused
+
+ALTER TABLE core_policy_action
+    ADD COLUMN IF NOT EXISTS validity jsonb;
+
+ALTER TABLE core_policy_violation
+    ADD COLUMN IF NOT EXISTS reason character varying COLLATE pg_catalog."default";
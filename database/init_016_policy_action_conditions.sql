@@ -0,0 +1,7 @@
+-- Policy action conditions and the user attributes they can refer to
+
+ALTER TABLE core_policy_action
+    ADD COLUMN IF NOT EXISTS conditions jsonb;
+
+ALTER TABLE core_user
+    ADD COLUMN IF NOT EXISTS attributes jsonb NOT NULL DEFAULT '{}'::jsonb;
@@ -17,6 +17,8 @@ pub struct Model {
     pub is_published: bool,
     #[sea_orm(column_name = "enableDataSharing")]
     pub enable_data_sharing: bool,
+    #[sea_orm(column_name = "telemetryRetentionDays")]
+    pub telemetry_retention_days: Option<i32>,
     #[sea_orm(column_name = "createdAt")]
     pub created_at: DateTimeWithTimeZone,
     #[sea_orm(column_name = "createdBy")]
@@ -2,7 +2,7 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "core_policy_action")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
@@ -18,6 +18,38 @@ pub struct Model {
     pub action_count: i32,
     #[sea_orm(column_name = "resetFrequencyId")]
     pub reset_frequency_id: i32,
+    #[sea_orm(column_name = "maxConcurrentStreams")]
+    pub max_concurrent_streams: Option<i32>,
+    #[sea_orm(column_name = "maxStreamDuration")]
+    pub max_stream_duration: Option<i32>,
+    #[sea_orm(column_name = "isCacheable")]
+    pub is_cacheable: bool,
+    #[sea_orm(column_name = "cacheTtl")]
+    pub cache_ttl: Option<i32>,
+    #[sea_orm(column_name = "cacheScope")]
+    pub cache_scope: String,
+    #[sea_orm(column_name = "cacheHitsCount")]
+    pub cache_hits_count: bool,
+    #[sea_orm(column_name = "requestSchema", column_type = "JsonBinary", nullable)]
+    pub request_schema: Option<Json>,
+    #[sea_orm(column_name = "responseSchema", column_type = "JsonBinary", nullable)]
+    pub response_schema: Option<Json>,
+    #[sea_orm(column_name = "responseValidation")]
+    pub response_validation: String,
+    #[sea_orm(column_name = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[sea_orm(column_name = "rateLimitAlgorithm")]
+    pub rate_limit_algorithm: Option<String>,
+    #[sea_orm(column_name = "rateLimitRequests")]
+    pub rate_limit_requests: Option<i32>,
+    #[sea_orm(column_name = "rateLimitPeriod")]
+    pub rate_limit_period: Option<String>,
+    #[sea_orm(column_name = "rateLimitBurst")]
+    pub rate_limit_burst: Option<i32>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub validity: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub conditions: Option<Json>,
     #[sea_orm(column_name = "createdAt")]
     pub created_at: DateTimeWithTimeZone,
     #[sea_orm(column_name = "createdBy")]
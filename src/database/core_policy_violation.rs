@@ -17,6 +17,7 @@ pub struct Model {
     pub action_id: Uuid,
     #[sea_orm(column_name = "twinId")]
     pub twin_id: Uuid,
+    pub reason: Option<String>,
     #[sea_orm(column_name = "violatedAt")]
     pub violated_at: DateTimeWithTimeZone,
 }
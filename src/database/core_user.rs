@@ -29,6 +29,8 @@ pub struct Model {
     pub deleted_at: Option<DateTimeWithTimeZone>,
     #[sea_orm(column_name = "deletedBy")]
     pub deleted_by: Option<Uuid>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub attributes: Json,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -5,14 +5,24 @@ pub mod prelude;
 pub mod core_action_reset_frequency;
 pub mod core_model;
 pub mod core_model_component;
+pub mod core_model_openapi;
+pub mod core_model_telemetry_field;
 pub mod core_model_type;
+pub mod core_owner_console_call;
+pub mod core_pipeline;
+pub mod core_pipeline_step;
 pub mod core_policy;
 pub mod core_policy_action;
+pub mod core_policy_block;
 pub mod core_policy_violation;
 pub mod core_role;
 pub mod core_shared_model_data;
 pub mod core_twin;
 pub mod core_twin_component;
+pub mod core_twin_mqtt_binding;
+pub mod core_twin_schedule;
+pub mod core_twin_shadow;
 pub mod core_twin_status;
+pub mod core_twin_telemetry;
 pub mod core_user;
 pub mod core_user_subscription;
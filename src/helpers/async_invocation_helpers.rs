@@ -0,0 +1,163 @@
+use axum::http::{StatusCode, Uri};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    database::core_twin,
+    helpers::twin_invocation_helpers::{invoke_twin_endpoint, TwinRequest},
+    utilities::{
+        app_error::AppError,
+        redis_connection_wrapper::RedisConnWrapper,
+        redis_helper::{get_token_from_redis, store_token_in_redis_for_seconds},
+        request_trace::{current_request_id, with_request_id},
+        upstream_client::UpstreamClient,
+        webhook_client::WebhookClient,
+    },
+};
+
+const DEFAULT_INVOCATION_TTL_SECS: usize = 86400;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InvocationRecord {
+    pub id: Uuid,
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    pub endpoint: String,
+    //pending, completed or failed
+    pub status: String,
+    #[serde(rename = "statusCode", skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "completedAt", skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+pub struct AsyncInvocation {
+    pub client: UpstreamClient,
+    //None when WEBHOOK_SECRET is not set, the invocation can then only be polled
+    pub webhook_client: Option<WebhookClient>,
+    pub webhook_url: Option<Uri>,
+}
+
+//Record the invocation as pending and run it in the background, the caller only gets the id back
+pub async fn start_async_invocation(
+    db: DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    invocation: AsyncInvocation,
+    user_id: Uuid,
+    twin: core_twin::Model,
+    request: TwinRequest,
+) -> Result<InvocationRecord, AppError> {
+    let record = InvocationRecord {
+        id: Uuid::new_v4(),
+        twin_id: twin.id,
+        user_id,
+        endpoint: request.endpoint_id.clone(),
+        status: "pending".to_string(),
+        status_code: None,
+        body: None,
+        error: None,
+        created_at: Utc::now(),
+        completed_at: None,
+    };
+    store_invocation(redis_url.clone(), &record).await?;
+
+    let pending = record.clone();
+    //The background run stays part of the caller's trace and keeps its request id
+    let span = tracing::info_span!("async_invocation", invocation_id = %record.id);
+    let request_id = current_request_id().unwrap_or_default();
+    let run = async move {
+        let AsyncInvocation {
+            client,
+            webhook_client,
+            webhook_url,
+        } = invocation;
+
+        let result =
+            invoke_twin_endpoint(&db, redis_url.clone(), &client, user_id, &twin, request).await;
+
+        let mut record = pending;
+        record.completed_at = Some(Utc::now());
+        match result {
+            Ok(response) => {
+                record.status = "completed".to_string();
+                record.status_code = Some(response.status.as_u16());
+                record.body = Some(
+                    serde_json::from_slice::<Value>(&response.body).unwrap_or_else(|_| {
+                        Value::String(String::from_utf8_lossy(&response.body).to_string())
+                    }),
+                );
+            }
+            Err(error) => {
+                record.status = "failed".to_string();
+                record.status_code = Some(error.code().as_u16());
+                record.error = Some(error.message().to_string());
+            }
+        }
+
+        if let Err(error) = store_invocation(redis_url, &record).await {
+            eprintln!("Error storing invocation {}: {:?}", record.id, error);
+        }
+
+        if let (Some(webhook_client), Some(webhook_url)) = (webhook_client, webhook_url) {
+            let payload = serde_json::to_vec(&record).unwrap_or_default();
+            let _delivered = webhook_client.deliver(&webhook_url, payload).await;
+        }
+    };
+    tokio::spawn(with_request_id(request_id, run.instrument(span)));
+
+    Ok(record)
+}
+
+//Invocations are only visible to the user who started them
+pub async fn get_invocation(
+    redis_url: RedisConnWrapper,
+    invocation_id: Uuid,
+    user_id: Uuid,
+) -> Result<InvocationRecord, AppError> {
+    let token = get_token_from_redis(redis_url, invocation_key(invocation_id)).await?;
+
+    let record = serde_json::from_str::<InvocationRecord>(&token)
+        .ok()
+        .filter(|record| record.user_id == user_id)
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Invocation not found"))?;
+
+    Ok(record)
+}
+
+async fn store_invocation(
+    redis_url: RedisConnWrapper,
+    record: &InvocationRecord,
+) -> Result<(), AppError> {
+    let value = serde_json::to_string(record).map_err(|error| {
+        eprintln!("Error encoding invocation: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again",
+        )
+    })?;
+
+    let ttl = std::env::var("INVOCATION_RESULT_TTL_SECS")
+        .ok()
+        .and_then(|ttl| ttl.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_INVOCATION_TTL_SECS);
+
+    store_token_in_redis_for_seconds(redis_url, invocation_key(record.id), value, ttl).await
+}
+
+fn invocation_key(invocation_id: Uuid) -> String {
+    let mut store_key = "Invocations:".to_string();
+    store_key += &invocation_id.to_string();
+    store_key
+}
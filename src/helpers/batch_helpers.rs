@@ -0,0 +1,32 @@
+use std::sync::{
+    atomic::{AtomicU16, Ordering},
+    Arc,
+};
+
+use axum::http::StatusCode;
+
+//Shared between the items of one batch. Only an exhausted quota or validity window stops the
+//batch, a failed condition or a rate limit only concerns the item that hit it
+#[derive(Clone, Default)]
+pub struct BatchGate {
+    denied_status: Arc<AtomicU16>,
+}
+
+impl BatchGate {
+    pub fn denied_status(&self) -> Option<u16> {
+        match self.denied_status.load(Ordering::SeqCst) {
+            0 => None,
+            status => Some(status),
+        }
+    }
+
+    pub fn record(&self, status: u16, failed: bool) {
+        if failed && stops_batch(status) {
+            self.denied_status.store(status, Ordering::SeqCst);
+        }
+    }
+}
+
+pub fn stops_batch(status: u16) -> bool {
+    status == StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS.as_u16()
+}
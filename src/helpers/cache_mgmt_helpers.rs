@@ -0,0 +1,130 @@
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::body::Bytes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    database::{core_policy_action, core_twin},
+    helpers::policy_mgmt_helpers::policy_action_key,
+    utilities::{
+        app_error::AppError,
+        redis_connection_wrapper::RedisConnWrapper,
+        redis_helper::{get_token_from_redis, store_token_in_redis_for_seconds},
+        upstream_client::UpstreamResponse,
+    },
+};
+
+pub const CACHE_STATUS_HEADER: &str = "x-cache";
+
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    status: u16,
+    #[serde(rename = "contentType")]
+    content_type: Option<String>,
+    body: String,
+}
+
+//Identical requests share a key: same verb, endpoint and body hash, scoped to the twin or the whole model
+pub fn cache_key(
+    policy_action: &core_policy_action::Model,
+    twin: &core_twin::Model,
+    body: &Bytes,
+) -> String {
+    let mut store_key = "Cache:".to_string();
+    if policy_action.cache_scope == "model" {
+        store_key += "Models:";
+        store_key += &twin.model_id.to_string();
+    } else {
+        store_key += "Twins:";
+        store_key += &twin.id.to_string();
+    }
+    store_key += ":";
+    store_key += &policy_action.end_point_verb;
+    store_key += ":";
+    store_key += &policy_action_key(
+        policy_action.component_alias.as_deref(),
+        &policy_action.end_point,
+    );
+    store_key += ":";
+    store_key += &format!("{:x}", Sha256::digest(body));
+
+    store_key
+}
+
+pub async fn get_cached_response(
+    redis_url: RedisConnWrapper,
+    key: String,
+) -> Result<Option<UpstreamResponse>, AppError> {
+    let token = get_token_from_redis(redis_url, key).await?;
+    if token.is_empty() {
+        return Ok(None);
+    }
+
+    //A cache entry we cannot decode is treated as a miss
+    let cached = match serde_json::from_str::<CachedResponse>(&token) {
+        Ok(cached) => cached,
+        Err(error) => {
+            eprintln!("Error decoding cached response: {:?}", error);
+            return Ok(None);
+        }
+    };
+
+    let body = match STANDARD.decode(cached.body) {
+        Ok(body) => body,
+        Err(error) => {
+            eprintln!("Error decoding cached response body: {:?}", error);
+            return Ok(None);
+        }
+    };
+
+    let mut headers = HeaderMap::new();
+    if let Some(content_type) = cached
+        .content_type
+        .and_then(|content_type| HeaderValue::from_str(&content_type).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+
+    Ok(Some(UpstreamResponse {
+        status: StatusCode::from_u16(cached.status).unwrap_or(StatusCode::OK),
+        headers,
+        body: Bytes::from(body),
+    }))
+}
+
+pub async fn store_cached_response(
+    redis_url: RedisConnWrapper,
+    key: String,
+    response: &UpstreamResponse,
+    ttl_seconds: i32,
+) -> Result<(), AppError> {
+    //Only successful responses are worth replaying
+    if !response.status.is_success() || ttl_seconds <= 0 {
+        return Ok(());
+    }
+
+    let cached = CachedResponse {
+        status: response.status.as_u16(),
+        content_type: response
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(|content_type| content_type.to_string()),
+        body: STANDARD.encode(&response.body),
+    };
+
+    let value = serde_json::to_string(&cached).map_err(|error| {
+        eprintln!("Error encoding cached response: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again",
+        )
+    })?;
+
+    store_token_in_redis_for_seconds(redis_url, key, value, ttl_seconds as usize).await
+}
+
+pub fn set_cache_status(headers: &mut HeaderMap, status: &'static str) {
+    headers.insert(CACHE_STATUS_HEADER, HeaderValue::from_static(status));
+}
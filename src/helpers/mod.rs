@@ -1,2 +1,18 @@
+pub mod async_invocation_helpers;
+pub mod batch_helpers;
+pub mod cache_mgmt_helpers;
 pub mod model_mgmt_helpers;
+pub mod mqtt_bridge_helpers;
+pub mod openapi_helpers;
+pub mod pipeline_helpers;
+pub mod policy_block_helpers;
+pub mod policy_condition_helpers;
+pub mod policy_document_helpers;
 pub mod policy_mgmt_helpers;
+pub mod policy_odrl_helpers;
+pub mod policy_validity_helpers;
+pub mod schedule_helpers;
+pub mod schema_validation_helpers;
+pub mod shadow_helpers;
+pub mod telemetry_helpers;
+pub mod twin_invocation_helpers;
@@ -4,12 +4,14 @@ use uuid::Uuid;
 
 use crate::{
     database::{core_model, core_model_component, core_twin, core_user},
+    helpers::openapi_helpers::fetch_component_documents,
     queries::{model_queries, twin_queries},
     routes::twins::create_twin_extractor::ValidateCreateTwin,
     utilities::{
         app_error::AppError,
         docker_helper::{create_docker_model, remove_docker_model, stop_docker_model},
         redis_connection_wrapper::RedisConnWrapper,
+        twin_events::publish_twin_status,
     },
 };
 
@@ -20,6 +22,17 @@ pub async fn publish(
     model_components: Vec<core_model_component::Model>,
     redis_url: RedisConnWrapper,
 ) -> Result<Json<String>, AppError> {
+    //The default entry point plus every exposed component may serve an OpenAPI document
+    let component_aliases = std::iter::once(None)
+        .chain(
+            model_components
+                .iter()
+                .filter(|component| component.is_exposed)
+                .filter_map(|component| component.component_alias.clone())
+                .map(Some),
+        )
+        .collect::<Vec<_>>();
+
     let (response, twin) =
         create_twin_infrastructure(&db, &user, &model, model_components, redis_url.clone()).await?;
     let mut model = model.clone().into_active_model();
@@ -29,7 +42,8 @@ pub async fn publish(
 
     let mut twin = twin.into_active_model();
     twin.twin_status_id = Set(2); //Set twin status to "Started"
-    twin_queries::save_active_coretwin(&db, twin).await?;
+    let twin = twin_queries::save_active_coretwin(db, twin).await?;
+    fetch_component_documents(db.clone(), twin, component_aliases, user.id);
 
     //Check if response is equal to "Docker containers removed successfully"
     if response.contains("Containers created successfully") {
@@ -63,6 +77,7 @@ pub async fn unpublish(
                     })?;
 
             let _delete_msg = twin_queries::delete_twin(&db, twin.id.clone(), user.id).await?;
+            publish_twin_status(redis_url.clone(), twin.id, "Deleted", None);
 
             // dbg!(delete_msg);
 
@@ -146,7 +161,11 @@ async fn create_twin_infrastructure(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Error creating docker model",
                 )
+            })
+            .inspect_err(|error| {
+                publish_twin_status(redis_url.clone(), twin.id, "Failed", Some(error))
             })?;
+            publish_twin_status(redis_url.clone(), twin.id, "Started", None);
 
             create_msg
 
@@ -0,0 +1,359 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use hyper::body::Bytes;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS};
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use tokio::sync::{RwLock, Semaphore};
+use uuid::Uuid;
+
+use crate::{
+    database::core_twin_mqtt_binding,
+    helpers::twin_invocation_helpers::{get_running_user_twin, invoke_twin_endpoint, TwinRequest},
+    queries::mqtt_binding_queries,
+    utilities::{
+        app_error::AppError, redis_connection_wrapper::RedisConnWrapper,
+        upstream_client::UpstreamClient,
+    },
+};
+
+//Forwards MQTT messages to twin endpoints and publishes the twin's answer back, disabled unless MQTT_HOST is set
+#[derive(Clone)]
+pub struct MqttBridge {
+    inner: Option<Arc<MqttBridgeInner>>,
+}
+
+struct MqttBridgeInner {
+    client: AsyncClient,
+    bindings: RwLock<Vec<core_twin_mqtt_binding::Model>>,
+    db: DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    upstream: UpstreamClient,
+    //Bounds the twin calls in flight, messages arriving while it is exhausted are dropped
+    forwarding: Arc<Semaphore>,
+}
+
+const DEFAULT_MQTT_MAX_INFLIGHT: usize = 32;
+
+impl MqttBridge {
+    pub async fn start(
+        db: DatabaseConnection,
+        redis_url: RedisConnWrapper,
+        upstream: UpstreamClient,
+    ) -> Self {
+        let host = match std::env::var("MQTT_HOST") {
+            Ok(host) if !host.trim().is_empty() => host,
+            _ => return Self { inner: None },
+        };
+        let port = std::env::var("MQTT_PORT")
+            .ok()
+            .and_then(|port| port.trim().parse::<u16>().ok())
+            .unwrap_or(1883);
+        let client_id =
+            std::env::var("MQTT_CLIENT_ID").unwrap_or_else(|_| "digital_twin_mw".to_string());
+
+        let mut options = MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let (Ok(username), Ok(password)) =
+            (std::env::var("MQTT_USERNAME"), std::env::var("MQTT_PASSWORD"))
+        {
+            options.set_credentials(username, password);
+        }
+
+        let max_inflight = std::env::var("MQTT_MAX_INFLIGHT")
+            .ok()
+            .and_then(|max_inflight| max_inflight.trim().parse::<usize>().ok())
+            .filter(|max_inflight| *max_inflight > 0)
+            .unwrap_or(DEFAULT_MQTT_MAX_INFLIGHT);
+
+        let (client, eventloop) = AsyncClient::new(options, 100);
+        let inner = Arc::new(MqttBridgeInner {
+            client,
+            bindings: RwLock::new(vec![]),
+            db,
+            redis_url,
+            upstream,
+            forwarding: Arc::new(Semaphore::new(max_inflight)),
+        });
+
+        //Subscriptions are made once the broker acknowledges the connection
+        match mqtt_binding_queries::get_active_mqtt_bindings(&inner.db).await {
+            Ok(bindings) => *inner.bindings.write().await = bindings,
+            Err(error) => eprintln!("Error loading mqtt bindings: {:?}", error),
+        }
+
+        tokio::spawn(run_event_loop(inner.clone(), eventloop));
+
+        Self { inner: Some(inner) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    //Pick up bindings created or removed through the api, adjusting broker subscriptions to match
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return Ok(()),
+        };
+
+        let bindings = mqtt_binding_queries::get_active_mqtt_bindings(&inner.db).await?;
+        let old_topics = subscribed_topics(&inner.bindings.read().await);
+        let new_topics = subscribed_topics(&bindings);
+        *inner.bindings.write().await = bindings;
+
+        for topic in old_topics.keys().filter(|topic| !new_topics.contains_key(*topic)) {
+            if let Err(error) = inner.client.unsubscribe(topic.clone()).await {
+                eprintln!("Error unsubscribing from {}: {:?}", topic, error);
+            }
+        }
+        for (topic, qos) in new_topics {
+            if old_topics.get(&topic) != Some(&qos) {
+                subscribe(&inner.client, topic, qos).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn run_event_loop(inner: Arc<MqttBridgeInner>, mut eventloop: EventLoop) {
+    loop {
+        match eventloop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                let topics = subscribed_topics(&inner.bindings.read().await);
+                for (topic, qos) in topics {
+                    subscribe(&inner.client, topic, qos).await;
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let bindings = inner
+                    .bindings
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|binding| rumqttc::matches(&publish.topic, &binding.subscribe_topic))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                for binding in bindings {
+                    let Ok(permit) = inner.forwarding.clone().try_acquire_owned() else {
+                        eprintln!(
+                            "Dropping mqtt message on {} for binding {}, too many calls in flight",
+                            publish.topic, binding.id
+                        );
+                        continue;
+                    };
+
+                    let inner = inner.clone();
+                    let payload = publish.payload.clone();
+                    tokio::spawn(async move {
+                        forward_message(inner, binding, payload).await;
+                        drop(permit);
+                    });
+                }
+            }
+            Ok(_) => {}
+            Err(error) => {
+                //The event loop reconnects on the next poll, back off so a dead broker is not hammered
+                eprintln!("Error polling mqtt broker: {:?}", error);
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+//Each message is an ordinary twin call made on behalf of the binding's owner, so policy accounting applies
+async fn forward_message(
+    inner: Arc<MqttBridgeInner>,
+    binding: core_twin_mqtt_binding::Model,
+    payload: Bytes,
+) {
+    let result = async {
+        let twin = get_running_user_twin(&inner.db, binding.twin_id, binding.created_by).await?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+
+        invoke_twin_endpoint(
+            &inner.db,
+            inner.redis_url.clone(),
+            &inner.upstream,
+            binding.created_by,
+            &twin,
+            TwinRequest {
+                component_alias: binding.component_alias.clone(),
+                endpoint_id: binding.end_point.clone(),
+                headers,
+                body: payload,
+            },
+        )
+        .await
+    }
+    .await;
+
+    let publish_topic = match (&result, &binding.publish_topic) {
+        (Ok(_), Some(publish_topic)) => publish_topic.clone(),
+        (Err(_), Some(publish_topic)) => format!("{}/error", publish_topic),
+        (_, None) => {
+            if let Err(error) = result {
+                eprintln!("Error forwarding mqtt message for binding {}: {:?}", binding.id, error);
+            }
+            return;
+        }
+    };
+
+    let payload = match result {
+        Ok(response) if response.status.is_success() => response.body.to_vec(),
+        Ok(response) => json!({
+            "status": response.status.as_u16(),
+            "error": String::from_utf8_lossy(&response.body),
+        })
+        .to_string()
+        .into_bytes(),
+        Err(error) => json!({
+            "status": error.code().as_u16(),
+            "error": error.message(),
+        })
+        .to_string()
+        .into_bytes(),
+    };
+
+    if let Err(error) = inner
+        .client
+        .publish(publish_topic.clone(), to_qos(binding.qos), false, payload)
+        .await
+    {
+        eprintln!("Error publishing to {}: {:?}", publish_topic, error);
+    }
+}
+
+//One subscription per topic filter, at the highest qos any binding on it asked for
+fn subscribed_topics(bindings: &[core_twin_mqtt_binding::Model]) -> HashMap<String, i32> {
+    let mut topics = HashMap::new();
+    for binding in bindings {
+        let qos = topics.entry(binding.subscribe_topic.clone()).or_insert(0);
+        *qos = binding.qos.max(*qos);
+    }
+    topics
+}
+
+async fn subscribe(client: &AsyncClient, topic: String, qos: i32) {
+    if let Err(error) = client.subscribe(topic.clone(), to_qos(qos)).await {
+        eprintln!("Error subscribing to {}: {:?}", topic, error);
+    }
+}
+
+fn to_qos(qos: i32) -> QoS {
+    match qos {
+        2 => QoS::ExactlyOnce,
+        1 => QoS::AtLeastOnce,
+        _ => QoS::AtMostOnce,
+    }
+}
+
+//Topics of a binding, as far as routing messages between bindings is concerned
+pub struct BindingTopics {
+    pub subscribe_topic: String,
+    pub publish_topic: Option<String>,
+}
+
+impl BindingTopics {
+    //Answers go to the publish topic, failures to publish_topic/error
+    fn output_topics(&self) -> Vec<String> {
+        match &self.publish_topic {
+            Some(publish_topic) => vec![publish_topic.clone(), format!("{}/error", publish_topic)],
+            None => vec![],
+        }
+    }
+
+    fn feeds(&self, other: &BindingTopics) -> bool {
+        self.output_topics()
+            .iter()
+            .any(|topic| rumqttc::matches(topic, &other.subscribe_topic))
+    }
+}
+
+//Every user binds topics under their own namespace on the shared broker
+pub fn binding_topic_prefix(user_id: Uuid) -> String {
+    format!("users/{}/", user_id)
+}
+
+//Reject topics the broker would refuse, topics outside the user's namespace,
+//and publish topics that would feed the binding back its own output through the user's other bindings
+pub fn validate_binding_topics(
+    user_id: Uuid,
+    binding: &BindingTopics,
+    existing_bindings: &[BindingTopics],
+) -> Result<(), AppError> {
+    let prefix = binding_topic_prefix(user_id);
+
+    if !rumqttc::valid_filter(&binding.subscribe_topic) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "subscribeTopic is not a valid MQTT topic filter",
+        ));
+    }
+    if !binding.subscribe_topic.starts_with(&prefix) || binding.subscribe_topic == prefix {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!("subscribeTopic must be under {}", prefix),
+        ));
+    }
+
+    if let Some(publish_topic) = &binding.publish_topic {
+        if !rumqttc::valid_topic(publish_topic) {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "publishTopic is not a valid MQTT topic",
+            ));
+        }
+        if !publish_topic.starts_with(&prefix) || *publish_topic == prefix {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("publishTopic must be under {}", prefix),
+            ));
+        }
+    }
+
+    if binding.feeds(binding) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "publishTopic must not match subscribeTopic",
+        ));
+    }
+    if creates_cycle(binding, existing_bindings) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "publishTopic would forward messages back to this binding through other bindings",
+        ));
+    }
+
+    Ok(())
+}
+
+//Follows the bindings the new one publishes to until one of them publishes back to it
+fn creates_cycle(binding: &BindingTopics, existing_bindings: &[BindingTopics]) -> bool {
+    let mut visited = vec![false; existing_bindings.len()];
+    let mut pending = vec![binding];
+
+    while let Some(current) = pending.pop() {
+        for (index, next) in existing_bindings.iter().enumerate() {
+            if visited[index] || !current.feeds(next) {
+                continue;
+            }
+            if next.feeds(binding) {
+                return true;
+            }
+            visited[index] = true;
+            pending.push(next);
+        }
+    }
+
+    false
+}
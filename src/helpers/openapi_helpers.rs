@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use axum::http::{StatusCode, Uri};
+use hyper::Client;
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_model_openapi, core_twin},
+    helpers::twin_invocation_helpers::resolve_twin_authority,
+    queries::openapi_queries::{self, OPENAPI_SOURCE_COMPONENT},
+    routes::policys::create_policy_action_extractor::ValidateCreatePolicyAction,
+    utilities::app_error::AppError,
+};
+
+const DEFAULT_OPENAPI_SPEC_PATH: &str = "openapi.json";
+//Containers are still starting right after publish, so the fetch is retried for a little while
+const OPENAPI_FETCH_ATTEMPTS: u32 = 5;
+const OPENAPI_FETCH_DELAY: Duration = Duration::from_secs(3);
+const OPENAPI_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+//Bounds $ref inlining so recursive schemas can't loop forever
+const MAX_REF_DEPTH: usize = 32;
+
+const HTTP_VERBS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+//Same field names as a policy action in RequestPolicyValidated, so owners can edit and submit them
+#[derive(Serialize, Clone, Debug)]
+pub struct DraftPolicyAction {
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    #[serde(rename = "endPointVerb")]
+    pub end_point_verb: String,
+    pub description: String,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "requestSchema", skip_serializing_if = "Option::is_none")]
+    pub request_schema: Option<Value>,
+    #[serde(rename = "responseSchema", skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<Value>,
+}
+
+pub fn validate_openapi_document(document: &Value) -> Result<(), AppError> {
+    let is_openapi_3 = document
+        .get("openapi")
+        .and_then(Value::as_str)
+        .is_some_and(|version| version.starts_with("3."));
+    if !is_openapi_3 {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Only OpenAPI 3 documents are supported",
+        ));
+    }
+
+    if !document.get("paths").is_some_and(Value::is_object) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "OpenAPI document has no paths",
+        ));
+    }
+
+    Ok(())
+}
+
+//(path, verb, operation) for every operation in the document
+pub fn openapi_operations(document: &Value) -> Vec<(&str, &str, &Value)> {
+    let Some(paths) = document.get("paths").and_then(Value::as_object) else {
+        return vec![];
+    };
+
+    paths
+        .iter()
+        .flat_map(|(path, item)| {
+            HTTP_VERBS.iter().filter_map(move |verb| {
+                item.get(*verb)
+                    .map(|operation| (path.as_str(), *verb, operation))
+            })
+        })
+        .collect()
+}
+
+pub fn draft_policy_actions(document: &core_model_openapi::Model) -> Vec<DraftPolicyAction> {
+    openapi_operations(&document.document)
+        .into_iter()
+        .map(|(path, verb, operation)| {
+            let description = ["summary", "description", "operationId"]
+                .iter()
+                .find_map(|field| operation.get(*field).and_then(Value::as_str))
+                .map(|description| description.to_string())
+                .unwrap_or_else(|| format!("{} {}", verb.to_uppercase(), path));
+
+            let request_schema = operation
+                .pointer("/requestBody/content/application~1json/schema")
+                .map(|schema| resolve_refs(&document.document, schema, 0));
+
+            //The first 2xx JSON response describes what the twin answers with
+            let response_schema = operation
+                .get("responses")
+                .and_then(Value::as_object)
+                .and_then(|responses| {
+                    responses
+                        .iter()
+                        .filter(|(status, _)| status.starts_with('2'))
+                        .find_map(|(_, response)| {
+                            response.pointer("/content/application~1json/schema")
+                        })
+                })
+                .map(|schema| resolve_refs(&document.document, schema, 0));
+
+            DraftPolicyAction {
+                end_point: path.trim_start_matches('/').to_string(),
+                end_point_verb: verb.to_uppercase(),
+                description,
+                component_alias: document.component_alias.clone(),
+                request_schema,
+                response_schema,
+            }
+        })
+        .collect()
+}
+
+//Policy schemas are compiled on their own, so local $refs are inlined from the document
+fn resolve_refs(document: &Value, schema: &Value, depth: usize) -> Value {
+    if depth > MAX_REF_DEPTH {
+        return Value::Object(Default::default());
+    }
+
+    match schema {
+        Value::Object(fields) => {
+            if let Some(target) = fields
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|pointer| document.pointer(pointer))
+            {
+                return resolve_refs(document, target, depth + 1);
+            }
+
+            Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.clone(), resolve_refs(document, value, depth + 1)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| resolve_refs(document, item, depth + 1))
+                .collect(),
+        ),
+        _ => schema.clone(),
+    }
+}
+
+//Templated segments such as {id} match any value the owner puts in the policy
+pub fn path_matches(template: &str, end_point: &str) -> bool {
+    let template = template.trim_matches('/').to_lowercase();
+    let end_point = end_point.trim_matches('/').to_lowercase();
+    let template = template.split('/').collect::<Vec<_>>();
+    let end_point = end_point.split('/').collect::<Vec<_>>();
+
+    template.len() == end_point.len()
+        && template
+            .iter()
+            .zip(end_point.iter())
+            .all(|(template, segment)| {
+                (template.starts_with('{') && template.ends_with('}')) || template == segment
+            })
+}
+
+pub fn check_policy_actions(
+    documents: &[core_model_openapi::Model],
+    actions: &[ValidateCreatePolicyAction],
+) -> Result<(), AppError> {
+    let documents = documents
+        .iter()
+        .map(|document| (document.component_alias.as_deref(), &document.document))
+        .collect::<Vec<_>>();
+    check_actions_against_documents(&documents, actions)
+}
+
+//Actions on an entry point that has a document must name one of its operations
+pub fn check_actions_against_documents(
+    documents: &[(Option<&str>, &Value)],
+    actions: &[ValidateCreatePolicyAction],
+) -> Result<(), AppError> {
+    for action in actions {
+        let Some((_, document)) = documents
+            .iter()
+            .find(|(component_alias, _)| *component_alias == action.component_alias.as_deref())
+        else {
+            continue;
+        };
+
+        let end_point = action.end_point.as_deref().unwrap_or_default();
+        let verb = action
+            .end_point_verb
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase();
+        let exists = openapi_operations(document)
+            .into_iter()
+            .any(|(path, operation_verb, _)| {
+                operation_verb == verb && path_matches(path, end_point)
+            });
+
+        if !exists {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "{} {} is not an operation in the OpenAPI document{}",
+                    verb.to_uppercase(),
+                    end_point,
+                    action
+                        .component_alias
+                        .as_ref()
+                        .map(|alias| format!(" of component {}", alias))
+                        .unwrap_or_default()
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+//Try each entry point of the freshly published twin for a document, in the background.
+//Goes around the upstream client so failures while containers boot don't open the twin's breaker
+pub fn fetch_component_documents(
+    db: DatabaseConnection,
+    twin: core_twin::Model,
+    component_aliases: Vec<Option<String>>,
+    user_id: Uuid,
+) {
+    let spec_path = std::env::var("OPENAPI_SPEC_PATH")
+        .unwrap_or_else(|_| DEFAULT_OPENAPI_SPEC_PATH.to_string());
+
+    tokio::spawn(async move {
+        for component_alias in component_aliases {
+            let document =
+                match fetch_document(&db, &twin, component_alias.as_deref(), &spec_path).await {
+                    Some(document) => document,
+                    None => continue,
+                };
+
+            if let Err(error) = openapi_queries::save_openapi_document(
+                &db,
+                twin.model_id,
+                component_alias,
+                document,
+                OPENAPI_SOURCE_COMPONENT,
+                user_id,
+            )
+            .await
+            {
+                eprintln!("Error storing fetched openapi document: {:?}", error);
+            }
+        }
+    });
+}
+
+async fn fetch_document(
+    db: &DatabaseConnection,
+    twin: &core_twin::Model,
+    component_alias: Option<&str>,
+    spec_path: &str,
+) -> Option<Value> {
+    let authority = resolve_twin_authority(db, twin, component_alias)
+        .await
+        .ok()?;
+    let uri = Uri::try_from(format!(
+        "http://{}/{}",
+        authority,
+        spec_path.trim_start_matches('/')
+    ))
+    .ok()?;
+    let client = Client::new();
+
+    for attempt in 0..OPENAPI_FETCH_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(OPENAPI_FETCH_DELAY).await;
+        }
+
+        let response = tokio::time::timeout(OPENAPI_FETCH_TIMEOUT, client.get(uri.clone())).await;
+        match response {
+            Ok(Ok(response)) if response.status().is_success() => {
+                let body = hyper::body::to_bytes(response.into_body()).await.ok()?;
+                return serde_json::from_slice::<Value>(&body)
+                    .ok()
+                    .filter(|document| validate_openapi_document(document).is_ok());
+            }
+            //The component answers but has no document, retrying won't help
+            Ok(Ok(_)) => return None,
+            _ => continue,
+        }
+    }
+
+    None
+}
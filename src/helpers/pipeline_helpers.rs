@@ -0,0 +1,259 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use hyper::body::Bytes;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    database::core_pipeline_step,
+    helpers::twin_invocation_helpers::{get_running_user_twin, invoke_twin_endpoint, TwinRequest},
+    routes::pipelines::RequestPipelineStep,
+    utilities::{
+        app_error::AppError,
+        json_path::{parse_json_path, select, set},
+        redis_connection_wrapper::RedisConnWrapper,
+        upstream_client::UpstreamClient,
+    },
+};
+
+//Mappings read from this source to get at the body the pipeline was run with
+pub const PIPELINE_INPUT: &str = "input";
+
+const DEFAULT_MAX_PIPELINE_STEPS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PipelineMapping {
+    //A step key or "input"
+    pub from: String,
+    //Where to read in the source, the whole document by default
+    #[serde(default = "root_path")]
+    pub path: String,
+    //Where to write in this step's body, the whole body by default
+    #[serde(default = "root_path")]
+    pub to: String,
+}
+
+fn root_path() -> String {
+    "$".to_string()
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PipelineStepTrace {
+    pub key: String,
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    //succeeded, failed or skipped when a step it reads from did not succeed
+    pub outcome: String,
+    #[serde(rename = "statusCode", skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn validate_pipeline_steps(steps: &[RequestPipelineStep]) -> Result<(), AppError> {
+    let steps = steps
+        .iter()
+        .map(|step| (step.key.as_str(), step.mappings.as_slice()))
+        .collect::<Vec<_>>();
+    validate_step_graph(&steps)
+}
+
+//Steps are kept in the order given, so reading only from earlier steps keeps the graph acyclic
+pub fn validate_step_graph(steps: &[(&str, &[PipelineMapping])]) -> Result<(), AppError> {
+    let max_steps = std::env::var("MAX_PIPELINE_STEPS")
+        .ok()
+        .and_then(|max| max.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_PIPELINE_STEPS);
+
+    if steps.is_empty() {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Pipeline must contain at least one step",
+        ));
+    }
+    if steps.len() > max_steps {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Pipeline cannot contain more than {} steps", max_steps),
+        ));
+    }
+
+    let mut earlier_keys = HashSet::new();
+    for (key, mappings) in steps {
+        let key = key.trim();
+        if key.is_empty() || key == PIPELINE_INPUT {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Step key cannot be empty or {}", PIPELINE_INPUT),
+            ));
+        }
+        if earlier_keys.contains(key) {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Step key {} is used more than once", key),
+            ));
+        }
+
+        for mapping in mappings.iter() {
+            if mapping.from != PIPELINE_INPUT && !earlier_keys.contains(mapping.from.as_str()) {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "Step {} reads from {}, which is not an earlier step",
+                        key, mapping.from
+                    ),
+                ));
+            }
+            parse_json_path(&mapping.path)?;
+            parse_json_path(&mapping.to)?;
+        }
+
+        earlier_keys.insert(key);
+    }
+
+    Ok(())
+}
+
+//Run the steps in order as the given user, every call is charged against that twin's policy
+pub async fn run_pipeline(
+    db: &DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: &UpstreamClient,
+    user_id: Uuid,
+    steps: Vec<core_pipeline_step::Model>,
+    input: Value,
+) -> Vec<PipelineStepTrace> {
+    let mut outputs = HashMap::from([(PIPELINE_INPUT.to_string(), input)]);
+    let mut traces = Vec::with_capacity(steps.len());
+
+    for step in steps {
+        let mappings = serde_json::from_value::<Vec<PipelineMapping>>(step.mappings.clone())
+            .unwrap_or_default();
+
+        //A step whose sources did not all succeed is skipped rather than run with partial input
+        if let Some(missing) = mappings
+            .iter()
+            .find(|mapping| !outputs.contains_key(&mapping.from))
+        {
+            traces.push(PipelineStepTrace {
+                key: step.step_key,
+                twin_id: step.twin_id,
+                end_point: step.end_point,
+                outcome: "skipped".to_string(),
+                status_code: None,
+                duration_ms: 0,
+                input: None,
+                output: None,
+                error: Some(format!("Step {} did not succeed", missing.from)),
+            });
+            continue;
+        }
+
+        let started_at = Instant::now();
+        let mut trace = PipelineStepTrace {
+            key: step.step_key.clone(),
+            twin_id: step.twin_id,
+            end_point: step.end_point.clone(),
+            outcome: "failed".to_string(),
+            status_code: None,
+            duration_ms: 0,
+            input: None,
+            output: None,
+            error: None,
+        };
+
+        let result = async {
+            let body = build_step_body(step.body.clone(), &mappings, &outputs)?;
+            trace.input = body.clone();
+
+            let twin = get_running_user_twin(db, step.twin_id, user_id).await?;
+
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+
+            invoke_twin_endpoint(
+                db,
+                redis_url.clone(),
+                client,
+                user_id,
+                &twin,
+                TwinRequest {
+                    component_alias: step.component_alias.clone(),
+                    endpoint_id: step.end_point.clone(),
+                    headers,
+                    body: body
+                        .map(|body| Bytes::from(body.to_string()))
+                        .unwrap_or_default(),
+                },
+            )
+            .await
+        }
+        .await;
+
+        trace.duration_ms = started_at.elapsed().as_millis() as u64;
+        match result {
+            Ok(response) => {
+                let output = serde_json::from_slice::<Value>(&response.body).unwrap_or_else(|_| {
+                    Value::String(String::from_utf8_lossy(&response.body).to_string())
+                });
+                trace.status_code = Some(response.status.as_u16());
+                if response.status.is_success() {
+                    trace.outcome = "succeeded".to_string();
+                    outputs.insert(step.step_key, output.clone());
+                }
+                trace.output = Some(output);
+            }
+            Err(error) => {
+                trace.status_code = Some(error.code().as_u16());
+                trace.error = Some(error.message().to_string());
+            }
+        }
+
+        traces.push(trace);
+    }
+
+    traces
+}
+
+//Write each mapped value on top of the step's base body, outputs holds the input and every earlier step's output
+pub fn build_step_body(
+    base_body: Option<Value>,
+    mappings: &[PipelineMapping],
+    outputs: &HashMap<String, Value>,
+) -> Result<Option<Value>, AppError> {
+    if mappings.is_empty() {
+        return Ok(base_body);
+    }
+
+    let mut body = base_body.unwrap_or(Value::Null);
+    for mapping in mappings {
+        let source = select(&outputs[&mapping.from], &parse_json_path(&mapping.path)?)
+            .cloned()
+            .ok_or_else(|| {
+                AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("{} has no value at {}", mapping.from, mapping.path),
+                )
+            })?;
+        set(&mut body, &parse_json_path(&mapping.to)?, source)?;
+    }
+
+    Ok(Some(body))
+}
@@ -0,0 +1,171 @@
+use axum::http::StatusCode;
+use sea_orm::{DatabaseConnection, IntoActiveModel, Set};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_policy, core_policy_block, core_twin},
+    queries::{
+        block_queries::{self, BlockDecision, BLOCK_DECISION_BLOCK, BLOCK_DECISION_UNBLOCK},
+        twin_queries, user_queries, violation_queries,
+    },
+    utilities::{
+        app_error::AppError,
+        docker_helper,
+        redis_connection_wrapper::RedisConnWrapper,
+        twin_events::{
+            publish_owner_event, publish_twin_status, OWNER_EVENT_TWIN_BLOCKED,
+            OWNER_EVENT_TWIN_REINSTATED,
+        },
+    },
+};
+
+//Calls on a deactivated twin are refused until the model owner reinstates it
+pub fn ensure_twin_not_deactivated(twin: &core_twin::Model) -> Result<(), AppError> {
+    if twin.twin_status_id == 3 {
+        return Err(AppError::new(
+            StatusCode::FORBIDDEN,
+            "Twin is deactivated after repeated policy violations",
+        ));
+    }
+
+    Ok(())
+}
+
+//Deactivate the twin once the user reached the policy's blockAfter violations since it was last reinstated.
+//A blockAfter of 0 never blocks
+pub async fn enforce_block_after(
+    db: &DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    policy: &core_policy::Model,
+    user_id: Uuid,
+    twin_id: Uuid,
+) -> Result<(), AppError> {
+    if policy.block_after <= 0 {
+        return Ok(());
+    }
+
+    let reinstated_at =
+        block_queries::get_latest_block_decision(db, twin_id, BLOCK_DECISION_UNBLOCK)
+            .await?
+            .map(|decision| decision.decided_at);
+    let violation_count =
+        violation_queries::count_policy_violations(db, user_id, policy.id, twin_id, reinstated_at)
+            .await?;
+    if violation_count < policy.block_after as u64 {
+        return Ok(());
+    }
+
+    let (twin, twin_components) = twin_queries::find_twin_by_id(db, twin_id, user_id).await?;
+    //Containers are named after the twin owner's email
+    let owner = user_queries::find_user_by_id(db, twin.created_by).await?;
+    //Concurrent denials race to the same threshold, only the one that moves the twin records the block
+    if !twin_queries::deactivate_twin(db, twin.id).await? {
+        return Ok(());
+    }
+
+    if twin.twin_status_id == 2 {
+        if let Err(error) = docker_helper::stop_docker_model(twin_components, &owner.email).await {
+            eprintln!("Error stopping deactivated twin: {:?}", error);
+        }
+    }
+
+    let decision = block_queries::create_block_decision(
+        db,
+        BlockDecision {
+            user_id,
+            model_id: policy.model_id,
+            policy_id: policy.id,
+            twin_id: twin.id,
+            decision: BLOCK_DECISION_BLOCK,
+            violation_count: violation_count.min(i32::MAX as u64) as i32,
+            reason: Some(format!(
+                "Reached {} violations of policy {}",
+                violation_count, policy.name
+            )),
+            decided_by: None,
+        },
+    )
+    .await?;
+
+    publish_twin_status(redis_url.clone(), twin.id, "Deactivated", None);
+    publish_owner_event(
+        redis_url,
+        policy.created_by,
+        twin.id,
+        OWNER_EVENT_TWIN_BLOCKED,
+        block_event_data(&decision),
+    );
+
+    Ok(())
+}
+
+//Owners lift a block, the twin comes back stopped and its violation count starts over
+pub async fn reinstate_twin(
+    db: &DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    owner_id: Uuid,
+    model_id: Uuid,
+    twin_id: Uuid,
+    reason: Option<String>,
+) -> Result<core_policy_block::Model, AppError> {
+    let (twin, _twin_components) = twin_queries::find_model_twin(db, twin_id, model_id).await?;
+    if twin.twin_status_id != 3 {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Twin is not deactivated",
+        ));
+    }
+
+    let block = block_queries::get_latest_block_decision(db, twin.id, BLOCK_DECISION_BLOCK)
+        .await?
+        .ok_or_else(|| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                "Twin was not deactivated for policy violations",
+            )
+        })?;
+
+    let user_id = twin.created_by;
+    let mut twin = twin.into_active_model();
+    twin.twin_status_id = Set(1);
+    twin.updated_by = Set(Some(owner_id));
+    twin.updated_at = Set(chrono::Utc::now().into());
+    let twin = twin_queries::save_active_coretwin(db, twin).await?;
+
+    let decision = block_queries::create_block_decision(
+        db,
+        BlockDecision {
+            user_id,
+            model_id,
+            policy_id: block.policy_id,
+            twin_id: twin.id,
+            decision: BLOCK_DECISION_UNBLOCK,
+            violation_count: block.violation_count,
+            reason,
+            decided_by: Some(owner_id),
+        },
+    )
+    .await?;
+
+    publish_twin_status(redis_url.clone(), twin.id, "Stopped", None);
+    publish_owner_event(
+        redis_url,
+        owner_id,
+        twin.id,
+        OWNER_EVENT_TWIN_REINSTATED,
+        block_event_data(&decision),
+    );
+
+    Ok(decision)
+}
+
+fn block_event_data(decision: &core_policy_block::Model) -> serde_json::Value {
+    json!({
+        "userId": decision.user_id,
+        "modelId": decision.model_id,
+        "policyId": decision.policy_id,
+        "violationCount": decision.violation_count,
+        "reason": decision.reason,
+    })
+}
@@ -0,0 +1,238 @@
+use std::cmp::Ordering;
+
+use axum::http::StatusCode;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::utilities::app_error::AppError;
+
+pub const CONDITION_SOURCE_BODY: &str = "body";
+pub const CONDITION_SOURCE_USER: &str = "user";
+
+const CONDITION_OPERATORS: [&str; 11] = [
+    "eq", "ne", "lt", "lte", "gt", "gte", "between", "in", "not_in", "matches", "exists",
+];
+
+//A rule over one field of the request body or of the calling user ({id, email, role, attributes}).
+//Fields are dot separated paths, array items are addressed by index (params.ranges.0.max)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PolicyCondition {
+    pub source: String,
+    pub field: String,
+    //eq, ne, lt, lte, gt, gte, between, in, not_in, matches or exists
+    pub op: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConditionFailure {
+    pub index: usize,
+    pub condition: PolicyCondition,
+    //None when the field is missing
+    pub actual: Option<Value>,
+}
+
+impl ConditionFailure {
+    pub fn message(&self) -> String {
+        let condition = &self.condition;
+        match self.actual {
+            Some(_) => format!(
+                "Policy condition not satisfied: {}.{} {} {}",
+                condition.source, condition.field, condition.op, condition.value
+            ),
+            None if condition.op == "exists" => format!(
+                "Policy condition not satisfied: {}.{} must not be set",
+                condition.source, condition.field
+            ),
+            None => format!(
+                "Policy condition not satisfied: {}.{} is missing",
+                condition.source, condition.field
+            ),
+        }
+    }
+
+    pub fn details(&self) -> Value {
+        json!({
+            "index": self.index,
+            "source": self.condition.source,
+            "field": self.condition.field,
+            "op": self.condition.op,
+            "value": self.condition.value,
+            "actual": self.actual,
+        })
+    }
+}
+
+//Checks the conditions sent with a new policy action
+pub fn conditions_from_request(value: &Value) -> Result<Vec<PolicyCondition>, AppError> {
+    let conditions =
+        serde_json::from_value::<Vec<PolicyCondition>>(value.clone()).map_err(|error| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("conditions must be a list of conditions: {}", error),
+            )
+        })?;
+
+    for (index, condition) in conditions.iter().enumerate() {
+        condition.validate().map_err(|message| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("conditions[{}]: {}", index, message),
+            )
+        })?;
+    }
+
+    Ok(conditions)
+}
+
+//None when every condition holds, otherwise the first one that failed
+pub fn first_failed_condition(
+    conditions: &[PolicyCondition],
+    body: &Value,
+    user: &Value,
+) -> Option<ConditionFailure> {
+    conditions
+        .iter()
+        .enumerate()
+        .find_map(|(index, condition)| {
+            let document = if condition.source == CONDITION_SOURCE_USER {
+                user
+            } else {
+                body
+            };
+            let actual = lookup(document, &condition.field);
+            if condition.holds(actual) {
+                return None;
+            }
+
+            Some(ConditionFailure {
+                index,
+                condition: condition.clone(),
+                actual: actual.cloned(),
+            })
+        })
+}
+
+impl PolicyCondition {
+    fn validate(&self) -> Result<(), String> {
+        if ![CONDITION_SOURCE_BODY, CONDITION_SOURCE_USER].contains(&self.source.as_str()) {
+            return Err("source must be body or user".to_string());
+        }
+        if self.field.is_empty() || self.field.split('.').any(|segment| segment.is_empty()) {
+            return Err("field must be a dot separated path".to_string());
+        }
+        if !CONDITION_OPERATORS.contains(&self.op.as_str()) {
+            return Err(format!(
+                "op must be one of {}",
+                CONDITION_OPERATORS.join(", ")
+            ));
+        }
+
+        match (self.op.as_str(), &self.value) {
+            ("lt" | "lte" | "gt" | "gte", Value::Number(_) | Value::String(_)) => Ok(()),
+            ("lt" | "lte" | "gt" | "gte", _) => {
+                Err("value must be a number or a string".to_string())
+            }
+            ("between", Value::Array(bounds))
+                if bounds.len() == 2
+                    && compare(&bounds[0], &bounds[1])
+                        .is_some_and(|ordering| ordering != Ordering::Greater) =>
+            {
+                Ok(())
+            }
+            ("between", _) => Err(
+                "value must be a [min, max] pair of numbers or strings with min <= max".to_string(),
+            ),
+            ("in" | "not_in", Value::Array(_)) => Ok(()),
+            ("in" | "not_in", _) => Err("value must be a list".to_string()),
+            ("matches", Value::String(pattern)) => Regex::new(pattern)
+                .map(|_| ())
+                .map_err(|error| format!("value is not a valid regex: {}", error)),
+            ("matches", _) => Err("value must be a regex".to_string()),
+            ("exists", Value::Bool(_) | Value::Null) => Ok(()),
+            ("exists", _) => Err("value must be true or false".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    //A missing field fails every operator except exists: false
+    fn holds(&self, actual: Option<&Value>) -> bool {
+        if self.op == "exists" {
+            let should_exist = self.value.as_bool().unwrap_or(true);
+            return actual.is_some() == should_exist;
+        }
+
+        let actual = match actual {
+            Some(actual) => actual,
+            None => return false,
+        };
+
+        match self.op.as_str() {
+            "eq" => equals(actual, &self.value),
+            "ne" => !equals(actual, &self.value),
+            "lt" => compare(actual, &self.value) == Some(Ordering::Less),
+            "lte" => {
+                compare(actual, &self.value).is_some_and(|ordering| ordering != Ordering::Greater)
+            }
+            "gt" => compare(actual, &self.value) == Some(Ordering::Greater),
+            "gte" => {
+                compare(actual, &self.value).is_some_and(|ordering| ordering != Ordering::Less)
+            }
+            "between" => match self.value.as_array().map(Vec::as_slice) {
+                Some([min, max]) => {
+                    compare(actual, min).is_some_and(|ordering| ordering != Ordering::Less)
+                        && compare(actual, max)
+                            .is_some_and(|ordering| ordering != Ordering::Greater)
+                }
+                _ => false,
+            },
+            "in" => self
+                .value
+                .as_array()
+                .is_some_and(|allowed| allowed.iter().any(|value| equals(actual, value))),
+            "not_in" => self
+                .value
+                .as_array()
+                .is_some_and(|refused| !refused.iter().any(|value| equals(actual, value))),
+            "matches" => match (actual.as_str(), self.value.as_str()) {
+                (Some(actual), Some(pattern)) => Regex::new(pattern)
+                    .map(|regex| regex.is_match(actual))
+                    .unwrap_or(false),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+fn lookup<'a>(document: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(document, |value, segment| match value {
+            Value::Object(object) => object.get(segment),
+            Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get(index)),
+            _ => None,
+        })
+}
+
+//Numbers compare by value so 1 and 1.0 are equal
+fn equals(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(left), Some(right)) => left == right,
+        _ => left == right,
+    }
+}
+
+//Numbers with numbers, strings with strings (ISO dates order correctly), anything else doesn't compare
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
@@ -0,0 +1,419 @@
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    database::{core_policy, core_policy_action},
+    helpers::{
+        policy_condition_helpers::{conditions_from_request, PolicyCondition},
+        policy_mgmt_helpers::{RateLimit, RATE_LIMIT_TOKEN_BUCKET},
+        policy_validity_helpers::ValidityWindow,
+        schema_validation_helpers::compile_schema,
+    },
+    routes::policys::{
+        create_policy_action_extractor::ValidateCreatePolicyAction,
+        create_policy_extractor::ValidateCreatePolicy, RequestPolicyValidated,
+    },
+    utilities::app_error::AppError,
+};
+
+pub const POLICY_DOCUMENT_FORMAT: &str = "dt-policy/v1";
+
+//Seeded into core_action_reset_frequency, the ids follow the insert order
+const RESET_FREQUENCIES: [(i32, &str); 5] = [
+    (1, "daily"),
+    (2, "weekly"),
+    (3, "monthly"),
+    (4, "yearly"),
+    (5, "never"),
+];
+
+const END_POINT_VERBS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+//A policy as owners write it and keep it in git, YAML or JSON.
+//Importing always creates a new policy version, policyVersion is only filled in on export
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocument {
+    pub format: String,
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "policyVersion", skip_serializing_if = "Option::is_none")]
+    pub policy_version: Option<i32>,
+    //Violations before the twin is deactivated, 0 never blocks
+    #[serde(rename = "blockAfter", default)]
+    pub block_after: i32,
+    pub actions: Vec<PolicyDocumentAction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocumentAction {
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    #[serde(rename = "componentAlias", skip_serializing_if = "Option::is_none")]
+    pub component_alias: Option<String>,
+    pub verb: String,
+    pub description: String,
+    pub quota: PolicyDocumentQuota,
+    #[serde(rename = "rateLimit", skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<PolicyDocumentRateLimit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity: Option<ValidityWindow>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<PolicyCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streams: Option<PolicyDocumentStreams>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<PolicyDocumentCache>,
+    #[serde(rename = "requestSchema", skip_serializing_if = "Option::is_none")]
+    pub request_schema: Option<Value>,
+    #[serde(rename = "responseSchema", skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<Value>,
+    //off, flag or reject
+    #[serde(rename = "responseValidation", skip_serializing_if = "Option::is_none")]
+    pub response_validation: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocumentQuota {
+    pub count: i32,
+    //daily, weekly, monthly, yearly or never
+    pub reset: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocumentRateLimit {
+    //token_bucket or sliding_window
+    pub algorithm: String,
+    pub requests: i32,
+    //second or minute
+    pub period: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocumentStreams {
+    #[serde(rename = "maxConcurrent", skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<i32>,
+    //Seconds
+    #[serde(rename = "maxDuration", skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocumentCache {
+    //Seconds
+    pub ttl: i32,
+    //twin or model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(rename = "hitsCount", skip_serializing_if = "Option::is_none")]
+    pub hits_count: Option<bool>,
+}
+
+impl PolicyDocument {
+    //YAML is a superset of JSON, so one parser reads both
+    pub fn parse(source: &str) -> Result<Self, AppError> {
+        let document = serde_yaml::from_str::<Self>(source).map_err(|error| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid policy document: {}", error),
+            )
+        })?;
+        document.validate()?;
+
+        Ok(document)
+    }
+
+    pub fn to_yaml(&self) -> Result<String, AppError> {
+        serde_yaml::to_string(self).map_err(|error| {
+            eprintln!("Error serializing policy document to yaml: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error exporting policy document",
+            )
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, AppError> {
+        serde_json::to_string_pretty(self).map_err(|error| {
+            eprintln!("Error serializing policy document to json: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error exporting policy document",
+            )
+        })
+    }
+
+    //Errors name the action they come from so owners can find them in their file
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.format != POLICY_DOCUMENT_FORMAT {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Unsupported policy document format {}, expected {}",
+                    self.format, POLICY_DOCUMENT_FORMAT
+                ),
+            ));
+        }
+        if self.name.trim().is_empty() {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "Policy document name cannot be empty",
+            ));
+        }
+        if self.block_after < 0 {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "blockAfter cannot be negative",
+            ));
+        }
+        if self.actions.is_empty() {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "Policy document must have at least one action",
+            ));
+        }
+
+        for (index, action) in self.actions.iter().enumerate() {
+            let duplicate = self.actions[..index].iter().any(|other| {
+                other.end_point.to_lowercase() == action.end_point.to_lowercase()
+                    && other.component_alias == action.component_alias
+            });
+            if duplicate {
+                return Err(action_error(
+                    index,
+                    action,
+                    "endPoint is listed more than once",
+                ));
+            }
+
+            action
+                .validate()
+                .map_err(|error| action_error(index, action, error.message()))?;
+        }
+
+        Ok(())
+    }
+
+    //Compiles into the same request the JSON policy API takes, so both go through create_model_policy
+    pub fn into_policy_request(self) -> RequestPolicyValidated {
+        RequestPolicyValidated {
+            policy_info: ValidateCreatePolicy {
+                name: Some(self.name),
+                description: Some(self.description),
+                model_id: None,
+                block_after: Some(self.block_after),
+            },
+            policy_action_info: self
+                .actions
+                .into_iter()
+                .map(PolicyDocumentAction::into_policy_action_request)
+                .collect(),
+        }
+    }
+
+    pub fn from_policy(
+        policy: &core_policy::Model,
+        policy_actions: &[core_policy_action::Model],
+    ) -> Self {
+        Self {
+            format: POLICY_DOCUMENT_FORMAT.to_string(),
+            name: policy.name.clone(),
+            description: policy.description.clone(),
+            policy_version: Some(policy.policy_version),
+            block_after: policy.block_after,
+            actions: policy_actions
+                .iter()
+                .map(PolicyDocumentAction::from_policy_action)
+                .collect(),
+        }
+    }
+}
+
+impl PolicyDocumentAction {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.end_point.trim().is_empty() {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "endPoint cannot be empty",
+            ));
+        }
+        if !END_POINT_VERBS.contains(&self.verb.to_uppercase().as_str()) {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("verb must be one of {}", END_POINT_VERBS.join(", ")),
+            ));
+        }
+        if self.quota.count < 0 {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "quota.count cannot be negative",
+            ));
+        }
+        reset_frequency_id(&self.quota.reset)?;
+
+        if let Some(rate_limit) = self.rate_limit.as_ref() {
+            RateLimit::from_request(
+                Some(&rate_limit.algorithm),
+                Some(rate_limit.requests),
+                Some(&rate_limit.period),
+                rate_limit.burst,
+            )?;
+        }
+        if let Some(validity) = self.validity.as_ref() {
+            ValidityWindow::from_request(&serde_json::to_value(validity).unwrap_or_default())?;
+        }
+        if !self.conditions.is_empty() {
+            conditions_from_request(&serde_json::to_value(&self.conditions).unwrap_or_default())?;
+        }
+        for schema in [&self.request_schema, &self.response_schema]
+            .into_iter()
+            .flatten()
+        {
+            compile_schema(schema)?;
+        }
+
+        Ok(())
+    }
+
+    fn into_policy_action_request(self) -> ValidateCreatePolicyAction {
+        let (rate_limit_algorithm, rate_limit_requests, rate_limit_period, rate_limit_burst) =
+            match self.rate_limit {
+                Some(rate_limit) => (
+                    Some(rate_limit.algorithm),
+                    Some(rate_limit.requests),
+                    Some(rate_limit.period),
+                    rate_limit.burst,
+                ),
+                None => (None, None, None, None),
+            };
+
+        ValidateCreatePolicyAction {
+            policy_id: None,
+            end_point: Some(self.end_point),
+            description: Some(self.description),
+            end_point_verb: Some(self.verb),
+            action_count: Some(self.quota.count),
+            reset_frequency_id: reset_frequency_id(&self.quota.reset).ok(),
+            max_concurrent_streams: self
+                .streams
+                .as_ref()
+                .and_then(|streams| streams.max_concurrent),
+            max_stream_duration: self.streams.and_then(|streams| streams.max_duration),
+            is_cacheable: self.cache.as_ref().map(|_| true),
+            cache_ttl: self.cache.as_ref().map(|cache| cache.ttl),
+            cache_scope: self.cache.as_ref().and_then(|cache| cache.scope.clone()),
+            cache_hits_count: self.cache.and_then(|cache| cache.hits_count),
+            request_schema: self.request_schema,
+            response_schema: self.response_schema,
+            response_validation: self.response_validation,
+            component_alias: self.component_alias,
+            rate_limit_algorithm,
+            rate_limit_requests,
+            rate_limit_period,
+            rate_limit_burst,
+            validity: self
+                .validity
+                .and_then(|validity| serde_json::to_value(validity).ok()),
+            conditions: Some(self.conditions)
+                .filter(|conditions| !conditions.is_empty())
+                .and_then(|conditions| serde_json::to_value(conditions).ok()),
+        }
+    }
+
+    fn from_policy_action(policy_action: &core_policy_action::Model) -> Self {
+        let rate_limit = RateLimit::from_policy_action(policy_action).map(|rate_limit| {
+            PolicyDocumentRateLimit {
+                requests: rate_limit.requests as i32,
+                period: policy_action.rate_limit_period.clone().unwrap_or_default(),
+                burst: Some(rate_limit.burst as i32)
+                    .filter(|_| rate_limit.algorithm == RATE_LIMIT_TOKEN_BUCKET),
+                algorithm: rate_limit.algorithm,
+            }
+        });
+
+        let streams = (policy_action.max_concurrent_streams.is_some()
+            || policy_action.max_stream_duration.is_some())
+        .then_some(PolicyDocumentStreams {
+            max_concurrent: policy_action.max_concurrent_streams,
+            max_duration: policy_action.max_stream_duration,
+        });
+
+        let cache = policy_action
+            .cache_ttl
+            .filter(|_| policy_action.is_cacheable)
+            .map(|ttl| PolicyDocumentCache {
+                ttl,
+                scope: Some(policy_action.cache_scope.clone()),
+                hits_count: Some(policy_action.cache_hits_count),
+            });
+
+        Self {
+            end_point: policy_action.end_point.clone(),
+            component_alias: policy_action.component_alias.clone(),
+            verb: policy_action.end_point_verb.clone(),
+            description: policy_action.description.clone(),
+            quota: PolicyDocumentQuota {
+                count: policy_action.action_count,
+                reset: reset_frequency_name(policy_action.reset_frequency_id).to_string(),
+            },
+            rate_limit,
+            validity: policy_action
+                .validity
+                .clone()
+                .and_then(|validity| serde_json::from_value(validity).ok()),
+            conditions: policy_action
+                .conditions
+                .clone()
+                .and_then(|conditions| serde_json::from_value(conditions).ok())
+                .unwrap_or_default(),
+            streams,
+            cache,
+            request_schema: policy_action.request_schema.clone(),
+            response_schema: policy_action.response_schema.clone(),
+            response_validation: Some(policy_action.response_validation.clone())
+                .filter(|response_validation| response_validation != "off"),
+        }
+    }
+}
+
+fn action_error(index: usize, action: &PolicyDocumentAction, message: &str) -> AppError {
+    AppError::new(
+        StatusCode::BAD_REQUEST,
+        format!(
+            "actions[{}] ({} {}): {}",
+            index,
+            action.verb.to_uppercase(),
+            action.end_point,
+            message
+        ),
+    )
+}
+
+fn reset_frequency_id(reset: &str) -> Result<i32, AppError> {
+    RESET_FREQUENCIES
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(reset))
+        .map(|(id, _)| *id)
+        .ok_or_else(|| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                "quota.reset must be one of daily, weekly, monthly, yearly or never",
+            )
+        })
+}
+
+fn reset_frequency_name(reset_frequency_id: i32) -> &'static str {
+    RESET_FREQUENCIES
+        .iter()
+        .find(|(id, _)| *id == reset_frequency_id)
+        .map(|(_, name)| *name)
+        .unwrap_or("never")
+}
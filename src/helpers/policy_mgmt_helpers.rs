@@ -1,121 +1,793 @@
-use hyper::StatusCode;
-use sea_orm::DatabaseConnection;
+use hyper::{body::Bytes, header, http::HeaderValue, StatusCode};
+use sea_orm::{DatabaseConnection, Set};
+use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::{
-    queries::policy_queries,
+    database::{
+        core_model_component,
+        core_policy_action::{ActiveModel as PolicyActActiveModel, Model as PolicyActionModel},
+    },
+    helpers::{
+        policy_block_helpers::enforce_block_after,
+        policy_condition_helpers::{
+            conditions_from_request, first_failed_condition, PolicyCondition, CONDITION_SOURCE_USER,
+        },
+        policy_validity_helpers::ValidityWindow,
+        schema_validation_helpers::compile_schema,
+    },
+    queries::{
+        policy_queries, role_queries, user_queries,
+        violation_queries::{self, PolicyViolation},
+    },
+    routes::policys::create_policy_action_extractor::ValidateCreatePolicyAction,
     utilities::{
         app_error::AppError,
         redis_connection_wrapper::RedisConnWrapper,
-        redis_helper::{get_token_from_redis, get_token_ttl, store_token_in_redis},
+        redis_helper::{
+            consume_quota_in_redis, consume_sliding_window_in_redis, consume_token_bucket_in_redis,
+            get_token_from_redis, store_token_in_redis, QuotaUsage, RateLimitUsage,
+        },
     },
 };
 
+//Recorded on violations of the action count and conditions, validity windows record their own reason
+pub const VIOLATION_REASON_QUOTA_EXCEEDED: &str = "quota_exceeded";
+pub const VIOLATION_REASON_CONDITION_FAILED: &str = "condition_failed";
+
+//A policy action is addressed by its endpoint, optionally scoped to one of the twin's exposed components
+#[derive(Clone, Debug)]
+pub struct PolicyEndpoint {
+    pub component_alias: Option<String>,
+    pub end_point: String,
+}
+
+impl PolicyEndpoint {
+    pub fn new(component_alias: Option<String>, end_point: String) -> Self {
+        Self {
+            component_alias,
+            end_point,
+        }
+    }
+
+    pub fn key(&self) -> String {
+        policy_action_key(self.component_alias.as_deref(), &self.end_point)
+    }
+}
+
+//Redis keys of component scoped actions carry the alias so they never clash with the default entry point
+pub fn policy_action_key(component_alias: Option<&str>, end_point: &str) -> String {
+    match component_alias {
+        Some(component_alias) => format!("{}|{}", component_alias, end_point),
+        None => end_point.to_string(),
+    }
+}
+
+//Daily, the column default of core_policy_action."resetFrequencyId"
+pub const DEFAULT_RESET_FREQUENCY_ID: i32 = 1;
+
+//Length of a quota window in seconds for core_action_reset_frequency, 0 for Never
+pub fn reset_window_secs(reset_frequency_id: i32) -> u64 {
+    match reset_frequency_id {
+        1 => 24 * 3600,
+        2 => 7 * 24 * 3600,
+        3 => 30 * 24 * 3600,
+        4 => 365 * 24 * 3600,
+        _ => 0,
+    }
+}
+
+pub const RATE_LIMIT_TOKEN_BUCKET: &str = "token_bucket";
+pub const RATE_LIMIT_SLIDING_WINDOW: &str = "sliding_window";
+//Cached in place of a rate limit for actions that don't have one
+const NO_RATE_LIMIT: &str = "none";
+
+//Burst protection for a policy action, enforced before its count quota
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub algorithm: String,
+    pub requests: i64,
+    pub period_secs: u64,
+    //Bucket capacity, only used by the token bucket
+    pub burst: i64,
+}
+
+impl RateLimit {
+    //Checks the rateLimit* fields of a new policy action, None when it has no rate limit
+    pub fn from_request(
+        algorithm: Option<&str>,
+        requests: Option<i32>,
+        period: Option<&str>,
+        burst: Option<i32>,
+    ) -> Result<Option<Self>, AppError> {
+        let Some(algorithm) = algorithm.map(str::to_lowercase) else {
+            if requests.is_some() || period.is_some() || burst.is_some() {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    "rateLimitAlgorithm is required when a rate limit is set",
+                ));
+            }
+            return Ok(None);
+        };
+
+        if algorithm != RATE_LIMIT_TOKEN_BUCKET && algorithm != RATE_LIMIT_SLIDING_WINDOW {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "rateLimitAlgorithm must be either token_bucket or sliding_window",
+            ));
+        }
+
+        let requests = requests.filter(|requests| *requests > 0).ok_or_else(|| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                "rateLimitRequests must be a positive number",
+            )
+        })?;
+
+        let period_secs = period.and_then(rate_limit_period_secs).ok_or_else(|| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                "rateLimitPeriod must be either second or minute",
+            )
+        })?;
+
+        let burst = match burst {
+            Some(_) if algorithm == RATE_LIMIT_SLIDING_WINDOW => {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    "rateLimitBurst only applies to the token_bucket algorithm",
+                ));
+            }
+            Some(burst) if burst <= 0 => {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    "rateLimitBurst must be a positive number",
+                ));
+            }
+            Some(burst) => burst,
+            None => requests,
+        };
+
+        Ok(Some(Self {
+            algorithm,
+            requests: requests as i64,
+            period_secs,
+            burst: burst as i64,
+        }))
+    }
+
+    pub fn from_policy_action(policy_action: &PolicyActionModel) -> Option<Self> {
+        let algorithm = policy_action.rate_limit_algorithm.clone()?;
+        let requests = policy_action.rate_limit_requests?;
+        let period_secs = rate_limit_period_secs(policy_action.rate_limit_period.as_deref()?)?;
+
+        Some(Self {
+            algorithm,
+            requests: requests as i64,
+            period_secs,
+            burst: policy_action.rate_limit_burst.unwrap_or(requests) as i64,
+        })
+    }
+
+    //Stored in redis as algorithm|requests|period|burst
+    pub fn to_token(rate_limit: Option<&Self>) -> String {
+        match rate_limit {
+            Some(rate_limit) => format!(
+                "{}|{}|{}|{}",
+                rate_limit.algorithm, rate_limit.requests, rate_limit.period_secs, rate_limit.burst
+            ),
+            None => NO_RATE_LIMIT.to_string(),
+        }
+    }
+
+    //None when the token is missing or unreadable, Some(None) when the action has no rate limit
+    pub fn from_token(token: &str) -> Option<Option<Self>> {
+        if token == NO_RATE_LIMIT {
+            return Some(None);
+        }
+
+        let mut parts = token.split('|');
+        let rate_limit = Self {
+            algorithm: parts.next()?.to_string(),
+            requests: parts.next()?.parse().ok()?,
+            period_secs: parts.next()?.parse().ok()?,
+            burst: parts.next()?.parse().ok()?,
+        };
+
+        Some(Some(rate_limit))
+    }
+}
+
+pub fn rate_limit_period_secs(period: &str) -> Option<u64> {
+    match period.to_lowercase().as_str() {
+        "second" => Some(1),
+        "minute" => Some(60),
+        _ => None,
+    }
+}
+
+//Check a new policy action and build its row along with the limits cached for it in redis
+pub fn build_policy_action(
+    policy_id: Uuid,
+    user_id: Uuid,
+    comp: ValidateCreatePolicyAction,
+    model_components: &[core_model_component::Model],
+) -> Result<(PolicyActActiveModel, Vec<(String, String)>), AppError> {
+    let end_point = comp.end_point.unwrap().to_lowercase();
+    let action_count_log: i32;
+
+    let mut new_comp = PolicyActActiveModel {
+        id: Set(Uuid::new_v4()),
+        policy_id: Set(policy_id),
+        end_point: Set(end_point.clone()),
+        description: Set(comp.description.unwrap()),
+        end_point_verb: Set(comp.end_point_verb.clone().unwrap().to_uppercase()),
+
+        created_by: Set(user_id),
+        updated_by: Set(Some(user_id)),
+        ..Default::default()
+    };
+
+    //Check if action_count is not null, then save it
+    if let Some(action_count) = comp.action_count {
+        new_comp.action_count = Set(action_count);
+        action_count_log = action_count;
+    } else {
+        action_count_log = 0;
+    }
+
+    //Set even when omitted so the row and the reset window cached in redis always agree
+    let reset_frequency_id = comp
+        .reset_frequency_id
+        .unwrap_or(DEFAULT_RESET_FREQUENCY_ID);
+    new_comp.reset_frequency_id = Set(reset_frequency_id);
+
+    //Streaming limits are optional, leaving them unset means unlimited
+    new_comp.max_concurrent_streams = Set(comp.max_concurrent_streams);
+    new_comp.max_stream_duration = Set(comp.max_stream_duration);
+
+    //Caching only applies when the owner marks the action as cacheable with a ttl
+    if let Some(is_cacheable) = comp.is_cacheable {
+        if is_cacheable && comp.cache_ttl.is_none() {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "cacheTtl is required for cacheable policy actions",
+            ));
+        }
+        new_comp.is_cacheable = Set(is_cacheable);
+    }
+    new_comp.cache_ttl = Set(comp.cache_ttl);
+
+    if let Some(cache_scope) = comp.cache_scope {
+        let cache_scope = cache_scope.to_lowercase();
+        if cache_scope != "twin" && cache_scope != "model" {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "cacheScope must be either twin or model",
+            ));
+        }
+        new_comp.cache_scope = Set(cache_scope);
+    }
+
+    if let Some(cache_hits_count) = comp.cache_hits_count {
+        new_comp.cache_hits_count = Set(cache_hits_count);
+    }
+
+    //Reject schemas that cannot be compiled so they fail here rather than on every call
+    for schema in [&comp.request_schema, &comp.response_schema]
+        .into_iter()
+        .flatten()
+    {
+        compile_schema(schema)?;
+    }
+    new_comp.request_schema = Set(comp.request_schema);
+    new_comp.response_schema = Set(comp.response_schema);
+
+    if let Some(response_validation) = comp.response_validation {
+        let response_validation = response_validation.to_lowercase();
+        if !["off", "flag", "reject"].contains(&response_validation.as_str()) {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "responseValidation must be one of off, flag or reject",
+            ));
+        }
+        new_comp.response_validation = Set(response_validation);
+    }
+
+    //Actions scoped to a component must name one of the model's exposed components
+    if let Some(component_alias) = comp.component_alias.as_ref() {
+        let is_exposed_component = model_components.iter().any(|model_component| {
+            model_component.is_exposed
+                && model_component.component_alias.as_deref() == Some(component_alias.as_str())
+        });
+        if !is_exposed_component {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "componentAlias {} is not an exposed model component",
+                    component_alias
+                ),
+            ));
+        }
+    }
+    let rate_limit = RateLimit::from_request(
+        comp.rate_limit_algorithm.as_deref(),
+        comp.rate_limit_requests,
+        comp.rate_limit_period.as_deref(),
+        comp.rate_limit_burst,
+    )?;
+    if let Some(rate_limit) = rate_limit.as_ref() {
+        new_comp.rate_limit_algorithm = Set(Some(rate_limit.algorithm.clone()));
+        new_comp.rate_limit_requests = Set(Some(rate_limit.requests as i32));
+        new_comp.rate_limit_period =
+            Set(comp.rate_limit_period.map(|period| period.to_lowercase()));
+        new_comp.rate_limit_burst = Set(comp.rate_limit_burst);
+    }
+
+    let validity = comp
+        .validity
+        .as_ref()
+        .map(ValidityWindow::from_request)
+        .transpose()?;
+    new_comp.validity = Set(validity
+        .as_ref()
+        .and_then(|validity| serde_json::to_value(validity).ok()));
+
+    let conditions = comp
+        .conditions
+        .as_ref()
+        .map(conditions_from_request)
+        .transpose()?;
+    new_comp.conditions = Set(conditions
+        .filter(|conditions| !conditions.is_empty())
+        .and_then(|conditions| serde_json::to_value(conditions).ok()));
+
+    let action_key = policy_action_key(comp.component_alias.as_deref(), &end_point);
+    new_comp.component_alias = Set(comp.component_alias);
+
+    //Policy action limits and verb cached in redis
+    let store_key = "Policy:Models:".to_string() + &policy_id.to_string();
+    let policy_tokens = vec![
+        (
+            store_key.clone() + ":Access:" + &action_key,
+            action_count_log.to_string(),
+        ),
+        (
+            store_key.clone() + ":Reset:" + &action_key,
+            reset_window_secs(reset_frequency_id).to_string(),
+        ),
+        (
+            store_key.clone() + ":Rate:" + &action_key,
+            RateLimit::to_token(rate_limit.as_ref()),
+        ),
+        (
+            store_key.clone() + ":Validity:" + &action_key,
+            ValidityWindow::to_token(validity.as_ref()),
+        ),
+        (
+            store_key + ":Verb:" + &action_key,
+            comp.end_point_verb.unwrap().to_uppercase(),
+        ),
+    ];
+
+    Ok((new_comp, policy_tokens))
+}
+
+//Limits of one policy action, cached in redis next to the action itself
+struct PolicyLimits {
+    action_count: i32,
+    window_secs: u64,
+    rate_limit: Option<RateLimit>,
+    validity: Option<ValidityWindow>,
+}
+
+//Admits the call and counts it against the user's quota
+#[tracing::instrument(skip(db, redis_url), err(Debug))]
 pub async fn check_policy(
     db: &DatabaseConnection,
     twin_id: Uuid,
-    endpoint_id: String,
+    endpoint: &PolicyEndpoint,
     user_id: Uuid,
     policy_id: Uuid,
     redis_url: RedisConnWrapper,
-) -> Result<bool, AppError> {
+) -> Result<QuotaUsage, AppError> {
+    let limits = load_policy_limits(db, endpoint, policy_id, redis_url.clone()).await?;
+
+    admit_policy_call(
+        db,
+        twin_id,
+        endpoint,
+        user_id,
+        policy_id,
+        redis_url.clone(),
+        &limits,
+    )
+    .await?;
+    account_policy_call(
+        db, twin_id, endpoint, user_id, policy_id, redis_url, &limits,
+    )
+    .await
+}
+
+//Cached responses are admitted like any other call, owners decide whether they count against the quota
+#[tracing::instrument(skip(db, redis_url), err(Debug))]
+pub async fn check_cached_call_policy(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    endpoint: &PolicyEndpoint,
+    user_id: Uuid,
+    policy_id: Uuid,
+    redis_url: RedisConnWrapper,
+    count_call: bool,
+) -> Result<(), AppError> {
+    let limits = load_policy_limits(db, endpoint, policy_id, redis_url.clone()).await?;
+
+    admit_policy_call(
+        db,
+        twin_id,
+        endpoint,
+        user_id,
+        policy_id,
+        redis_url.clone(),
+        &limits,
+    )
+    .await?;
+    if count_call {
+        account_policy_call(
+            db, twin_id, endpoint, user_id, policy_id, redis_url, &limits,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn load_policy_limits(
+    db: &DatabaseConnection,
+    endpoint: &PolicyEndpoint,
+    policy_id: Uuid,
+    redis_url: RedisConnWrapper,
+) -> Result<PolicyLimits, AppError> {
+    let endpoint_id = endpoint.key();
+
     //Check redis for policy
     let mut store_key_model = "Policy:Models:".to_string();
     store_key_model = store_key_model + &policy_id.clone().to_string();
+    let store_key_reset = store_key_model.clone() + ":Reset:" + &endpoint_id.clone();
+    let store_key_rate = store_key_model.clone() + ":Rate:" + &endpoint_id.clone();
+    let store_key_validity = store_key_model.clone() + ":Validity:" + &endpoint_id.clone();
     store_key_model = store_key_model + ":Access:" + &endpoint_id.clone();
 
     let model_policy_token =
         get_token_from_redis(redis_url.clone(), store_key_model.clone()).await?;
+    let reset_window_token =
+        get_token_from_redis(redis_url.clone(), store_key_reset.clone()).await?;
+    let rate_limit_token = get_token_from_redis(redis_url.clone(), store_key_rate.clone()).await?;
+    let validity_token =
+        get_token_from_redis(redis_url.clone(), store_key_validity.clone()).await?;
 
-    let model_action_count = match model_policy_token.clone() {
-        token if token != "" => token.parse::<i32>().unwrap_or_default(),
-        _ => {
-            println!("Policy not found in redis");
-            // Get policy_action from db using policy_id and endpoint_id
-            let policy_action = policy_queries::get_policy_action_by_policyid_and_endpoint(
-                db,
-                policy_id,
-                endpoint_id.clone(),
-            )
-            .await?;
+    if let (Ok(action_count), Ok(window_secs), Some(rate_limit), Some(validity)) = (
+        model_policy_token.parse::<i32>(),
+        reset_window_token.parse::<u64>(),
+        RateLimit::from_token(&rate_limit_token),
+        ValidityWindow::from_token(&validity_token),
+    ) {
+        return Ok(PolicyLimits {
+            action_count,
+            window_secs,
+            rate_limit,
+            validity,
+        });
+    }
+
+    println!("Policy not found in redis");
+    // Get policy_action from db using policy_id and endpoint_id
+    let policy_action = policy_queries::get_policy_action_by_policyid_and_endpoint(
+        db,
+        policy_id,
+        endpoint.component_alias.as_deref(),
+        endpoint.end_point.clone(),
+    )
+    .await?;
+    let window_secs = reset_window_secs(policy_action.reset_frequency_id);
 
-            //Store policy_action in redis
-            let _redis_response = store_token_in_redis(
+    //Store policy_action in redis
+    store_token_in_redis(
+        redis_url.clone(),
+        store_key_model.clone(),
+        policy_action.action_count.to_string().clone(),
+        usize::MAX,
+    )
+    .await?;
+    store_token_in_redis(
+        redis_url.clone(),
+        store_key_reset.clone(),
+        window_secs.to_string(),
+        usize::MAX,
+    )
+    .await?;
+    let rate_limit = RateLimit::from_policy_action(&policy_action);
+    store_token_in_redis(
+        redis_url.clone(),
+        store_key_rate.clone(),
+        RateLimit::to_token(rate_limit.as_ref()),
+        usize::MAX,
+    )
+    .await?;
+    let validity = policy_action
+        .validity
+        .clone()
+        .and_then(|validity| serde_json::from_value::<ValidityWindow>(validity).ok());
+    store_token_in_redis(
+        redis_url.clone(),
+        store_key_validity.clone(),
+        ValidityWindow::to_token(validity.as_ref()),
+        usize::MAX,
+    )
+    .await?;
+
+    Ok(PolicyLimits {
+        action_count: policy_action.action_count,
+        window_secs,
+        rate_limit,
+        validity,
+    })
+}
+
+//Validity windows and rate limits apply to every call, including cached responses that don't count against the quota
+async fn admit_policy_call(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    endpoint: &PolicyEndpoint,
+    user_id: Uuid,
+    policy_id: Uuid,
+    redis_url: RedisConnWrapper,
+    limits: &PolicyLimits,
+) -> Result<(), AppError> {
+    //Outside its validity window the action is refused outright, and that counts as a violation
+    if let Some(validity) = limits.validity.as_ref() {
+        if let Err(denial) = validity.check(chrono::Utc::now()) {
+            record_policy_violation(
+                db,
                 redis_url.clone(),
-                store_key_model.clone(),
-                policy_action.action_count.to_string().clone(),
-                usize::MAX,
+                twin_id,
+                endpoint,
+                user_id,
+                policy_id,
+                denial.reason,
             )
-            .await?;
-            policy_action.action_count
+            .await;
+            return Err(
+                AppError::new(StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS, denial.message)
+                    .with_details(json!({
+                        "reason": denial.reason,
+                        "timezone": denial.timezone,
+                        "localTime": denial.local_time,
+                    })),
+            );
         }
-    };
+    }
 
-    // dbg!(model_action_count);
+    //Bursts are refused before they are counted against the quota, and are not violations of it
+    if let Some(rate_limit) = limits.rate_limit.as_ref() {
+        let mut store_key_user_rate = "RateLimit:Users:".to_string();
+        store_key_user_rate += &user_id.to_string();
+        store_key_user_rate += ":";
+        store_key_user_rate += &twin_id.to_string();
+        store_key_user_rate += ":";
+        store_key_user_rate += &endpoint.key();
 
+        check_rate_limit(redis_url, store_key_user_rate, rate_limit).await?;
+    }
+
+    Ok(())
+}
+
+//Counts the call against the user's quota, owners can let cached responses skip this
+async fn account_policy_call(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    endpoint: &PolicyEndpoint,
+    user_id: Uuid,
+    policy_id: Uuid,
+    redis_url: RedisConnWrapper,
+    limits: &PolicyLimits,
+) -> Result<QuotaUsage, AppError> {
     //User's redis key
     let mut store_key_user = "Policy:Users:".to_string();
     store_key_user += &user_id.to_string();
     store_key_user += ":";
     store_key_user += &twin_id.to_string();
     store_key_user += ":";
-    store_key_user += &endpoint_id.to_string();
-
-    let user_policy_token = get_token_from_redis(redis_url.clone(), store_key_user.clone()).await?;
-
-    let user_action_count: i32 = match user_policy_token.clone() {
-        token if token != "" => token.parse::<i32>().unwrap_or_default(),
-        _ => {
-            println!("Policy not found in redis");
-            // Get policy_action from db using policy_id and endpoint_id
-            let policy_action = policy_queries::get_policy_action_by_policyid_and_endpoint(
-                db,
-                policy_id,
-                endpoint_id.clone(),
-            )
-            .await?;
-
-            //Match policy_action.reset_frequency_id and multiply the value with policy_action.action_count and assign to an integer variable expires_in
-            let expires_in = match policy_action.reset_frequency_id {
-                1 => 24 * 60,
-                2 => 7 * 26 * 60,
-                3 => 4 * 7 * 26 * 60,
-                4 => 12 * 4 * 7 * 26 * 60,
-                _ => usize::MAX as i32,
-            };
-
-            //Store policy_action in redis
-            let _redis_response = store_token_in_redis(
-                redis_url.clone(),
-                store_key_user.clone(),
-                "0".to_string(),
-                expires_in as usize,
-            )
-            .await?;
-            0
-        }
-    };
+    store_key_user += &endpoint.key();
 
-    // dbg!(user_action_count);
+    //Compare and count in a single atomic step, concurrent calls can't all slip under the quota
+    let usage = consume_quota_in_redis(
+        redis_url.clone(),
+        store_key_user,
+        limits.action_count as i64,
+        limits.window_secs,
+    )
+    .await?;
 
-    //Check if user_action_count is greater than or equal to model_action_count
-    if user_action_count >= model_action_count {
+    if !usage.allowed {
+        record_policy_violation(
+            db,
+            redis_url,
+            twin_id,
+            endpoint,
+            user_id,
+            policy_id,
+            VIOLATION_REASON_QUOTA_EXCEEDED,
+        )
+        .await;
         return Err(AppError::new(
             StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
             "Resource usage policy not satisfied!",
-        ));
+        )
+        .with_details(json!({
+            "used": usage.used,
+            "limit": usage.limit,
+            "resetIn": usage.reset_in,
+        })));
     }
 
-    //Increment user_action_count
-    let expires_in = get_token_ttl(redis_url.clone(), store_key_user.clone()).await?;
+    Ok(usage)
+}
 
-    let _redis_response = store_token_in_redis(
-        redis_url,
-        store_key_user,
-        (user_action_count + 1).to_string(),
-        expires_in as usize,
-    )
-    .await?;
+//Owners' conditions on the request body and the user's attributes, checked before the call is forwarded
+pub async fn check_policy_conditions(
+    db: &DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    twin_id: Uuid,
+    endpoint: &PolicyEndpoint,
+    user_id: Uuid,
+    policy_action: &PolicyActionModel,
+    body: &Bytes,
+) -> Result<(), AppError> {
+    let conditions = match policy_action
+        .conditions
+        .clone()
+        .and_then(|conditions| serde_json::from_value::<Vec<PolicyCondition>>(conditions).ok())
+    {
+        Some(conditions) if !conditions.is_empty() => conditions,
+        _ => return Ok(()),
+    };
+
+    //A body that isn't JSON has none of the fields the conditions look for
+    let body = serde_json::from_slice::<Value>(body).unwrap_or(Value::Null);
+
+    let needs_user = conditions
+        .iter()
+        .any(|condition| condition.source == CONDITION_SOURCE_USER);
+    let user = if needs_user {
+        let user = user_queries::find_user_by_id(db, user_id).await?;
+        let role = role_queries::find_role_by_id(db, user.role_id).await?;
+        json!({
+            "id": user.id,
+            "email": user.email,
+            "role": role.name,
+            "attributes": user.attributes,
+        })
+    } else {
+        Value::Null
+    };
+
+    if let Some(failure) = first_failed_condition(&conditions, &body, &user) {
+        record_policy_violation(
+            db,
+            redis_url,
+            twin_id,
+            endpoint,
+            user_id,
+            policy_action.policy_id,
+            VIOLATION_REASON_CONDITION_FAILED,
+        )
+        .await;
+        return Err(
+            AppError::new(StatusCode::FORBIDDEN, failure.message()).with_details(failure.details())
+        );
+    }
 
-    Ok(true)
+    Ok(())
+}
+
+async fn check_rate_limit(
+    redis_url: RedisConnWrapper,
+    key: String,
+    rate_limit: &RateLimit,
+) -> Result<RateLimitUsage, AppError> {
+    let period_ms = rate_limit.period_secs * 1000;
+    let usage = if rate_limit.algorithm == RATE_LIMIT_TOKEN_BUCKET {
+        consume_token_bucket_in_redis(
+            redis_url,
+            key,
+            rate_limit.burst,
+            period_ms as f64 / rate_limit.requests as f64,
+        )
+        .await?
+    } else {
+        consume_sliding_window_in_redis(redis_url, key, rate_limit.requests, period_ms).await?
+    };
+
+    if usage.allowed {
+        return Ok(usage);
+    }
+
+    let mut policy = format!("{};w={}", rate_limit.requests, rate_limit.period_secs);
+    if rate_limit.algorithm == RATE_LIMIT_TOKEN_BUCKET {
+        policy += &format!(";burst={}", rate_limit.burst);
+    }
+    let retry_after = usage.retry_after_ms.div_ceil(1000).max(1);
+    let headers = [
+        (header::RETRY_AFTER, retry_after.to_string()),
+        (
+            header::HeaderName::from_static("ratelimit-limit"),
+            usage.limit.to_string(),
+        ),
+        (
+            header::HeaderName::from_static("ratelimit-remaining"),
+            usage.remaining.to_string(),
+        ),
+        (
+            header::HeaderName::from_static("ratelimit-reset"),
+            usage.reset_ms.div_ceil(1000).to_string(),
+        ),
+        (header::HeaderName::from_static("ratelimit-policy"), policy),
+    ];
+
+    let error =
+        AppError::new(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").with_details(json!({
+            "limit": usage.limit,
+            "remaining": usage.remaining,
+            "retryAfter": retry_after,
+        }));
+    Err(headers.into_iter().fold(error, |error, (name, value)| {
+        match HeaderValue::from_str(&value) {
+            Ok(value) => error.with_header(name, value),
+            Err(_) => error,
+        }
+    }))
+}
+
+//A denial is answered even when it can't be recorded, so failures here are only logged
+async fn record_policy_violation(
+    db: &DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    twin_id: Uuid,
+    endpoint: &PolicyEndpoint,
+    user_id: Uuid,
+    policy_id: Uuid,
+    reason: &str,
+) {
+    let recorded = async {
+        let policy = policy_queries::find_policy_by_id(db, policy_id).await?;
+        let policy_action = policy_queries::get_policy_action_by_policyid_and_endpoint(
+            db,
+            policy_id,
+            endpoint.component_alias.as_deref(),
+            endpoint.end_point.clone(),
+        )
+        .await?;
+
+        violation_queries::create_policy_violation(
+            db,
+            PolicyViolation {
+                user_id,
+                model_id: policy.model_id,
+                policy_id,
+                action_id: policy_action.id,
+                twin_id,
+                reason: reason.to_string(),
+            },
+        )
+        .await?;
+
+        enforce_block_after(db, redis_url, &policy, user_id, twin_id).await
+    }
+    .await;
+
+    if let Err(error) = recorded {
+        eprintln!("Error recording policy violation: {:?}", error);
+    }
 }
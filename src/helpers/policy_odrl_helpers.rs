@@ -0,0 +1,850 @@
+use axum::http::StatusCode;
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    helpers::{
+        policy_condition_helpers::{PolicyCondition, CONDITION_SOURCE_BODY, CONDITION_SOURCE_USER},
+        policy_document_helpers::{
+            PolicyDocument, PolicyDocumentAction, PolicyDocumentQuota, PolicyDocumentRateLimit,
+            POLICY_DOCUMENT_FORMAT,
+        },
+        policy_mgmt_helpers::RATE_LIMIT_SLIDING_WINDOW,
+        policy_validity_helpers::{HourRange, ValidityWindow},
+    },
+    utilities::app_error::AppError,
+};
+
+pub const ODRL_CONTEXT: &str = "http://www.w3.org/ns/odrl.jsonld";
+const ODRL_NAMESPACE: &str = "http://www.w3.org/ns/odrl/2/";
+//Profile for what core ODRL has no terms for: weekdays, hours, rate limits and payload conditions
+pub const DT_NAMESPACE: &str = "urn:dt:odrl:";
+pub const DT_PROFILE: &str = "urn:dt:odrl:profile";
+//Targets are urn:dt:model:<modelId>[:component:<alias>]:endpoint:<VERB>:<endPoint>
+const TARGET_PREFIX: &str = "urn:dt:model:";
+//ODRL permissions without a count constraint are unlimited
+const UNLIMITED_COUNT: i32 = i32::MAX;
+const QUOTA_RESETS: [&str; 5] = ["daily", "weekly", "monthly", "yearly", "never"];
+const RATE_LIMIT_PERIODS: [&str; 2] = ["second", "minute"];
+
+//Part of an ODRL policy the middleware can't enforce, path points into the imported JSON
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct OdrlUnsupported {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub struct OdrlImport {
+    pub document: PolicyDocument,
+    pub unsupported: Vec<OdrlUnsupported>,
+}
+
+//Reads an ODRL policy compacted with the ODRL context into a policy document.
+//Everything that can't be enforced is collected in unsupported rather than dropped
+pub fn import_odrl_policy(policy: &Value, model_id: Uuid) -> Result<OdrlImport, AppError> {
+    let policy = policy
+        .as_object()
+        .ok_or_else(|| bad_request("ODRL policy must be a JSON object".to_string()))?;
+    let uid = policy
+        .get("uid")
+        .or_else(|| policy.get("@id"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| bad_request("ODRL policy must have a uid".to_string()))?;
+
+    let mut unsupported = vec![];
+    if policy.contains_key("inheritFrom") {
+        unsupported.push(OdrlUnsupported {
+            path: "inheritFrom".to_string(),
+            reason:
+                "Policy inheritance is not supported, import the parent policy's rules directly"
+                    .to_string(),
+        });
+    }
+    if policy.get("conflict").and_then(term).as_deref() == Some("perm") {
+        unsupported.push(OdrlUnsupported {
+            path: "conflict".to_string(),
+            reason: "Prohibitions always take precedence over permissions".to_string(),
+        });
+    }
+    for (index, obligation) in as_list(policy.get("obligation")).into_iter().enumerate() {
+        unsupported.push(OdrlUnsupported {
+            path: format!("obligation[{}]", index),
+            reason: format!(
+                "Obligation {} cannot be enforced by the middleware",
+                rule_action(obligation).unwrap_or_else(|| "without an action".to_string())
+            ),
+        });
+    }
+
+    let mut permissions = vec![];
+    for (index, permission) in as_list(policy.get("permission")).into_iter().enumerate() {
+        let path = format!("permission[{}]", index);
+        let target = read_rule(permission, &path, model_id, &mut unsupported)?;
+
+        let mut builder = ActionBuilder::new(target, permission);
+        for (constraint_index, constraint) in as_list(permission.get("constraint"))
+            .into_iter()
+            .enumerate()
+        {
+            builder.apply(
+                constraint,
+                false,
+                &format!("{}.constraint[{}]", path, constraint_index),
+                &mut unsupported,
+            );
+        }
+        for (duty_index, duty) in as_list(permission.get("duty")).into_iter().enumerate() {
+            unsupported.push(OdrlUnsupported {
+                path: format!("{}.duty[{}]", path, duty_index),
+                reason: format!(
+                    "Duty {} cannot be enforced by the middleware",
+                    rule_action(duty).unwrap_or_else(|| "without an action".to_string())
+                ),
+            });
+        }
+
+        permissions.push(builder);
+    }
+
+    //Endpoints without a permission are refused already, prohibitions only narrow the permitted ones
+    for (index, prohibition) in as_list(policy.get("prohibition")).into_iter().enumerate() {
+        let path = format!("prohibition[{}]", index);
+        let target = read_rule(prohibition, &path, model_id, &mut unsupported)?;
+        if prohibition.get("remedy").is_some() {
+            unsupported.push(OdrlUnsupported {
+                path: format!("{}.remedy", path),
+                reason: "Remedies cannot be enforced by the middleware".to_string(),
+            });
+        }
+
+        let Some(builder) = permissions
+            .iter_mut()
+            .find(|builder| builder.target == target)
+        else {
+            continue;
+        };
+
+        match as_list(prohibition.get("constraint")).as_slice() {
+            [] => {
+                return Err(bad_request(format!(
+                    "{} prohibits {} {} outright while a permission allows it",
+                    path, target.verb, target.end_point
+                )))
+            }
+            [constraint] => builder.apply(
+                constraint,
+                true,
+                &format!("{}.constraint[0]", path),
+                &mut unsupported,
+            ),
+            _ => unsupported.push(OdrlUnsupported {
+                path: format!("{}.constraint", path),
+                reason: "Prohibitions with several constraints can't become permission conditions"
+                    .to_string(),
+            }),
+        }
+    }
+
+    let document = PolicyDocument {
+        format: POLICY_DOCUMENT_FORMAT.to_string(),
+        name: policy
+            .get("dct:title")
+            .and_then(Value::as_str)
+            .unwrap_or(uid)
+            .to_string(),
+        description: policy
+            .get("dct:description")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Imported from ODRL policy {}", uid)),
+        policy_version: None,
+        block_after: policy
+            .get("dt:blockAfter")
+            .and_then(Value::as_i64)
+            .unwrap_or_default() as i32,
+        actions: permissions.into_iter().map(ActionBuilder::build).collect(),
+    };
+    document.validate()?;
+
+    Ok(OdrlImport {
+        document,
+        unsupported,
+    })
+}
+
+//Writes a policy document as an ODRL Set.
+//Streams, caching and schemas are operational settings rather than usage terms and stay out
+pub fn export_odrl_policy(document: &PolicyDocument, model_id: Uuid) -> Value {
+    let mut policy = json!({
+        "@context": [
+            ODRL_CONTEXT,
+            {
+                "dt": DT_NAMESPACE,
+                "dct": "http://purl.org/dc/terms/",
+                "xsd": "http://www.w3.org/2001/XMLSchema#",
+            },
+        ],
+        "@type": "Set",
+        "uid": format!(
+            "{}{}:policy:{}",
+            TARGET_PREFIX,
+            model_id,
+            document
+                .policy_version
+                .map(|policy_version| policy_version.to_string())
+                .unwrap_or_else(|| "draft".to_string())
+        ),
+        "profile": DT_PROFILE,
+        "dct:title": document.name,
+        "dct:description": document.description,
+        "permission": document
+            .actions
+            .iter()
+            .map(|action| export_permission(action, model_id))
+            .collect::<Vec<_>>(),
+    });
+    if document.block_after > 0 {
+        policy["dt:blockAfter"] = json!(document.block_after);
+    }
+
+    policy
+}
+
+fn export_permission(action: &PolicyDocumentAction, model_id: Uuid) -> Value {
+    let mut constraints = vec![];
+
+    if action.quota.count != UNLIMITED_COUNT {
+        let mut count = json!({
+            "leftOperand": "count",
+            "operator": "lteq",
+            "rightOperand": action.quota.count,
+        });
+        if action.quota.reset != "never" {
+            count["unit"] = json!(format!("dt:{}", action.quota.reset.to_lowercase()));
+        }
+        constraints.push(count);
+    }
+
+    if let Some(validity) = action.validity.as_ref() {
+        for (date, operator) in [(&validity.from, "gteq"), (&validity.until, "lteq")] {
+            if let Some(date) = date {
+                constraints.push(json!({
+                    "leftOperand": "dateTime",
+                    "operator": operator,
+                    "rightOperand": { "@value": date, "@type": "xsd:date" },
+                }));
+            }
+        }
+        if let Some(timezone) = validity.timezone.as_ref() {
+            constraints.push(json!({
+                "leftOperand": "dt:timezone",
+                "operator": "eq",
+                "rightOperand": timezone,
+            }));
+        }
+        if let Some(weekdays) = validity.weekdays.as_ref() {
+            constraints.push(json!({
+                "leftOperand": "dt:weekday",
+                "operator": "isAnyOf",
+                "rightOperand": weekdays,
+            }));
+        }
+        if let Some(hours) = validity.hours.as_ref() {
+            constraints.push(json!({
+                "leftOperand": "dt:timeOfDay",
+                "operator": "isAnyOf",
+                "rightOperand": hours
+                    .iter()
+                    .map(|range| format!("{}-{}", range.from, range.to))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+    }
+
+    if let Some(rate_limit) = action.rate_limit.as_ref() {
+        let mut constraint = json!({
+            "leftOperand": "dt:rateLimit",
+            "operator": "lteq",
+            "rightOperand": rate_limit.requests,
+            "unit": format!("dt:{}", rate_limit.period.to_lowercase()),
+            "dt:algorithm": rate_limit.algorithm.to_lowercase(),
+        });
+        if let Some(burst) = rate_limit.burst {
+            constraint["dt:burst"] = json!(burst);
+        }
+        constraints.push(constraint);
+    }
+
+    for condition in &action.conditions {
+        let left_operand = format!("dt:{}.{}", condition.source, condition.field);
+        let operators: Vec<(&str, Value)> = match condition.op.as_str() {
+            "between" => match condition.value.as_array().map(Vec::as_slice) {
+                Some([min, max]) => vec![("gteq", min.clone()), ("lteq", max.clone())],
+                _ => vec![],
+            },
+            "exists" => vec![(
+                "dt:exists",
+                json!(condition.value.as_bool().unwrap_or(true)),
+            )],
+            op => CONDITION_OPERATORS
+                .iter()
+                .find(|(_, condition_op)| *condition_op == op)
+                .map(|(operator, _)| vec![(*operator, condition.value.clone())])
+                .unwrap_or_default(),
+        };
+        for (operator, right_operand) in operators {
+            constraints.push(json!({
+                "leftOperand": left_operand,
+                "operator": operator,
+                "rightOperand": right_operand,
+            }));
+        }
+    }
+
+    let mut permission = json!({
+        "target": target_iri(
+            model_id,
+            action.component_alias.as_deref(),
+            &action.verb,
+            &action.end_point
+        ),
+        "action": "use",
+        "dt:description": action.description,
+    });
+    if !constraints.is_empty() {
+        permission["constraint"] = Value::Array(constraints);
+    }
+
+    permission
+}
+
+//ODRL operators and the condition operators they map to
+const CONDITION_OPERATORS: [(&str, &str); 9] = [
+    ("eq", "eq"),
+    ("neq", "ne"),
+    ("lt", "lt"),
+    ("lteq", "lte"),
+    ("gt", "gt"),
+    ("gteq", "gte"),
+    ("isAnyOf", "in"),
+    ("isNoneOf", "not_in"),
+    ("dt:matches", "matches"),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+struct Target {
+    component_alias: Option<String>,
+    verb: String,
+    end_point: String,
+}
+
+fn target_iri(
+    model_id: Uuid,
+    component_alias: Option<&str>,
+    verb: &str,
+    end_point: &str,
+) -> String {
+    let component = component_alias
+        .map(|component_alias| format!("component:{}:", component_alias))
+        .unwrap_or_default();
+
+    format!(
+        "{}{}:{}endpoint:{}:{}",
+        TARGET_PREFIX,
+        model_id,
+        component,
+        verb.to_uppercase(),
+        end_point
+    )
+}
+
+fn parse_target(iri: &str, model_id: Uuid) -> Result<Target, String> {
+    let invalid = || {
+        format!(
+            "target {} is not a twin endpoint ({}<modelId>[:component:<alias>]:endpoint:<VERB>:<endPoint>)",
+            iri, TARGET_PREFIX
+        )
+    };
+
+    let (target_model_id, rest) = iri
+        .strip_prefix(TARGET_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .ok_or_else(invalid)?;
+    if Uuid::parse_str(target_model_id).ok() != Some(model_id) {
+        return Err(format!(
+            "target {} belongs to model {}, not {}",
+            iri, target_model_id, model_id
+        ));
+    }
+
+    let (component_alias, rest) = match rest.strip_prefix("component:") {
+        Some(rest) => {
+            let (component_alias, rest) = rest.split_once(':').ok_or_else(invalid)?;
+            (Some(component_alias.to_string()), rest)
+        }
+        None => (None, rest),
+    };
+    let (verb, end_point) = rest
+        .strip_prefix("endpoint:")
+        .and_then(|rest| rest.split_once(':'))
+        .filter(|(verb, end_point)| !verb.is_empty() && !end_point.is_empty())
+        .ok_or_else(invalid)?;
+
+    Ok(Target {
+        component_alias,
+        verb: verb.to_uppercase(),
+        end_point: end_point.to_string(),
+    })
+}
+
+//Target and action are shared by permissions and prohibitions
+fn read_rule(
+    rule: &Value,
+    path: &str,
+    model_id: Uuid,
+    unsupported: &mut Vec<OdrlUnsupported>,
+) -> Result<Target, AppError> {
+    let target = rule
+        .get("target")
+        .and_then(|target| {
+            target
+                .as_str()
+                .or_else(|| target.get("uid").and_then(Value::as_str))
+                .or_else(|| target.get("@id").and_then(Value::as_str))
+        })
+        .ok_or_else(|| bad_request(format!("{}.target is required", path)))?;
+    let target = parse_target(target, model_id)
+        .map_err(|message| bad_request(format!("{}.target: {}", path, message)))?;
+
+    let action =
+        rule_action(rule).ok_or_else(|| bad_request(format!("{}.action is required", path)))?;
+    if action != "use" && action != "execute" {
+        unsupported.push(OdrlUnsupported {
+            path: format!("{}.action", path),
+            reason: format!(
+                "Action {} has no meaning for twin endpoints, use odrl:use",
+                action
+            ),
+        });
+    }
+    if rule
+        .get("action")
+        .and_then(|action| action.get("refinement"))
+        .is_some()
+    {
+        unsupported.push(OdrlUnsupported {
+            path: format!("{}.action.refinement", path),
+            reason: "Action refinements are not supported, use constraints instead".to_string(),
+        });
+    }
+    if rule.get("assignee").is_some() {
+        unsupported.push(OdrlUnsupported {
+            path: format!("{}.assignee", path),
+            reason: "Policies apply to every subscriber of the model, assignees are not supported"
+                .to_string(),
+        });
+    }
+
+    Ok(target)
+}
+
+fn rule_action(rule: &Value) -> Option<String> {
+    let action = rule.get("action")?;
+    term(action).or_else(|| action.get("rdf:value").and_then(term))
+}
+
+//Accumulates one permission's constraints into a policy document action
+struct ActionBuilder {
+    target: Target,
+    description: Option<String>,
+    count: Option<i32>,
+    reset: Option<String>,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    timezone: Option<String>,
+    weekdays: Option<Vec<String>>,
+    hours: Option<Vec<HourRange>>,
+    rate_limit: Option<PolicyDocumentRateLimit>,
+    conditions: Vec<PolicyCondition>,
+}
+
+impl ActionBuilder {
+    fn new(target: Target, permission: &Value) -> Self {
+        Self {
+            target,
+            description: permission
+                .get("dt:description")
+                .or_else(|| permission.get("dct:description"))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            count: None,
+            reset: None,
+            from: None,
+            until: None,
+            timezone: None,
+            weekdays: None,
+            hours: None,
+            rate_limit: None,
+            conditions: vec![],
+        }
+    }
+
+    //A prohibition's constraint is negated into a permission constraint
+    fn apply(
+        &mut self,
+        constraint: &Value,
+        negate: bool,
+        path: &str,
+        unsupported: &mut Vec<OdrlUnsupported>,
+    ) {
+        if let Err(reason) = self.try_apply(constraint, negate) {
+            unsupported.push(OdrlUnsupported {
+                path: path.to_string(),
+                reason,
+            });
+        }
+    }
+
+    fn try_apply(&mut self, constraint: &Value, negate: bool) -> Result<(), String> {
+        if ["and", "or", "xone", "andSequence"]
+            .iter()
+            .any(|logical| constraint.get(logical).is_some())
+        {
+            return Err(
+                "Logical constraints are not supported, list the constraints directly".to_string(),
+            );
+        }
+
+        let left_operand = constraint
+            .get("leftOperand")
+            .and_then(term)
+            .ok_or_else(|| "Constraint has no leftOperand".to_string())?;
+        let mut operator = constraint
+            .get("operator")
+            .and_then(term)
+            .ok_or_else(|| "Constraint has no operator".to_string())?;
+        let right_operand = constraint
+            .get("rightOperand")
+            .map(literal)
+            .ok_or_else(|| "Constraint has no rightOperand".to_string())?;
+        let unit = constraint.get("unit").and_then(term);
+
+        let negatable = left_operand == "dateTime"
+            || left_operand.starts_with("dt:body.")
+            || left_operand.starts_with("dt:user.");
+        if negate {
+            if !negatable {
+                return Err(format!(
+                    "leftOperand {} is not supported in prohibitions",
+                    left_operand
+                ));
+            }
+            if operator != "dt:exists" {
+                operator = negated_operator(&operator)
+                    .ok_or_else(|| {
+                        format!("Operator {} can't be negated for a prohibition", operator)
+                    })?
+                    .to_string();
+            }
+        }
+
+        match left_operand.as_str() {
+            "count" => self.apply_count(&operator, right_operand, unit),
+            "dateTime" => self.apply_date_time(&operator, right_operand),
+            "dt:timezone" => {
+                expect_operator(&operator, "eq", &left_operand)?;
+                let timezone = right_operand
+                    .as_str()
+                    .ok_or_else(|| "dt:timezone must be a timezone name".to_string())?;
+                self.timezone = Some(timezone.to_string());
+                Ok(())
+            }
+            "dt:weekday" => {
+                expect_operator(&operator, "isAnyOf", &left_operand)?;
+                self.weekdays = Some(string_list(right_operand, &left_operand)?);
+                Ok(())
+            }
+            "dt:timeOfDay" => {
+                expect_operator(&operator, "isAnyOf", &left_operand)?;
+                let hours = string_list(right_operand, &left_operand)?
+                    .into_iter()
+                    .map(|range| {
+                        range
+                            .split_once('-')
+                            .map(|(from, to)| HourRange {
+                                from: from.to_string(),
+                                to: to.to_string(),
+                            })
+                            .ok_or_else(|| format!("dt:timeOfDay {} is not HH:MM-HH:MM", range))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.hours = Some(hours);
+                Ok(())
+            }
+            "dt:rateLimit" => self.apply_rate_limit(&operator, right_operand, unit, constraint),
+            _ if negatable => self.apply_condition(&left_operand, &operator, right_operand, negate),
+            _ => Err(format!("leftOperand {} is not supported", left_operand)),
+        }
+    }
+
+    fn apply_count(
+        &mut self,
+        operator: &str,
+        right_operand: &Value,
+        unit: Option<String>,
+    ) -> Result<(), String> {
+        if self.count.is_some() {
+            return Err("Only one count constraint per permission is supported".to_string());
+        }
+        let count = right_operand
+            .as_i64()
+            .ok_or_else(|| "count must be an integer".to_string())?;
+        let count = match operator {
+            "lteq" => count,
+            "lt" => count - 1,
+            _ => {
+                return Err(format!(
+                    "Operator {} is not supported for count, use lteq",
+                    operator
+                ))
+            }
+        };
+        if !(0..UNLIMITED_COUNT as i64).contains(&count) {
+            return Err(format!("count {} is out of range", count));
+        }
+
+        //The unit names the window the count resets in, counts without one never reset
+        let reset = match unit {
+            Some(unit) => unit
+                .strip_prefix("dt:")
+                .filter(|reset| QUOTA_RESETS.contains(reset))
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    format!(
+                        "count unit {} is not a reset window (dt:daily, dt:weekly, dt:monthly, dt:yearly or dt:never)",
+                        unit
+                    )
+                })?,
+            None => "never".to_string(),
+        };
+
+        self.count = Some(count as i32);
+        self.reset = Some(reset);
+        Ok(())
+    }
+
+    //Validity windows are kept per day, so dateTime constraints must compare against an xsd:date
+    fn apply_date_time(&mut self, operator: &str, right_operand: &Value) -> Result<(), String> {
+        let date = right_operand
+            .as_str()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .ok_or_else(|| {
+                "dateTime is enforced per day, the rightOperand must be an xsd:date (YYYY-MM-DD)"
+                    .to_string()
+            })?;
+
+        let (from, until) = match operator {
+            "gteq" => (Some(date), None),
+            "gt" => (Some(date + Duration::days(1)), None),
+            "lteq" => (None, Some(date)),
+            "lt" => (None, Some(date - Duration::days(1))),
+            "eq" => (Some(date), Some(date)),
+            _ => {
+                return Err(format!(
+                    "Operator {} is not supported for dateTime",
+                    operator
+                ))
+            }
+        };
+        if let Some(from) = from {
+            self.from = Some(self.from.map_or(from, |current| current.max(from)));
+        }
+        if let Some(until) = until {
+            self.until = Some(self.until.map_or(until, |current| current.min(until)));
+        }
+
+        Ok(())
+    }
+
+    fn apply_rate_limit(
+        &mut self,
+        operator: &str,
+        right_operand: &Value,
+        unit: Option<String>,
+        constraint: &Value,
+    ) -> Result<(), String> {
+        expect_operator(operator, "lteq", "dt:rateLimit")?;
+        let requests = right_operand
+            .as_i64()
+            .and_then(|requests| i32::try_from(requests).ok())
+            .ok_or_else(|| "dt:rateLimit must be a number of requests".to_string())?;
+        let period = unit
+            .as_deref()
+            .and_then(|unit| unit.strip_prefix("dt:"))
+            .filter(|period| RATE_LIMIT_PERIODS.contains(period))
+            .ok_or_else(|| "dt:rateLimit unit must be dt:second or dt:minute".to_string())?;
+
+        self.rate_limit = Some(PolicyDocumentRateLimit {
+            algorithm: constraint
+                .get("dt:algorithm")
+                .and_then(Value::as_str)
+                .unwrap_or(RATE_LIMIT_SLIDING_WINDOW)
+                .to_string(),
+            requests,
+            period: period.to_string(),
+            burst: constraint
+                .get("dt:burst")
+                .and_then(Value::as_i64)
+                .map(|burst| burst as i32),
+        });
+        Ok(())
+    }
+
+    fn apply_condition(
+        &mut self,
+        left_operand: &str,
+        operator: &str,
+        right_operand: &Value,
+        negate: bool,
+    ) -> Result<(), String> {
+        let (source, field) = left_operand
+            .trim_start_matches("dt:")
+            .split_once('.')
+            .filter(|(source, _)| [CONDITION_SOURCE_BODY, CONDITION_SOURCE_USER].contains(source))
+            .ok_or_else(|| format!("leftOperand {} is not supported", left_operand))?;
+
+        let (op, value) = if operator == "dt:exists" {
+            let exists = right_operand.as_bool().unwrap_or(true);
+            ("exists", json!(exists != negate))
+        } else {
+            let op = CONDITION_OPERATORS
+                .iter()
+                .find(|(odrl_operator, _)| *odrl_operator == operator)
+                .map(|(_, op)| *op)
+                .ok_or_else(|| format!("Operator {} is not supported", operator))?;
+            (op, right_operand.clone())
+        };
+
+        self.conditions.push(PolicyCondition {
+            source: source.to_string(),
+            field: field.to_string(),
+            op: op.to_string(),
+            value,
+        });
+        Ok(())
+    }
+
+    fn build(self) -> PolicyDocumentAction {
+        let has_validity = self.from.is_some()
+            || self.until.is_some()
+            || self.timezone.is_some()
+            || self.weekdays.is_some()
+            || self.hours.is_some();
+        let validity = has_validity.then(|| ValidityWindow {
+            timezone: self.timezone,
+            from: self.from.map(|from| from.format("%Y-%m-%d").to_string()),
+            until: self.until.map(|until| until.format("%Y-%m-%d").to_string()),
+            weekdays: self.weekdays,
+            hours: self.hours,
+        });
+
+        PolicyDocumentAction {
+            description: self
+                .description
+                .unwrap_or_else(|| format!("{} {}", self.target.verb, self.target.end_point)),
+            end_point: self.target.end_point,
+            component_alias: self.target.component_alias,
+            verb: self.target.verb,
+            quota: PolicyDocumentQuota {
+                count: self.count.unwrap_or(UNLIMITED_COUNT),
+                reset: self.reset.unwrap_or_else(|| "never".to_string()),
+            },
+            rate_limit: self.rate_limit,
+            validity,
+            conditions: self.conditions,
+            streams: None,
+            cache: None,
+            request_schema: None,
+            response_schema: None,
+            response_validation: None,
+        }
+    }
+}
+
+fn negated_operator(operator: &str) -> Option<&'static str> {
+    match operator {
+        "eq" => Some("neq"),
+        "neq" => Some("eq"),
+        "lt" => Some("gteq"),
+        "gteq" => Some("lt"),
+        "lteq" => Some("gt"),
+        "gt" => Some("lteq"),
+        "isAnyOf" => Some("isNoneOf"),
+        "isNoneOf" => Some("isAnyOf"),
+        _ => None,
+    }
+}
+
+fn expect_operator(operator: &str, expected: &str, left_operand: &str) -> Result<(), String> {
+    if operator != expected {
+        return Err(format!(
+            "Operator {} is not supported for {}, use {}",
+            operator, left_operand, expected
+        ));
+    }
+
+    Ok(())
+}
+
+fn string_list(value: &Value, left_operand: &str) -> Result<Vec<String>, String> {
+    as_list(Some(value))
+        .into_iter()
+        .map(|item| {
+            literal(item)
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("{} must be a list of strings", left_operand))
+        })
+        .collect()
+}
+
+//ODRL and profile terms come as prefixed names, full IRIs or {"@id": ...}
+fn term(value: &Value) -> Option<String> {
+    let raw = value
+        .as_str()
+        .or_else(|| value.get("@id").and_then(Value::as_str))?;
+
+    Some(
+        if let Some(odrl_term) = raw
+            .strip_prefix(ODRL_NAMESPACE)
+            .or_else(|| raw.strip_prefix("odrl:"))
+        {
+            odrl_term.to_string()
+        } else if let Some(dt_term) = raw.strip_prefix(DT_NAMESPACE) {
+            format!("dt:{}", dt_term)
+        } else {
+            raw.to_string()
+        },
+    )
+}
+
+//Typed literals ({"@value": ..., "@type": ...}) are compared by their value
+fn literal(value: &Value) -> &Value {
+    value.get("@value").unwrap_or(value)
+}
+
+//JSON-LD allows a single object wherever a list is expected
+fn as_list(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(Value::Null) | None => vec![],
+        Some(item) => vec![item],
+    }
+}
+
+fn bad_request(message: String) -> AppError {
+    AppError::new(StatusCode::BAD_REQUEST, message)
+}
@@ -0,0 +1,291 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utilities::app_error::AppError;
+
+//Cached in place of a window for actions that can be used at any time
+const NO_VALIDITY_WINDOW: &str = "none";
+
+//When a policy action may be used, evaluated in its own timezone (UTC by default).
+//Dates are inclusive days, hour ranges include their start and exclude their end
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidityWindow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    //YYYY-MM-DD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    //mon, tue, ... or full day names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weekdays: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hours: Option<Vec<HourRange>>,
+}
+
+//HH:MM, a range whose end is before its start runs past midnight
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HourRange {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValidityDenial {
+    //not_yet_valid, expired, weekday_not_allowed or outside_hours
+    pub reason: &'static str,
+    pub message: String,
+    pub timezone: String,
+    pub local_time: String,
+}
+
+impl ValidityWindow {
+    //Checks a window sent with a new policy action and returns it normalised
+    pub fn from_request(value: &Value) -> Result<Self, AppError> {
+        let window = serde_json::from_value::<Self>(value.clone()).map_err(|error| {
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("validity is not a valid window: {}", error),
+            )
+        })?;
+
+        window.timezone()?;
+        let from = window
+            .from
+            .as_deref()
+            .map(|from| parse_date(from, "validity.from"))
+            .transpose()?;
+        let until = window
+            .until
+            .as_deref()
+            .map(|until| parse_date(until, "validity.until"))
+            .transpose()?;
+        if let (Some(from), Some(until)) = (from, until) {
+            if from > until {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    "validity.from must not be after validity.until",
+                ));
+            }
+        }
+
+        let weekdays = window.weekdays()?;
+        if weekdays
+            .as_ref()
+            .is_some_and(|weekdays| weekdays.is_empty())
+        {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "validity.weekdays cannot be empty",
+            ));
+        }
+
+        let hours = window.hours()?;
+        if hours.as_ref().is_some_and(|hours| hours.is_empty()) {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "validity.hours cannot be empty",
+            ));
+        }
+        if hours
+            .iter()
+            .flatten()
+            .any(|(hours_from, hours_to)| hours_from == hours_to)
+        {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "validity.hours ranges cannot start and end at the same time",
+            ));
+        }
+
+        Ok(Self {
+            weekdays: weekdays.map(|weekdays| {
+                weekdays
+                    .iter()
+                    .map(|weekday| weekday.to_string().to_lowercase())
+                    .collect()
+            }),
+            ..window
+        })
+    }
+
+    //Stored in redis as JSON
+    pub fn to_token(window: Option<&Self>) -> String {
+        window
+            .and_then(|window| serde_json::to_string(window).ok())
+            .unwrap_or_else(|| NO_VALIDITY_WINDOW.to_string())
+    }
+
+    //None when the token is missing or unreadable, Some(None) when the action has no window
+    pub fn from_token(token: &str) -> Option<Option<Self>> {
+        if token == NO_VALIDITY_WINDOW {
+            return Some(None);
+        }
+
+        serde_json::from_str::<Self>(token).ok().map(Some)
+    }
+
+    pub fn check(&self, now: DateTime<Utc>) -> Result<(), ValidityDenial> {
+        //Windows are validated when the policy is created, anything unreadable here is skipped
+        let timezone = self.timezone().unwrap_or(Tz::UTC);
+        let local = now.with_timezone(&timezone);
+        let denial = |reason: &'static str, message: String| ValidityDenial {
+            reason,
+            message,
+            timezone: timezone.name().to_string(),
+            local_time: local.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        };
+
+        let today = local.date_naive();
+        if let Some(from) = self
+            .from
+            .as_deref()
+            .and_then(|from| parse_date(from, "").ok())
+        {
+            if today < from {
+                return Err(denial(
+                    "not_yet_valid",
+                    format!(
+                        "Policy action is only valid from {} ({})",
+                        from,
+                        timezone.name()
+                    ),
+                ));
+            }
+        }
+        if let Some(until) = self
+            .until
+            .as_deref()
+            .and_then(|until| parse_date(until, "").ok())
+        {
+            if today > until {
+                return Err(denial(
+                    "expired",
+                    format!(
+                        "Policy action expired after {} ({})",
+                        until,
+                        timezone.name()
+                    ),
+                ));
+            }
+        }
+
+        if let Ok(Some(weekdays)) = self.weekdays() {
+            if !weekdays.contains(&local.weekday()) {
+                return Err(denial(
+                    "weekday_not_allowed",
+                    format!(
+                        "Policy action is only available on {} ({})",
+                        weekdays
+                            .iter()
+                            .map(|weekday| weekday.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        timezone.name()
+                    ),
+                ));
+            }
+        }
+
+        if let Ok(Some(hours)) = self.hours() {
+            let time = local.time();
+            let within = hours.iter().any(|(hours_from, hours_to)| {
+                if hours_from < hours_to {
+                    *hours_from <= time && time < *hours_to
+                } else {
+                    time >= *hours_from || time < *hours_to
+                }
+            });
+            if !within {
+                return Err(denial(
+                    "outside_hours",
+                    format!(
+                        "Policy action is only available {} ({})",
+                        hours
+                            .iter()
+                            .map(|(hours_from, hours_to)| format!(
+                                "{}-{}",
+                                hours_from.format("%H:%M"),
+                                hours_to.format("%H:%M")
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        timezone.name()
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn timezone(&self) -> Result<Tz, AppError> {
+        match self.timezone.as_deref() {
+            Some(timezone) => timezone.parse::<Tz>().map_err(|_| {
+                AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown timezone: {}", timezone),
+                )
+            }),
+            None => Ok(Tz::UTC),
+        }
+    }
+
+    fn weekdays(&self) -> Result<Option<Vec<Weekday>>, AppError> {
+        self.weekdays
+            .as_ref()
+            .map(|weekdays| {
+                weekdays
+                    .iter()
+                    .map(|weekday| {
+                        weekday.parse::<Weekday>().map_err(|_| {
+                            AppError::new(
+                                StatusCode::BAD_REQUEST,
+                                format!("Unknown weekday: {}", weekday),
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+
+    fn hours(&self) -> Result<Option<Vec<(NaiveTime, NaiveTime)>>, AppError> {
+        self.hours
+            .as_ref()
+            .map(|hours| {
+                hours
+                    .iter()
+                    .map(|range| {
+                        Ok((
+                            parse_time(&range.from, "validity.hours.from")?,
+                            parse_time(&range.to, "validity.hours.to")?,
+                        ))
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+fn parse_date(value: &str, name: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!("{} must be a YYYY-MM-DD date", name),
+        )
+    })
+}
+
+fn parse_time(value: &str, name: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| {
+        AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!("{} must be an HH:MM time", name),
+        )
+    })
+}
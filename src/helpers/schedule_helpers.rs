@@ -0,0 +1,253 @@
+use std::{str::FromStr, time::Duration};
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use hyper::body::Bytes;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    database::core_twin_schedule,
+    helpers::twin_invocation_helpers::{get_running_user_twin, invoke_twin_endpoint, TwinRequest},
+    queries::schedule_queries,
+    utilities::{
+        app_error::AppError,
+        redis_connection_wrapper::RedisConnWrapper,
+        redis_helper::{get_redis_list, push_to_redis_list},
+        upstream_client::UpstreamClient,
+    },
+};
+
+const DEFAULT_SCHEDULE_TICK_SECS: u64 = 15;
+const DEFAULT_SCHEDULE_HISTORY: isize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduleRunRecord {
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    #[serde(rename = "finishedAt")]
+    pub finished_at: DateTime<Utc>,
+    #[serde(rename = "statusCode")]
+    pub status_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+//Accept the usual five field form by adding the seconds field the cron crate expects
+pub fn parse_cron(expression: &str) -> Result<Schedule, AppError> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+
+    Schedule::from_str(&expression).map_err(|error| {
+        eprintln!("Error parsing cron expression: {:?}", error);
+        AppError::new(StatusCode::BAD_REQUEST, "Invalid cron expression")
+    })
+}
+
+pub fn next_run_after(
+    schedule: &Schedule,
+    after: DateTime<Utc>,
+    ends_at: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    schedule
+        .after(&after)
+        .next()
+        .filter(|next_run| ends_at.is_none_or(|ends_at| *next_run <= ends_at))
+}
+
+//Schedules firing more often than SCHEDULE_MIN_INTERVAL_SECS would burn through quotas, reject them up front
+pub fn check_schedule_interval(schedule: &Schedule) -> Result<(), AppError> {
+    let min_interval = std::env::var("SCHEDULE_MIN_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.trim().parse::<i64>().ok())
+        .unwrap_or(60);
+
+    let runs = schedule.upcoming(Utc).take(10).collect::<Vec<_>>();
+    let too_frequent = runs
+        .windows(2)
+        .any(|pair| (pair[1] - pair[0]).num_seconds() < min_interval);
+
+    if too_frequent {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Schedules cannot run more often than every {} seconds",
+                min_interval
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+//Poll for due schedules; claiming a run in the database keeps several instances from firing it twice
+pub fn start_schedule_runner(
+    db: DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: UpstreamClient,
+) {
+    let tick = std::env::var("SCHEDULE_TICK_SECS")
+        .ok()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_SCHEDULE_TICK_SECS);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(tick));
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now();
+            let schedules = match schedule_queries::get_due_schedules(&db, now).await {
+                Ok(schedules) => schedules,
+                Err(error) => {
+                    eprintln!("Error loading due schedules: {:?}", error);
+                    continue;
+                }
+            };
+
+            for schedule in schedules {
+                let next_run_at = parse_cron(&schedule.cron_expression).ok().and_then(|cron| {
+                    next_run_after(&cron, now, schedule.ends_at.map(|ends_at| ends_at.into()))
+                });
+
+                match schedule_queries::claim_schedule_run(&db, &schedule, next_run_at, now).await {
+                    Ok(true) => {
+                        tokio::spawn(run_schedule(
+                            db.clone(),
+                            redis_url.clone(),
+                            client.clone(),
+                            schedule,
+                        ));
+                    }
+                    Ok(false) => {}
+                    Err(error) => eprintln!("Error claiming schedule {}: {:?}", schedule.id, error),
+                }
+            }
+        }
+    });
+}
+
+//Runs as the user who created the schedule, so their policy quotas apply like any other call
+async fn run_schedule(
+    db: DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: UpstreamClient,
+    schedule: core_twin_schedule::Model,
+) {
+    let started_at = Utc::now();
+
+    let result = async {
+        let twin = get_running_user_twin(&db, schedule.twin_id, schedule.created_by).await?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let body = schedule
+            .body
+            .as_ref()
+            .map(|body| Bytes::from(body.to_string()))
+            .unwrap_or_default();
+
+        invoke_twin_endpoint(
+            &db,
+            redis_url.clone(),
+            &client,
+            schedule.created_by,
+            &twin,
+            TwinRequest {
+                component_alias: schedule.component_alias.clone(),
+                endpoint_id: schedule.end_point.clone(),
+                headers,
+                body,
+            },
+        )
+        .await
+    }
+    .await;
+
+    let record = match result {
+        Ok(response) => ScheduleRunRecord {
+            started_at,
+            finished_at: Utc::now(),
+            status_code: response.status.as_u16(),
+            body: Some(
+                serde_json::from_slice::<Value>(&response.body).unwrap_or_else(|_| {
+                    Value::String(String::from_utf8_lossy(&response.body).to_string())
+                }),
+            ),
+            error: None,
+        },
+        Err(error) => ScheduleRunRecord {
+            started_at,
+            finished_at: Utc::now(),
+            status_code: error.code().as_u16(),
+            body: None,
+            error: Some(error.message().to_string()),
+        },
+    };
+
+    let failed = record.error.is_some()
+        || !StatusCode::from_u16(record.status_code)
+            .map(|status| status.is_success())
+            .unwrap_or(false);
+    let key = if failed {
+        schedule_history_key(schedule.id, "Failures")
+    } else {
+        schedule_history_key(schedule.id, "Results")
+    };
+
+    let value = serde_json::to_string(&record).unwrap_or_default();
+    if let Err(error) = push_to_redis_list(redis_url, key, value, schedule_history_len()).await {
+        eprintln!("Error storing run of schedule {}: {:?}", schedule.id, error);
+    }
+}
+
+//Last runs of a schedule, newest first: (results, failures)
+pub async fn get_schedule_history(
+    redis_url: RedisConnWrapper,
+    schedule_id: Uuid,
+) -> Result<(Vec<ScheduleRunRecord>, Vec<ScheduleRunRecord>), AppError> {
+    let decode = |values: Vec<String>| {
+        values
+            .iter()
+            .filter_map(|value| serde_json::from_str::<ScheduleRunRecord>(value).ok())
+            .collect::<Vec<_>>()
+    };
+
+    let results = get_redis_list(
+        redis_url.clone(),
+        schedule_history_key(schedule_id, "Results"),
+    )
+    .await?;
+    let failures = get_redis_list(redis_url, schedule_history_key(schedule_id, "Failures")).await?;
+
+    Ok((decode(results), decode(failures)))
+}
+
+fn schedule_history_len() -> isize {
+    std::env::var("SCHEDULE_RESULT_HISTORY")
+        .ok()
+        .and_then(|len| len.trim().parse::<isize>().ok())
+        .filter(|len| *len > 0)
+        .unwrap_or(DEFAULT_SCHEDULE_HISTORY)
+}
+
+fn schedule_history_key(schedule_id: Uuid, kind: &str) -> String {
+    let mut store_key = "Schedules:".to_string();
+    store_key += &schedule_id.to_string();
+    store_key += ":";
+    store_key += kind;
+    store_key
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use axum::http::{HeaderValue, StatusCode};
+use hyper::body::Bytes;
+use jsonschema::JSONSchema;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    database::core_policy_action,
+    utilities::{app_error::AppError, upstream_client::UpstreamResponse},
+};
+
+pub const RESPONSE_CONTRACT_HEADER: &str = "x-response-contract";
+
+pub fn compile_schema(schema: &Value) -> Result<JSONSchema, AppError> {
+    JSONSchema::compile(schema).map_err(|error| {
+        eprintln!("Error compiling json schema: {}", error);
+        AppError::new(StatusCode::BAD_REQUEST, "Invalid JSON schema").with_details(
+            json!([{ "field": error.instance_path.to_string(), "message": error.to_string() }]),
+        )
+    })
+}
+
+//Check the incoming body against the action's request schema before it is forwarded to the twin
+pub fn validate_request_body(
+    policy_action: &core_policy_action::Model,
+    body: &Bytes,
+) -> Result<(), AppError> {
+    match &policy_action.request_schema {
+        Some(schema) => check_request_contract(policy_action.id, schema, body),
+        None => Ok(()),
+    }
+}
+
+pub fn check_request_contract(
+    action_id: Uuid,
+    schema: &Value,
+    body: &Bytes,
+) -> Result<(), AppError> {
+    let instance = parse_body(body).map_err(|message| {
+        AppError::new(StatusCode::BAD_REQUEST, "Request body is not valid JSON")
+            .with_details(json!([{ "field": "", "message": message }]))
+    })?;
+
+    let field_errors = validate(action_id, SchemaKind::Request, schema, &instance)?;
+    if !field_errors.is_empty() {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Request body does not match the endpoint schema",
+        )
+        .with_details(Value::Array(field_errors)));
+    }
+
+    Ok(())
+}
+
+//Check the twin's response against the action's response schema, flagging or rejecting it depending on the action
+pub fn check_response_body(
+    policy_action: &core_policy_action::Model,
+    response: &mut UpstreamResponse,
+) -> Result<(), AppError> {
+    match (
+        &policy_action.response_schema,
+        policy_action.response_validation.as_str(),
+    ) {
+        (Some(schema), validation @ ("flag" | "reject")) => {
+            check_response_contract(policy_action.id, schema, validation == "reject", response)
+        }
+        _ => Ok(()),
+    }
+}
+
+//Only successful responses carry the contract, the twin's own errors are passed through untouched
+pub fn check_response_contract(
+    action_id: Uuid,
+    schema: &Value,
+    reject: bool,
+    response: &mut UpstreamResponse,
+) -> Result<(), AppError> {
+    if !response.status.is_success() {
+        return Ok(());
+    }
+
+    let field_errors = match parse_body(&response.body) {
+        Ok(instance) => validate(action_id, SchemaKind::Response, schema, &instance)?,
+        Err(message) => vec![json!({ "field": "", "message": message })],
+    };
+
+    if field_errors.is_empty() {
+        response
+            .headers
+            .insert(RESPONSE_CONTRACT_HEADER, HeaderValue::from_static("valid"));
+        return Ok(());
+    }
+
+    eprintln!(
+        "Twin response for policy action {} broke its contract: {:?}",
+        action_id, field_errors
+    );
+
+    if reject {
+        return Err(AppError::new(
+            StatusCode::BAD_GATEWAY,
+            "Twin response does not match the endpoint schema",
+        )
+        .with_details(Value::Array(field_errors)));
+    }
+
+    response.headers.insert(
+        RESPONSE_CONTRACT_HEADER,
+        HeaderValue::from_static("violated"),
+    );
+    Ok(())
+}
+
+fn parse_body(body: &Bytes) -> Result<Value, String> {
+    if body.is_empty() {
+        return Ok(Value::Null);
+    }
+
+    serde_json::from_slice::<Value>(body).map_err(|error| error.to_string())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum SchemaKind {
+    Request,
+    Response,
+}
+
+//Policy actions are never edited once saved, so a schema compiled for an action stays valid for its lifetime
+type CompiledSchemas = Mutex<HashMap<(Uuid, SchemaKind), Arc<JSONSchema>>>;
+
+fn compiled_schemas() -> &'static CompiledSchemas {
+    static COMPILED_SCHEMAS: OnceLock<CompiledSchemas> = OnceLock::new();
+    COMPILED_SCHEMAS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_schema(
+    action_id: Uuid,
+    kind: SchemaKind,
+    schema: &Value,
+) -> Result<Arc<JSONSchema>, AppError> {
+    if let Some(compiled) = compiled_schemas().lock().unwrap().get(&(action_id, kind)) {
+        return Ok(compiled.clone());
+    }
+
+    //Schemas are checked when the policy is saved, one that no longer compiles is our fault and not the caller's
+    let compiled = Arc::new(JSONSchema::compile(schema).map_err(|error| {
+        eprintln!(
+            "Error compiling stored json schema of policy action {}: {}",
+            action_id, error
+        );
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again",
+        )
+    })?);
+
+    compiled_schemas()
+        .lock()
+        .unwrap()
+        .insert((action_id, kind), compiled.clone());
+    Ok(compiled)
+}
+
+fn validate(
+    action_id: Uuid,
+    kind: SchemaKind,
+    schema: &Value,
+    instance: &Value,
+) -> Result<Vec<Value>, AppError> {
+    let compiled = cached_schema(action_id, kind, schema)?;
+
+    let field_errors = match compiled.validate(instance) {
+        Ok(()) => vec![],
+        Err(errors) => errors
+            .map(|error| {
+                json!({
+                    "field": error.instance_path.to_string(),
+                    "message": error.to_string(),
+                })
+            })
+            .collect(),
+    };
+
+    Ok(field_errors)
+}
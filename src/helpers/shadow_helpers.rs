@@ -0,0 +1,188 @@
+use axum::http::{header, HeaderMap, HeaderValue, Method, Uri};
+use hmac::{Hmac, Mac};
+use hyper::body::Bytes;
+use sea_orm::DatabaseConnection;
+use serde_json::{json, Map, Value};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_twin, core_twin_shadow},
+    helpers::twin_invocation_helpers::resolve_twin_authority,
+    utilities::upstream_client::UpstreamClient,
+};
+
+//Twins authenticate their state reports with this header
+pub const SHADOW_TOKEN_HEADER: &str = "x-twin-token";
+
+const DEFAULT_SHADOW_DELTA_PATH: &str = "shadow/delta";
+const DEFAULT_SHADOW_CALLBACK_BASE_URL: &str = "http://host.docker.internal:3000";
+
+//RFC 7386 merge patch: objects are merged key by key and null removes a key
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().unwrap();
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+//The part of desired the twin has not reported yet, nested objects are compared key by key
+pub fn compute_delta(desired: &Value, reported: &Value) -> Value {
+    let (desired, reported) = match (desired, reported) {
+        (Value::Object(desired), Value::Object(reported)) => (desired, reported),
+        _ if desired == reported => return json!({}),
+        _ => return desired.clone(),
+    };
+
+    let mut delta = Map::new();
+    for (key, desired_value) in desired {
+        match reported.get(key) {
+            Some(reported_value) if desired_value.is_object() && reported_value.is_object() => {
+                let nested = compute_delta(desired_value, reported_value);
+                if nested.as_object().is_some_and(|nested| !nested.is_empty()) {
+                    delta.insert(key.clone(), nested);
+                }
+            }
+            Some(reported_value) if reported_value == desired_value => {}
+            _ => {
+                delta.insert(key.clone(), desired_value.clone());
+            }
+        }
+    }
+
+    Value::Object(delta)
+}
+
+//Each twin gets its own token, derived from the twin id so nothing has to be stored.
+//None when SHADOW_SECRET is unset, twins then can't report their state
+pub fn shadow_token(twin_id: Uuid) -> Option<String> {
+    let mut mac = shadow_mac()?;
+    mac.update(twin_id.as_bytes());
+
+    Some(format!("{:x}", mac.finalize().into_bytes()))
+}
+
+pub fn verify_shadow_token(twin_id: Uuid, token: &str) -> bool {
+    let Some(expected) = shadow_token(twin_id) else {
+        return false;
+    };
+
+    //Compare in constant time so the token can't be guessed byte by byte
+    expected.len() == token.len()
+        && expected
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+pub fn shadow_callbacks_enabled() -> bool {
+    shadow_secret().is_some()
+}
+
+//Environment handed to every twin container so it can read and report its shadow
+pub fn shadow_env(twin_id: Uuid) -> Vec<String> {
+    let base_url = std::env::var("SHADOW_CALLBACK_BASE_URL")
+        .unwrap_or_else(|_| DEFAULT_SHADOW_CALLBACK_BASE_URL.to_string());
+
+    let mut env = vec![format!("TWIN_ID={}", twin_id)];
+    if let Some(token) = shadow_token(twin_id) {
+        env.push(format!("TWIN_SHADOW_TOKEN={}", token));
+        env.push(format!(
+            "TWIN_SHADOW_URL={}/twins/{}/shadow",
+            base_url.trim_end_matches('/'),
+            twin_id
+        ));
+    }
+
+    env
+}
+
+//Twin tokens have their own key, never the one that signs user tokens
+fn shadow_secret() -> Option<String> {
+    std::env::var("SHADOW_SECRET")
+        .ok()
+        .filter(|secret| !secret.trim().is_empty())
+}
+
+fn shadow_mac() -> Option<Hmac<Sha256>> {
+    let secret = shadow_secret()?;
+
+    Some(Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size"))
+}
+
+//Send the outstanding delta to a running twin, the twin answers later through the reported callback
+pub fn push_shadow_delta(
+    db: DatabaseConnection,
+    client: UpstreamClient,
+    twin: core_twin::Model,
+    shadow: &core_twin_shadow::Model,
+) {
+    let delta = compute_delta(&shadow.desired, &shadow.reported);
+    if twin.twin_status_id != 2 || delta.as_object().is_some_and(|delta| delta.is_empty()) {
+        return;
+    }
+
+    let body = json!({
+        "twinId": twin.id,
+        "version": shadow.version,
+        "state": delta,
+    });
+
+    tokio::spawn(async move {
+        let delta_path = std::env::var("SHADOW_DELTA_PATH")
+            .unwrap_or_else(|_| DEFAULT_SHADOW_DELTA_PATH.to_string());
+
+        let authority = match resolve_twin_authority(&db, &twin, None).await {
+            Ok(authority) => authority,
+            Err(error) => {
+                eprintln!("Error resolving twin for shadow delta: {:?}", error);
+                return;
+            }
+        };
+
+        let uri = match Uri::try_from(format!(
+            "http://{}/{}",
+            authority,
+            delta_path.trim_start_matches('/')
+        )) {
+            Ok(uri) => uri,
+            Err(error) => {
+                eprintln!("Error building shadow delta uri: {:?}", error);
+                return;
+            }
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        if let Some(token) =
+            shadow_token(twin.id).and_then(|token| HeaderValue::from_str(&token).ok())
+        {
+            headers.insert(SHADOW_TOKEN_HEADER, token);
+        }
+
+        let body = Bytes::from(body.to_string());
+        if let Err(error) = client.send(twin.id, Method::POST, uri, headers, body).await {
+            eprintln!("Error pushing shadow delta to twin {}: {:?}", twin.id, error);
+        }
+    });
+}
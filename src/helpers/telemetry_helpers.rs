@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use hyper::body::Bytes;
+use sea_orm::{DatabaseConnection, Set};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_twin, core_twin_telemetry},
+    queries::telemetry_queries,
+    utilities::json_path::select_number,
+};
+
+const DEFAULT_TELEMETRY_RETENTION_DAYS: i32 = 30;
+const RETENTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+//Pull the model's configured numeric fields out of a twin response, in the background so callers don't wait on it
+pub fn capture_telemetry(
+    db: DatabaseConnection,
+    twin: &core_twin::Model,
+    component_alias: Option<String>,
+    end_point: String,
+    body: Bytes,
+) {
+    let twin_id = twin.id;
+    let model_id = twin.model_id;
+
+    tokio::spawn(async move {
+        let fields = match telemetry_queries::get_telemetry_fields(&db, model_id).await {
+            Ok(fields) if !fields.is_empty() => fields,
+            Ok(_) => return,
+            Err(error) => {
+                eprintln!("Error loading telemetry fields: {:?}", error);
+                return;
+            }
+        };
+
+        let response = match serde_json::from_slice::<Value>(&body) {
+            Ok(response) => response,
+            Err(_) => return,
+        };
+
+        let recorded_at = Utc::now();
+        let end_point = end_point.trim_matches('/').to_string();
+        let points = fields
+            .into_iter()
+            .filter(|field| {
+                field.component_alias.is_none() || field.component_alias == component_alias
+            })
+            .filter(|field| {
+                field
+                    .end_point
+                    .as_deref()
+                    .is_none_or(|field_end_point| field_end_point.trim_matches('/') == end_point)
+            })
+            .filter_map(|field| {
+                let value = select_number(&response, &field.json_path)?;
+                Some(core_twin_telemetry::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    twin_id: Set(twin_id),
+                    field_name: Set(field.name),
+                    value: Set(value),
+                    component_alias: Set(component_alias.clone()),
+                    end_point: Set(end_point.clone()),
+                    recorded_at: Set(recorded_at.into()),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(error) = telemetry_queries::store_telemetry_points(&db, points).await {
+            eprintln!("Error storing telemetry for twin {}: {:?}", twin_id, error);
+        }
+    });
+}
+
+//Keeps monthly partitions ahead of the clock and enforces each model's retention
+pub fn start_telemetry_retention(db: DatabaseConnection) {
+    let default_retention_days = std::env::var("TELEMETRY_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.trim().parse::<i32>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_TELEMETRY_RETENTION_DAYS);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now();
+            if let Err(error) = telemetry_queries::ensure_telemetry_partitions(&db, now).await {
+                eprintln!("Error preparing telemetry partitions: {:?}", error);
+            }
+            if let Err(error) =
+                telemetry_queries::prune_telemetry(&db, default_retention_days, now).await
+            {
+                eprintln!("Error applying telemetry retention: {:?}", error);
+            }
+        }
+    });
+}
@@ -0,0 +1,386 @@
+use std::time::Instant;
+
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use hyper::body::Bytes;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    database::core_twin,
+    helpers::{
+        cache_mgmt_helpers::{
+            cache_key, get_cached_response, set_cache_status, store_cached_response,
+        },
+        policy_block_helpers::ensure_twin_not_deactivated,
+        policy_mgmt_helpers::{
+            check_cached_call_policy, check_policy, check_policy_conditions, PolicyEndpoint,
+        },
+        schema_validation_helpers::{check_response_body, validate_request_body},
+        telemetry_helpers::capture_telemetry,
+    },
+    queries::{
+        policy_queries,
+        shared_data_queries::{self, SharedData},
+        twin_queries,
+    },
+    utilities::{
+        app_error::AppError,
+        proxy_network::ProxyNetworkMode,
+        redis_connection_wrapper::RedisConnWrapper,
+        twin_events::{publish_twin_event, TWIN_EVENT_INVOCATION, TWIN_EVENT_POLICY_DENIED},
+        upstream_client::{ProxyError, UpstreamClient, UpstreamResponse},
+    },
+};
+
+pub async fn get_running_user_twin(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    user_id: Uuid,
+) -> Result<core_twin::Model, AppError> {
+    //Get twin from db by using appropriate twin_queries
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(db, twin_id, user_id).await?;
+    ensure_twin_not_deactivated(&twin)?;
+
+    //Check if twin is running
+    if twin.twin_status_id != 2 {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Twin is not running",
+        ));
+    }
+
+    Ok(twin)
+}
+
+//host:port the proxy uses to reach a twin component, no alias means the twin's default entry point
+pub async fn resolve_twin_authority(
+    db: &DatabaseConnection,
+    twin: &core_twin::Model,
+    component_alias: Option<&str>,
+) -> Result<String, AppError> {
+    if ProxyNetworkMode::from_env() == ProxyNetworkMode::Host {
+        let twin_port = match component_alias {
+            Some(component_alias) => {
+                twin_queries::find_exposed_twin_component_by_alias(db, twin.id, component_alias)
+                    .await?
+                    .host_port
+            }
+            None => twin.twin_port,
+        };
+        let twin_port = twin_port.ok_or_else(|| {
+            eprintln!("Twin {} has no exposed port", twin.id);
+            AppError::new(StatusCode::BAD_REQUEST, "Twin has no exposed port")
+        })?;
+
+        return Ok(format!("localhost:{}", twin_port));
+    }
+
+    let component = match component_alias {
+        Some(component_alias) => {
+            twin_queries::find_exposed_twin_component_by_alias(db, twin.id, component_alias).await?
+        }
+        None => {
+            //Prefer the component behind twin_port when ports were published, else the first exposed one
+            let components = twin_queries::get_exposed_twin_components(db, twin.id).await?;
+            let default_component = components
+                .iter()
+                .position(|component| {
+                    twin.twin_port.is_some() && component.host_port == twin.twin_port
+                })
+                .unwrap_or(0);
+            components
+                .into_iter()
+                .nth(default_component)
+                .ok_or_else(|| {
+                    eprintln!("Twin {} has no exposed component", twin.id);
+                    AppError::new(StatusCode::BAD_REQUEST, "Twin has no exposed port")
+                })?
+        }
+    };
+
+    //Aliases repeat across twins of the same model, container names are unique on every network the proxy joins
+    match (component.container_name, component.container_port) {
+        (Some(container_name), Some(container_port)) => {
+            Ok(format!("{}:{}", container_name, container_port))
+        }
+        _ => {
+            eprintln!("Twin component {} is not deployed", component.id);
+            Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "Twin component is not deployed",
+            ))
+        }
+    }
+}
+
+pub struct TwinRequest {
+    //None addresses the twin's default entry point
+    pub component_alias: Option<String>,
+    pub endpoint_id: String,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+//How a call is accounted for, subscribers always go through the policy and the twin's data sharing setting
+pub struct InvocationOptions {
+    pub enforce_policy: bool,
+    pub use_cache: bool,
+    //None follows the twin's enable_data_sharing flag
+    pub capture_data: Option<bool>,
+}
+
+impl InvocationOptions {
+    pub fn subscriber() -> Self {
+        Self {
+            enforce_policy: true,
+            use_cache: true,
+            capture_data: None,
+        }
+    }
+}
+
+//Run one call against a twin endpoint: schema checks, cache, policy accounting, the upstream call and data sharing
+pub async fn invoke_twin_endpoint(
+    db: &DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: &UpstreamClient,
+    user_id: Uuid,
+    twin: &core_twin::Model,
+    request: TwinRequest,
+) -> Result<UpstreamResponse, AppError> {
+    invoke_twin_endpoint_with(
+        db,
+        redis_url,
+        client,
+        user_id,
+        twin,
+        request,
+        &InvocationOptions::subscriber(),
+    )
+    .await
+}
+
+pub async fn invoke_twin_endpoint_with(
+    db: &DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: &UpstreamClient,
+    user_id: Uuid,
+    twin: &core_twin::Model,
+    request: TwinRequest,
+    options: &InvocationOptions,
+) -> Result<UpstreamResponse, AppError> {
+    let twin_id = twin.id;
+    let TwinRequest {
+        component_alias,
+        endpoint_id,
+        headers,
+        body: input_body_bytes,
+    } = request;
+    let policy_endpoint = PolicyEndpoint::new(component_alias.clone(), endpoint_id.clone());
+
+    //Cacheable endpoints are configured on the policy action
+    let policy_action = match twin.policy_id {
+        Some(policy_id) => Some(
+            policy_queries::get_policy_action_by_policyid_and_endpoint(
+                db,
+                policy_id,
+                component_alias.as_deref(),
+                endpoint_id.clone(),
+            )
+            .await?,
+        ),
+        None => None,
+    };
+
+    //Bad payloads are rejected here instead of reaching the twin container
+    if let Some(policy_action) = policy_action.as_ref() {
+        validate_request_body(policy_action, &input_body_bytes)?;
+
+        if options.enforce_policy {
+            check_policy_conditions(
+                db,
+                redis_url.clone(),
+                twin_id,
+                &policy_endpoint,
+                user_id,
+                policy_action,
+                &input_body_bytes,
+            )
+            .await
+            .inspect_err(|error| {
+                report_policy_denial(&redis_url, twin_id, user_id, &policy_endpoint, error)
+            })?;
+        }
+    }
+
+    let cache_key = policy_action
+        .as_ref()
+        .filter(|policy_action| options.use_cache && policy_action.is_cacheable)
+        .map(|policy_action| cache_key(policy_action, twin, &input_body_bytes));
+
+    if let Some(cache_key) = cache_key.clone() {
+        if let Some(mut cached_response) =
+            get_cached_response(redis_url.clone(), cache_key).await?
+        {
+            //Replayed responses are still rate limited, owners decide whether they count against the user's quota
+            if let Some(policy_id) = twin.policy_id.filter(|_| options.enforce_policy) {
+                check_cached_call_policy(
+                    db,
+                    twin_id,
+                    &policy_endpoint,
+                    user_id,
+                    policy_id,
+                    redis_url.clone(),
+                    policy_action
+                        .as_ref()
+                        .is_some_and(|policy_action| policy_action.cache_hits_count),
+                )
+                .await
+                .inspect_err(|error| {
+                    report_policy_denial(&redis_url, twin_id, user_id, &policy_endpoint, error)
+                })?;
+            }
+
+            set_cache_status(&mut cached_response.headers, "HIT");
+            return Ok(cached_response);
+        }
+    }
+
+    //Check if user has access to endpoint
+    if let Some(policy_id) = twin.policy_id.filter(|_| options.enforce_policy) {
+        check_policy(
+            db,
+            twin_id,
+            &policy_endpoint,
+            user_id,
+            policy_id,
+            redis_url.clone(),
+        )
+        .await
+        .inspect_err(|error| {
+            report_policy_denial(&redis_url, twin_id, user_id, &policy_endpoint, error)
+        })?;
+    }
+
+    // Construct URI
+    let authority = resolve_twin_authority(db, twin, component_alias.as_deref()).await?;
+    let uri = format!("http://{}/{}", authority, endpoint_id);
+
+    // Prepare request and return
+    let uri = Uri::try_from(uri).map_err(|error| {
+        eprintln!("Error building twin uri: {:?}", error);
+        AppError::new(StatusCode::BAD_REQUEST, "Invalid twin endpoint")
+    })?;
+
+    //Source of the method is core_policy_action.end_point_verb
+    let policy_action_verb = policy_action
+        .as_ref()
+        .map(|policy_action| policy_action.end_point_verb.as_str())
+        .ok_or_else(|| {
+            eprintln!("Twin {} has no policy", twin.id);
+            AppError::new(StatusCode::NOT_FOUND, "Endpoint not found in twin policy")
+        })?;
+    let method = Method::from_bytes(policy_action_verb.as_bytes()).map_err(|error| {
+        eprintln!("Error parsing policy action verb: {:?}", error);
+        AppError::new(StatusCode::NOT_FOUND, "Endpoint not found in twin policy")
+    })?;
+
+    let input_body = String::from_utf8_lossy(&input_body_bytes).to_string();
+
+    let started_at = Instant::now();
+    let upstream_result = client
+        .send(twin.id, method, uri, headers, input_body_bytes)
+        .await
+        .map_err(ProxyError::into_app_error);
+
+    let (status, error) = match &upstream_result {
+        Ok(response) => (response.status.as_u16(), None),
+        Err(error) => (error.code().as_u16(), Some(error.message().to_string())),
+    };
+    publish_twin_event(
+        redis_url.clone(),
+        twin_id,
+        TWIN_EVENT_INVOCATION,
+        json!({
+            "userId": user_id,
+            "componentAlias": component_alias,
+            "endpoint": endpoint_id,
+            "status": status,
+            "durationMs": started_at.elapsed().as_millis() as u64,
+            "error": error,
+        }),
+    );
+    let mut upstream_response = upstream_result?;
+
+    if let Some(policy_action) = policy_action.as_ref() {
+        check_response_body(policy_action, &mut upstream_response)?;
+    }
+
+    //Configured telemetry fields are kept even when the response itself is not shared
+    if upstream_response.status.is_success() {
+        capture_telemetry(
+            db.clone(),
+            twin,
+            component_alias.clone(),
+            endpoint_id.clone(),
+            upstream_response.body.clone(),
+        );
+    }
+
+    let cache_status = match (cache_key, policy_action.as_ref()) {
+        (Some(cache_key), Some(policy_action)) => {
+            store_cached_response(
+                redis_url.clone(),
+                cache_key,
+                &upstream_response,
+                policy_action.cache_ttl.unwrap_or_default(),
+            )
+            .await?;
+            "MISS"
+        }
+        _ => "BYPASS",
+    };
+    set_cache_status(&mut upstream_response.headers, cache_status);
+
+    if options.capture_data.unwrap_or(twin.enable_data_sharing) {
+        let return_body = String::from_utf8_lossy(&upstream_response.body).to_string();
+        let _store_result = shared_data_queries::store_usage_data(
+            db.clone(),
+            SharedData {
+                model_id: Some(twin.model_id),
+                input_data: Some(input_body),
+                output_response: Some(return_body),
+            },
+        )
+        .await?;
+    }
+
+    Ok(upstream_response)
+}
+
+pub(crate) fn report_policy_denial(
+    redis_url: &RedisConnWrapper,
+    twin_id: Uuid,
+    user_id: Uuid,
+    policy_endpoint: &PolicyEndpoint,
+    error: &AppError,
+) {
+    //Server side failures while checking are not denials
+    if !error.code().is_client_error() {
+        return;
+    }
+
+    publish_twin_event(
+        redis_url.clone(),
+        twin_id,
+        TWIN_EVENT_POLICY_DENIED,
+        json!({
+            "userId": user_id,
+            "componentAlias": policy_endpoint.component_alias,
+            "endpoint": policy_endpoint.end_point,
+            "status": error.code().as_u16(),
+            "reason": error.message(),
+        }),
+    );
+}
@@ -6,7 +6,9 @@ use digital_twin_mw::{
     run,
     utilities::token_wrapper::TokenWrapper,
     utilities::{
-        redis_connection_wrapper::RedisConnWrapper, token_duration_wrapper::TokenDurationWrapper,
+        redis_connection_wrapper::RedisConnWrapper,
+        request_trace::{init_tracing, shutdown_tracing},
+        token_duration_wrapper::TokenDurationWrapper,
     },
 };
 use sea_orm::Database;
@@ -14,6 +16,7 @@ use sea_orm::Database;
 #[tokio::main]
 async fn main() {
     dotenv().ok();
+    init_tracing();
 
     let database_url = dotenv!("DATABASE_URL");
     let jwt_secret = dotenv!("JWT_SECRET").to_owned();
@@ -39,6 +42,7 @@ async fn main() {
     };
 
     run(app_state).await;
+    shutdown_tracing();
 
     // endregion: Start Server
 }
@@ -1 +1,2 @@
 pub mod require_authentication;
+pub mod request_id;
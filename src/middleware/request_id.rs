@@ -0,0 +1,45 @@
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::utilities::request_trace::{set_parent_from_headers, with_request_id, REQUEST_ID_HEADER};
+
+pub async fn propagate_request_id<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    //Honour the caller's id when it looks sane, otherwise start a new one
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|request_id| request_id.to_str().ok())
+        .filter(|request_id| !request_id.is_empty() && request_id.len() <= 128)
+        .map(|request_id| request_id.to_string())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let header_value = HeaderValue::from_str(&request_id).unwrap();
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value.clone());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+        status = tracing::field::Empty,
+    );
+    set_parent_from_headers(&span, request.headers());
+
+    let mut response = with_request_id(request_id, next.run(request))
+        .instrument(span.clone())
+        .await;
+
+    span.record("status", response.status().as_u16());
+    response
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value);
+
+    response
+}
@@ -83,6 +83,7 @@ pub async fn require_authentication<B>(
         updated_by: None,
         deleted_at: None,
         deleted_by: None,
+        attributes: serde_json::json!({}),
     };
 
     if user_role != "admin" {
@@ -0,0 +1,95 @@
+use axum::http::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use uuid::Uuid;
+
+use crate::database::core_policy_block::{self, Entity as PolicyBlocks};
+use crate::utilities::app_error::AppError;
+
+pub const BLOCK_DECISION_BLOCK: &str = "block";
+pub const BLOCK_DECISION_UNBLOCK: &str = "unblock";
+
+pub struct BlockDecision {
+    pub user_id: Uuid,
+    pub model_id: Uuid,
+    pub policy_id: Uuid,
+    pub twin_id: Uuid,
+    pub decision: &'static str,
+    pub violation_count: i32,
+    pub reason: Option<String>,
+    //None when the platform blocked the twin on its own
+    pub decided_by: Option<Uuid>,
+}
+
+pub async fn create_block_decision(
+    db: &DatabaseConnection,
+    decision: BlockDecision,
+) -> Result<core_policy_block::Model, AppError> {
+    let new_decision = core_policy_block::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(decision.user_id),
+        model_id: Set(decision.model_id),
+        policy_id: Set(decision.policy_id),
+        twin_id: Set(decision.twin_id),
+        decision: Set(decision.decision.to_string()),
+        violation_count: Set(decision.violation_count),
+        reason: Set(decision.reason),
+        decided_by: Set(decision.decided_by),
+        ..Default::default()
+    };
+
+    new_decision.insert(db).await.map_err(|error| {
+        eprintln!("Error saving block decision: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error saving block decision",
+        )
+    })
+}
+
+pub async fn get_latest_block_decision(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    decision: &str,
+) -> Result<Option<core_policy_block::Model>, AppError> {
+    PolicyBlocks::find()
+        .filter(core_policy_block::Column::TwinId.eq(twin_id))
+        .filter(core_policy_block::Column::Decision.eq(decision))
+        .order_by_desc(core_policy_block::Column::DecidedAt)
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting latest block decision: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the block decisions",
+            )
+        })
+}
+
+pub async fn get_model_block_decisions(
+    db: &DatabaseConnection,
+    model_id: Uuid,
+    twin_id: Option<Uuid>,
+    limit: u64,
+) -> Result<Vec<core_policy_block::Model>, AppError> {
+    let mut query = PolicyBlocks::find().filter(core_policy_block::Column::ModelId.eq(model_id));
+    if let Some(twin_id) = twin_id {
+        query = query.filter(core_policy_block::Column::TwinId.eq(twin_id));
+    }
+
+    query
+        .order_by_desc(core_policy_block::Column::DecidedAt)
+        .limit(limit)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting block decisions by model id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the block decisions",
+            )
+        })
+}
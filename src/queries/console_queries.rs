@@ -0,0 +1,69 @@
+use axum::http::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use uuid::Uuid;
+
+use crate::database::core_owner_console_call::{self, Entity as ConsoleCalls};
+use crate::utilities::app_error::AppError;
+
+pub struct ConsoleCall {
+    pub owner_id: Uuid,
+    pub model_id: Uuid,
+    pub twin_id: Uuid,
+    pub component_alias: Option<String>,
+    pub end_point: String,
+    pub status_code: u16,
+    pub duration_ms: u128,
+    pub error_message: Option<String>,
+    pub data_shared: bool,
+}
+
+pub async fn log_console_call(db: &DatabaseConnection, call: ConsoleCall) -> Result<(), AppError> {
+    let new_call = core_owner_console_call::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        owner_id: Set(call.owner_id),
+        model_id: Set(call.model_id),
+        twin_id: Set(call.twin_id),
+        component_alias: Set(call.component_alias),
+        end_point: Set(call.end_point),
+        status_code: Set(call.status_code as i32),
+        duration_ms: Set(call.duration_ms.min(i32::MAX as u128) as i32),
+        error_message: Set(call.error_message),
+        data_shared: Set(call.data_shared),
+        ..Default::default()
+    };
+
+    new_call.insert(db).await.map_err(|error| {
+        eprintln!("Error saving console call: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error saving console call",
+        )
+    })?;
+
+    Ok(())
+}
+
+pub async fn get_console_calls(
+    db: &DatabaseConnection,
+    model_id: Uuid,
+    owner_id: Uuid,
+    limit: u64,
+) -> Result<Vec<core_owner_console_call::Model>, AppError> {
+    ConsoleCalls::find()
+        .filter(core_owner_console_call::Column::ModelId.eq(model_id))
+        .filter(core_owner_console_call::Column::OwnerId.eq(owner_id))
+        .order_by_desc(core_owner_console_call::Column::CalledAt)
+        .limit(limit)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting console calls by model id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your console calls",
+            )
+        })
+}
@@ -1,6 +1,15 @@
+pub mod block_queries;
+pub mod console_queries;
 pub mod model_queries;
+pub mod openapi_queries;
+pub mod mqtt_binding_queries;
+pub mod pipeline_queries;
 pub mod policy_queries;
 pub mod role_queries;
+pub mod schedule_queries;
+pub mod shadow_queries;
 pub mod shared_data_queries;
+pub mod telemetry_queries;
 pub mod twin_queries;
 pub mod user_queries;
+pub mod violation_queries;
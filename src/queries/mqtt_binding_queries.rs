@@ -0,0 +1,137 @@
+use axum::http::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::database::core_twin_mqtt_binding::{self, Entity as MqttBindings};
+use crate::routes::twins::create_mqtt_binding_extractor::ValidateCreateMqttBinding;
+use crate::utilities::app_error::AppError;
+
+pub async fn create_mqtt_binding(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    user_id: Uuid,
+    binding: ValidateCreateMqttBinding,
+) -> Result<core_twin_mqtt_binding::Model, AppError> {
+    let new_binding = core_twin_mqtt_binding::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        twin_id: Set(twin_id),
+        subscribe_topic: Set(binding.subscribe_topic.unwrap()),
+        component_alias: Set(binding.component_alias),
+        end_point: Set(binding.end_point.unwrap()),
+        publish_topic: Set(binding.publish_topic),
+        qos: Set(binding.qos.unwrap_or_default()),
+        created_by: Set(user_id),
+        updated_by: Set(Some(user_id)),
+        ..Default::default()
+    };
+
+    new_binding.insert(db).await.map_err(|error| {
+        eprintln!("Error saving mqtt binding: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error saving mqtt binding",
+        )
+    })
+}
+
+pub async fn get_twin_mqtt_bindings(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<core_twin_mqtt_binding::Model>, AppError> {
+    MqttBindings::find()
+        .filter(core_twin_mqtt_binding::Column::TwinId.eq(twin_id))
+        .filter(core_twin_mqtt_binding::Column::CreatedBy.eq(user_id))
+        .filter(core_twin_mqtt_binding::Column::DeletedAt.is_null())
+        .order_by_asc(core_twin_mqtt_binding::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting mqtt bindings by twin id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your mqtt bindings",
+            )
+        })
+}
+
+//A user's active bindings across all of their twins
+pub async fn get_user_mqtt_bindings(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+) -> Result<Vec<core_twin_mqtt_binding::Model>, AppError> {
+    MqttBindings::find()
+        .filter(core_twin_mqtt_binding::Column::CreatedBy.eq(user_id))
+        .filter(core_twin_mqtt_binding::Column::IsActive.eq(true))
+        .filter(core_twin_mqtt_binding::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting mqtt bindings by user id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your mqtt bindings",
+            )
+        })
+}
+
+//Every binding the bridge has to serve, across all twins
+pub async fn get_active_mqtt_bindings(
+    db: &DatabaseConnection,
+) -> Result<Vec<core_twin_mqtt_binding::Model>, AppError> {
+    MqttBindings::find()
+        .filter(core_twin_mqtt_binding::Column::IsActive.eq(true))
+        .filter(core_twin_mqtt_binding::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting active mqtt bindings: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the mqtt bindings",
+            )
+        })
+}
+
+pub async fn delete_mqtt_binding(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    binding_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    let binding = MqttBindings::find_by_id(binding_id)
+        .filter(core_twin_mqtt_binding::Column::TwinId.eq(twin_id))
+        .filter(core_twin_mqtt_binding::Column::CreatedBy.eq(user_id))
+        .filter(core_twin_mqtt_binding::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting mqtt binding by id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your mqtt binding",
+            )
+        })?
+        .ok_or_else(|| {
+            eprintln!("Could not find mqtt binding by id");
+            AppError::new(StatusCode::NOT_FOUND, "not found")
+        })?;
+
+    let mut binding = binding.into_active_model();
+    binding.is_active = Set(false);
+    binding.deleted_by = Set(Some(user_id));
+    binding.deleted_at = Set(Some(chrono::Utc::now().into()));
+
+    binding.save(db).await.map_err(|error| {
+        eprintln!("Error deleting mqtt binding: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error deleting mqtt binding",
+        )
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use axum::http::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::database::core_model_openapi::{self, Entity as OpenApiDocuments};
+use crate::utilities::app_error::AppError;
+
+pub const OPENAPI_SOURCE_UPLOAD: &str = "upload";
+pub const OPENAPI_SOURCE_COMPONENT: &str = "component";
+
+//Store the document for a model entry point; a fetched document never replaces one the owner uploaded
+pub async fn save_openapi_document(
+    db: &DatabaseConnection,
+    model_id: Uuid,
+    component_alias: Option<String>,
+    document: Value,
+    source: &str,
+    user_id: Uuid,
+) -> Result<Option<core_model_openapi::Model>, AppError> {
+    let existing = get_model_openapi_documents(db, model_id)
+        .await?
+        .into_iter()
+        .find(|existing| existing.component_alias == component_alias);
+
+    let saved = match existing {
+        Some(existing)
+            if existing.source == OPENAPI_SOURCE_UPLOAD && source == OPENAPI_SOURCE_COMPONENT =>
+        {
+            return Ok(None);
+        }
+        Some(existing) => {
+            let mut existing = existing.into_active_model();
+            existing.document = Set(document);
+            existing.source = Set(source.to_string());
+            existing.updated_by = Set(Some(user_id));
+            existing.updated_at = Set(chrono::Utc::now().into());
+            existing.update(db).await
+        }
+        None => {
+            core_model_openapi::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                model_id: Set(model_id),
+                component_alias: Set(component_alias),
+                document: Set(document),
+                source: Set(source.to_string()),
+                created_by: Set(user_id),
+                updated_by: Set(Some(user_id)),
+                ..Default::default()
+            }
+            .insert(db)
+            .await
+        }
+    }
+    .map_err(|error| {
+        eprintln!("Error saving openapi document: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error saving openapi document",
+        )
+    })?;
+
+    Ok(Some(saved))
+}
+
+pub async fn get_model_openapi_documents(
+    db: &DatabaseConnection,
+    model_id: Uuid,
+) -> Result<Vec<core_model_openapi::Model>, AppError> {
+    OpenApiDocuments::find()
+        .filter(core_model_openapi::Column::ModelId.eq(model_id))
+        .order_by_asc(core_model_openapi::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting openapi documents by model id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the openapi documents",
+            )
+        })
+}
@@ -0,0 +1,173 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::database::core_pipeline::{self, Entity as Pipelines};
+use crate::database::core_pipeline_step::{self, Entity as PipelineSteps};
+use crate::routes::pipelines::create_pipeline_extractor::ValidateCreatePipeline;
+use crate::utilities::app_error::AppError;
+
+pub async fn create_pipeline(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    pipeline: ValidateCreatePipeline,
+) -> Result<(core_pipeline::Model, Vec<core_pipeline_step::Model>), AppError> {
+    let txn = db.begin().await.map_err(|error| {
+        eprintln!("Error beginning transaction: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error beginning transaction",
+        )
+    })?;
+
+    let pipeline_id = Uuid::new_v4();
+    let new_pipeline = core_pipeline::ActiveModel {
+        id: Set(pipeline_id),
+        name: Set(pipeline.name.unwrap()),
+        description: Set(pipeline.description),
+        created_by: Set(user_id),
+        updated_by: Set(Some(user_id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|error| {
+        eprintln!("Error saving pipeline: {:?}", error);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error saving pipeline")
+    })?;
+
+    let mut steps = vec![];
+    for (position, step) in pipeline.steps.unwrap().into_iter().enumerate() {
+        let new_step = core_pipeline_step::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            pipeline_id: Set(pipeline_id),
+            step_key: Set(step.key.trim().to_string()),
+            position: Set(position as i32),
+            twin_id: Set(step.twin_id),
+            component_alias: Set(step.component_alias),
+            end_point: Set(step.end_point),
+            body: Set(step.body),
+            mappings: Set(serde_json::to_value(step.mappings).unwrap_or_default()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .map_err(|error| {
+            eprintln!("Error saving pipeline step: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error saving pipeline step",
+            )
+        })?;
+        steps.push(new_step);
+    }
+
+    txn.commit().await.map_err(|error| {
+        eprintln!("Error committing transaction: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error committing transaction",
+        )
+    })?;
+
+    Ok((new_pipeline, steps))
+}
+
+pub async fn get_user_pipelines(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+) -> Result<Vec<(core_pipeline::Model, Vec<core_pipeline_step::Model>)>, AppError> {
+    let pipelines = Pipelines::find()
+        .filter(core_pipeline::Column::CreatedBy.eq(user_id))
+        .filter(core_pipeline::Column::DeletedAt.is_null())
+        .order_by_asc(core_pipeline::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting pipelines by user id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your pipelines",
+            )
+        })?;
+
+    let steps = get_pipeline_steps(db, pipelines.iter().map(|pipeline| pipeline.id)).await?;
+
+    Ok(pipelines
+        .into_iter()
+        .map(|pipeline| {
+            let pipeline_steps = steps
+                .iter()
+                .filter(|step| step.pipeline_id == pipeline.id)
+                .cloned()
+                .collect::<Vec<_>>();
+            (pipeline, pipeline_steps)
+        })
+        .collect())
+}
+
+pub async fn find_user_pipeline(
+    db: &DatabaseConnection,
+    pipeline_id: Uuid,
+    user_id: Uuid,
+) -> Result<(core_pipeline::Model, Vec<core_pipeline_step::Model>), AppError> {
+    let pipeline = Pipelines::find_by_id(pipeline_id)
+        .filter(core_pipeline::Column::CreatedBy.eq(user_id))
+        .filter(core_pipeline::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting pipeline by id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your pipeline",
+            )
+        })?
+        .ok_or_else(|| {
+            eprintln!("Could not find pipeline by id");
+            AppError::new(StatusCode::NOT_FOUND, "Pipeline not found")
+        })?;
+
+    let steps = get_pipeline_steps(db, [pipeline.id]).await?;
+
+    Ok((pipeline, steps))
+}
+
+pub async fn delete_pipeline(
+    db: &DatabaseConnection,
+    pipeline: core_pipeline::Model,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    let mut pipeline = pipeline.into_active_model();
+    pipeline.deleted_by = Set(Some(user_id));
+    pipeline.deleted_at = Set(Some(Utc::now().into()));
+
+    pipeline.save(db).await.map_err(|error| {
+        eprintln!("Error deleting pipeline: {:?}", error);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error deleting pipeline")
+    })?;
+
+    Ok(())
+}
+
+async fn get_pipeline_steps(
+    db: &DatabaseConnection,
+    pipeline_ids: impl IntoIterator<Item = Uuid>,
+) -> Result<Vec<core_pipeline_step::Model>, AppError> {
+    PipelineSteps::find()
+        .filter(core_pipeline_step::Column::PipelineId.is_in(pipeline_ids))
+        .order_by_asc(core_pipeline_step::Column::Position)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting pipeline steps: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your pipeline steps",
+            )
+        })
+}
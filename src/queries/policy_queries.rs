@@ -5,6 +5,10 @@ use sea_orm::{
 };
 use uuid::Uuid;
 
+use crate::helpers::{
+    openapi_helpers::check_policy_actions, policy_mgmt_helpers::build_policy_action,
+};
+use crate::queries::{model_queries, openapi_queries};
 use crate::routes::policys::{RequestPolicyValidated, ResponsePolicy, ResponsePolicyAction};
 use crate::utilities::redis_connection_wrapper::RedisConnWrapper;
 use crate::utilities::redis_helper::store_token_in_redis;
@@ -88,15 +92,13 @@ pub async fn create_model_policy(
     model: RequestPolicyValidated,
     redis_url: RedisConnWrapper,
 ) -> Result<CorePolicy, AppError> {
-    let txn = db.begin().await.map_err(|error| {
-        eprintln!("Error beginning transaction: {:?}", error);
-        AppError::new(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Error beginning transaction",
-        )
-    })?;
     //Get Policies by model_id
     let policies = find_policy_by_model_id(db, model_id, user.id).await?;
+    let (_model, model_components) = model_queries::find_model_by_id(db, model_id, user.id).await?;
+
+    //Endpoints that are not in the model's OpenAPI documents would never be reachable
+    let documents = openapi_queries::get_model_openapi_documents(db, model_id).await?;
+    check_policy_actions(&documents, &model.policy_action_info)?;
 
     //Assign the length of policies to policy_version if policies is not empty
     let policy_version = if !policies.is_empty() {
@@ -105,16 +107,35 @@ pub async fn create_model_policy(
         Some(1)
     };
 
-    //save model
     let new_policy_id = Uuid::new_v4();
+
+    //Every action is checked before anything is saved, redis only learns about the policy once it is committed
+    let mut new_comps = vec![];
+    let mut policy_tokens = vec![];
+    for comp in model.policy_action_info {
+        let (new_comp, tokens) =
+            build_policy_action(new_policy_id, user.id, comp, &model_components)?;
+        new_comps.push(new_comp);
+        policy_tokens.extend(tokens);
+    }
+
+    let txn = db.begin().await.map_err(|error| {
+        eprintln!("Error beginning transaction: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error beginning transaction",
+        )
+    })?;
+
+    //save model
     let mut new_model = core_policy::ActiveModel {
-        id: Set(new_policy_id.clone()),
+        id: Set(new_policy_id),
         name: Set(model.policy_info.name.unwrap()),
         description: Set(model.policy_info.description.unwrap()),
         policy_version: Set(policy_version.unwrap()),
         model_id: Set(model_id),
-        created_by: Set(user.id.clone()),
-        updated_by: Set(Some(user.id.clone())),
+        created_by: Set(user.id),
+        updated_by: Set(Some(user.id)),
         ..Default::default()
     };
 
@@ -131,36 +152,7 @@ pub async fn create_model_policy(
     })?;
 
     //Save Model Components
-    for comp in model.policy_action_info {
-        let new_comp_id = Uuid::new_v4();
-        let end_point = comp.end_point.unwrap().to_lowercase();
-        let action_count_log: i32;
-
-        let mut new_comp = PolicyActActiveModel {
-            id: Set(new_comp_id.clone()),
-            policy_id: Set(new_policy_id.clone()),
-            end_point: Set(end_point.clone()),
-            description: Set(comp.description.unwrap()),
-            end_point_verb: Set(comp.end_point_verb.clone().unwrap().to_uppercase()),
-
-            created_by: Set(user.id.clone()),
-            updated_by: Set(Some(user.id.clone())),
-            ..Default::default()
-        };
-
-        //Check if action_count is not null, then save it
-        if let Some(action_count) = comp.action_count {
-            new_comp.action_count = Set(action_count);
-            action_count_log = action_count.clone();
-        } else {
-            action_count_log = 0;
-        }
-
-        //Check if reset_frequency is not null, then save it
-        if let Some(reset_frequency_id) = comp.reset_frequency_id {
-            new_comp.reset_frequency_id = Set(reset_frequency_id);
-        }
-
+    for new_comp in new_comps {
         //save_active_coremodelcomp(db, new_comp).await?;
         new_comp.insert(&txn).await.map_err(|error| {
             eprintln!("Error saving model policy action: {:?}", error);
@@ -169,30 +161,6 @@ pub async fn create_model_policy(
                 "Error saving model policy action",
             )
         })?;
-
-        //Save Policy Action Access Counts to Redis
-        let mut store_key = "Policy:Models:".to_string();
-        store_key = store_key + &new_policy_id.clone().to_string();
-        store_key = store_key + ":Access:" + &end_point;
-        let _redis_response = store_token_in_redis(
-            redis_url.clone(),
-            store_key.clone(),
-            action_count_log.to_string(),
-            usize::MAX,
-        )
-        .await?;
-
-        //Save Policy Action Verb to Redis
-        let mut store_key_verb = "Policy:Models:".to_string();
-        store_key_verb = store_key_verb + &new_policy_id.clone().to_string();
-        store_key_verb = store_key_verb + ":Verb:" + &end_point;
-        let _redis_response = store_token_in_redis(
-            redis_url.clone(),
-            store_key_verb.clone(),
-            comp.end_point_verb.unwrap().to_uppercase(),
-            usize::MAX,
-        )
-        .await?;
     }
 
     txn.commit().await.map_err(|error| {
@@ -203,6 +171,10 @@ pub async fn create_model_policy(
         )
     })?;
 
+    for (store_key, token) in policy_tokens {
+        store_token_in_redis(redis_url.clone(), store_key, token, usize::MAX).await?;
+    }
+
     Ok(new_model)
 }
 
@@ -232,6 +204,22 @@ pub async fn get_policies(
                     end_point_verb: policy_action.end_point_verb,
                     action_count: Some(policy_action.action_count),
                     reset_frequency: Some(policy_action.reset_frequency_id.to_owned().to_string()),
+                    max_concurrent_streams: policy_action.max_concurrent_streams,
+                    max_stream_duration: policy_action.max_stream_duration,
+                    is_cacheable: policy_action.is_cacheable,
+                    cache_ttl: policy_action.cache_ttl,
+                    cache_scope: policy_action.cache_scope,
+                    cache_hits_count: policy_action.cache_hits_count,
+                    request_schema: policy_action.request_schema,
+                    response_schema: policy_action.response_schema,
+                    response_validation: policy_action.response_validation,
+                    component_alias: policy_action.component_alias,
+                    rate_limit_algorithm: policy_action.rate_limit_algorithm,
+                    rate_limit_requests: policy_action.rate_limit_requests,
+                    rate_limit_period: policy_action.rate_limit_period,
+                    rate_limit_burst: policy_action.rate_limit_burst,
+                    validity: policy_action.validity,
+                    conditions: policy_action.conditions,
                 })
                 .collect::<Vec<ResponsePolicyAction>>();
 
@@ -252,8 +240,15 @@ pub async fn get_policies(
 pub async fn get_policy_action_by_policyid_and_endpoint(
     db: &DatabaseConnection,
     policy_id: uuid::Uuid,
+    component_alias: Option<&str>,
     end_point: String,
 ) -> Result<PolicyActionModel, AppError> {
+    //Actions without a component alias belong to the twin's default entry point
+    let component_filter = match component_alias {
+        Some(component_alias) => core_policy_action::Column::ComponentAlias.eq(component_alias),
+        None => core_policy_action::Column::ComponentAlias.is_null(),
+    };
+
     let policy_action = PolicyActions::find()
         .filter(core_policy_action::Column::DeletedAt.is_null())
         .filter(
@@ -261,6 +256,7 @@ pub async fn get_policy_action_by_policyid_and_endpoint(
                 .eq(policy_id)
                 .and(core_policy_action::Column::EndPoint.eq(end_point)),
         )
+        .filter(component_filter)
         .one(db)
         .await
         .map_err(|error| {
@@ -276,3 +272,51 @@ pub async fn get_policy_action_by_policyid_and_endpoint(
         AppError::new(StatusCode::NOT_FOUND, "not found")
     })
 }
+
+pub async fn find_policy_by_id(
+    db: &DatabaseConnection,
+    policy_id: Uuid,
+) -> Result<CorePolicy, AppError> {
+    let policy = Models::find_by_id(policy_id)
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting Policy by id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the policy, please try again",
+            )
+        })?;
+
+    policy.ok_or_else(|| {
+        eprintln!("Could not find Policy by id");
+        AppError::new(StatusCode::NOT_FOUND, "not found")
+    })
+}
+
+pub async fn find_policy_by_version(
+    db: &DatabaseConnection,
+    model_id: Uuid,
+    policy_version: i32,
+) -> Result<(CorePolicy, Vec<PolicyActionModel>), AppError> {
+    let policy = Models::find()
+        .filter(core_policy::Column::ModelId.eq(model_id))
+        .filter(core_policy::Column::PolicyVersion.eq(policy_version))
+        .find_with_related(PolicyActions)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting policy by version: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the policy, please try again",
+            )
+        })?;
+
+    policy.into_iter().next().ok_or_else(|| {
+        AppError::new(
+            StatusCode::NOT_FOUND,
+            format!("Policy version {} not found", policy_version),
+        )
+    })
+}
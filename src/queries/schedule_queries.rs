@@ -0,0 +1,175 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::database::core_twin_schedule::{self, Entity as Schedules};
+use crate::routes::twins::create_schedule_extractor::ValidateCreateSchedule;
+use crate::utilities::app_error::AppError;
+
+pub async fn create_schedule(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    user_id: Uuid,
+    schedule: ValidateCreateSchedule,
+    ends_at: Option<DateTime<Utc>>,
+    next_run_at: DateTime<Utc>,
+) -> Result<core_twin_schedule::Model, AppError> {
+    let new_schedule = core_twin_schedule::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        twin_id: Set(twin_id),
+        name: Set(schedule.name),
+        cron_expression: Set(schedule.cron.unwrap()),
+        component_alias: Set(schedule.component_alias),
+        end_point: Set(schedule.end_point.unwrap()),
+        body: Set(schedule.body),
+        ends_at: Set(ends_at.map(Into::into)),
+        next_run_at: Set(Some(next_run_at.into())),
+        created_by: Set(user_id),
+        updated_by: Set(Some(user_id)),
+        ..Default::default()
+    };
+
+    new_schedule.insert(db).await.map_err(|error| {
+        eprintln!("Error saving schedule: {:?}", error);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error saving schedule")
+    })
+}
+
+pub async fn count_active_twin_schedules(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+) -> Result<u64, AppError> {
+    Schedules::find()
+        .filter(core_twin_schedule::Column::TwinId.eq(twin_id))
+        .filter(core_twin_schedule::Column::IsActive.eq(true))
+        .filter(core_twin_schedule::Column::DeletedAt.is_null())
+        .count(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error counting schedules by twin id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your schedules",
+            )
+        })
+}
+
+pub async fn get_twin_schedules(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<core_twin_schedule::Model>, AppError> {
+    Schedules::find()
+        .filter(core_twin_schedule::Column::TwinId.eq(twin_id))
+        .filter(core_twin_schedule::Column::CreatedBy.eq(user_id))
+        .filter(core_twin_schedule::Column::DeletedAt.is_null())
+        .order_by_asc(core_twin_schedule::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting schedules by twin id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your schedules",
+            )
+        })
+}
+
+pub async fn find_twin_schedule(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    schedule_id: Uuid,
+    user_id: Uuid,
+) -> Result<core_twin_schedule::Model, AppError> {
+    Schedules::find_by_id(schedule_id)
+        .filter(core_twin_schedule::Column::TwinId.eq(twin_id))
+        .filter(core_twin_schedule::Column::CreatedBy.eq(user_id))
+        .filter(core_twin_schedule::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting schedule by id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your schedule",
+            )
+        })?
+        .ok_or_else(|| {
+            eprintln!("Could not find schedule by id");
+            AppError::new(StatusCode::NOT_FOUND, "Schedule not found")
+        })
+}
+
+pub async fn delete_schedule(
+    db: &DatabaseConnection,
+    schedule: core_twin_schedule::Model,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    let mut schedule = schedule.into_active_model();
+    schedule.is_active = Set(false);
+    schedule.deleted_by = Set(Some(user_id));
+    schedule.deleted_at = Set(Some(Utc::now().into()));
+
+    schedule.save(db).await.map_err(|error| {
+        eprintln!("Error deleting schedule: {:?}", error);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error deleting schedule")
+    })?;
+
+    Ok(())
+}
+
+pub async fn get_due_schedules(
+    db: &DatabaseConnection,
+    now: DateTime<Utc>,
+) -> Result<Vec<core_twin_schedule::Model>, AppError> {
+    Schedules::find()
+        .filter(core_twin_schedule::Column::IsActive.eq(true))
+        .filter(core_twin_schedule::Column::DeletedAt.is_null())
+        .filter(core_twin_schedule::Column::NextRunAt.lte(now))
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting due schedules: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the due schedules",
+            )
+        })
+}
+
+//Move the schedule to its next run, only one instance wins the claim for a given run
+pub async fn claim_schedule_run(
+    db: &DatabaseConnection,
+    schedule: &core_twin_schedule::Model,
+    next_run_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    let now: sea_orm::prelude::DateTimeWithTimeZone = now.into();
+    let next_run_at: Option<sea_orm::prelude::DateTimeWithTimeZone> = next_run_at.map(Into::into);
+
+    let result = Schedules::update_many()
+        .col_expr(
+            core_twin_schedule::Column::NextRunAt,
+            Expr::value(next_run_at),
+        )
+        .col_expr(core_twin_schedule::Column::LastRunAt, Expr::value(now))
+        .col_expr(
+            core_twin_schedule::Column::IsActive,
+            Expr::value(next_run_at.is_some()),
+        )
+        .col_expr(core_twin_schedule::Column::UpdatedAt, Expr::value(now))
+        .filter(core_twin_schedule::Column::Id.eq(schedule.id))
+        .filter(core_twin_schedule::Column::NextRunAt.eq(schedule.next_run_at))
+        .exec(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error claiming schedule run: {:?}", error);
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error updating schedule")
+        })?;
+
+    Ok(result.rows_affected == 1)
+}
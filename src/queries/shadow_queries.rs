@@ -0,0 +1,191 @@
+use axum::http::StatusCode;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, RuntimeErr, Set,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::core_twin_shadow::{self, Entity as TwinShadows};
+use crate::helpers::shadow_helpers::merge_patch;
+use crate::utilities::app_error::AppError;
+
+//Concurrent writers are retried a few times before giving up
+const SHADOW_UPDATE_ATTEMPTS: usize = 3;
+
+//Postgres error codes an insert of a new shadow can run into
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShadowSection {
+    Desired,
+    Reported,
+}
+
+//Twins that never had their state touched get an empty shadow at version 0
+pub async fn get_twin_shadow(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+) -> Result<core_twin_shadow::Model, AppError> {
+    let shadow = find_twin_shadow(db, twin_id).await?;
+
+    Ok(shadow.unwrap_or_else(|| {
+        let now = chrono::Utc::now().into();
+        core_twin_shadow::Model {
+            twin_id,
+            desired: json!({}),
+            reported: json!({}),
+            version: 0,
+            desired_updated_at: None,
+            reported_updated_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }))
+}
+
+//Apply a JSON merge patch to one section, bumping the version. expected_version turns it into a compare and set
+pub async fn update_twin_shadow(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    section: ShadowSection,
+    patch: &Value,
+    expected_version: Option<i32>,
+) -> Result<core_twin_shadow::Model, AppError> {
+    for _attempt in 0..SHADOW_UPDATE_ATTEMPTS {
+        let shadow = get_twin_shadow(db, twin_id).await?;
+        if expected_version.is_some_and(|expected_version| expected_version != shadow.version) {
+            return Err(AppError::new(
+                StatusCode::CONFLICT,
+                "Shadow version conflict",
+            ));
+        }
+
+        let now: sea_orm::prelude::DateTimeWithTimeZone = chrono::Utc::now().into();
+        let mut state = match section {
+            ShadowSection::Desired => shadow.desired.clone(),
+            ShadowSection::Reported => shadow.reported.clone(),
+        };
+        merge_patch(&mut state, patch);
+
+        let saved = if shadow.version == 0 {
+            let mut new_shadow = core_twin_shadow::ActiveModel {
+                twin_id: Set(twin_id),
+                desired: Set(json!({})),
+                reported: Set(json!({})),
+                version: Set(1),
+                ..Default::default()
+            };
+            match section {
+                ShadowSection::Desired => {
+                    new_shadow.desired = Set(state);
+                    new_shadow.desired_updated_at = Set(Some(now));
+                }
+                ShadowSection::Reported => {
+                    new_shadow.reported = Set(state);
+                    new_shadow.reported_updated_at = Set(Some(now));
+                }
+            }
+
+            match new_shadow.insert(db).await {
+                Ok(_) => true,
+                //Another writer created the shadow first
+                Err(error) if database_error_code(&error).as_deref() == Some(UNIQUE_VIOLATION) => {
+                    false
+                }
+                Err(error) => {
+                    return Err(insert_shadow_error(error));
+                }
+            }
+        } else {
+            let (state_column, updated_at_column) = match section {
+                ShadowSection::Desired => (
+                    core_twin_shadow::Column::Desired,
+                    core_twin_shadow::Column::DesiredUpdatedAt,
+                ),
+                ShadowSection::Reported => (
+                    core_twin_shadow::Column::Reported,
+                    core_twin_shadow::Column::ReportedUpdatedAt,
+                ),
+            };
+
+            let result = TwinShadows::update_many()
+                .col_expr(state_column, Expr::value(state))
+                .col_expr(updated_at_column, Expr::value(now))
+                .col_expr(core_twin_shadow::Column::UpdatedAt, Expr::value(now))
+                .col_expr(
+                    core_twin_shadow::Column::Version,
+                    Expr::value(shadow.version + 1),
+                )
+                .filter(core_twin_shadow::Column::TwinId.eq(twin_id))
+                .filter(core_twin_shadow::Column::Version.eq(shadow.version))
+                .exec(db)
+                .await
+                .map_err(|error| {
+                    eprintln!("Error saving twin shadow: {:?}", error);
+                    AppError::new(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Error saving twin shadow",
+                    )
+                })?;
+
+            result.rows_affected == 1
+        };
+
+        if saved {
+            return get_twin_shadow(db, twin_id).await;
+        }
+        if expected_version.is_some() {
+            return Err(AppError::new(
+                StatusCode::CONFLICT,
+                "Shadow version conflict",
+            ));
+        }
+    }
+
+    Err(AppError::new(
+        StatusCode::CONFLICT,
+        "Shadow is being updated concurrently, please try again",
+    ))
+}
+
+fn insert_shadow_error(error: DbErr) -> AppError {
+    if database_error_code(&error).as_deref() == Some(FOREIGN_KEY_VIOLATION) {
+        return AppError::new(StatusCode::NOT_FOUND, "Twin not found");
+    }
+
+    eprintln!("Error saving twin shadow: {:?}", error);
+    AppError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Error saving twin shadow",
+    )
+}
+
+fn database_error_code(error: &DbErr) -> Option<String> {
+    match error {
+        DbErr::Exec(RuntimeErr::SqlxError(error)) | DbErr::Query(RuntimeErr::SqlxError(error)) => {
+            error
+                .as_database_error()
+                .and_then(|error| error.code())
+                .map(|code| code.to_string())
+        }
+        _ => None,
+    }
+}
+
+async fn find_twin_shadow(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+) -> Result<Option<core_twin_shadow::Model>, AppError> {
+    TwinShadows::find_by_id(twin_id)
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting twin shadow: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the twin shadow",
+            )
+        })
+}
@@ -17,6 +17,7 @@ pub struct SharedData {
     pub output_response: Option<String>,
 }
 
+#[tracing::instrument(skip_all, fields(model_id = ?shared_data.model_id), err(Debug))]
 pub async fn store_usage_data(
     db: DatabaseConnection,
     shared_data: SharedData,
@@ -0,0 +1,351 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    FromQueryResult, IntoActiveModel, QueryFilter, QueryOrder, Set, Statement, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::database::core_model;
+use crate::database::core_model_telemetry_field::{self, Entity as TelemetryFields};
+use crate::database::core_twin_telemetry::{self, Entity as TwinTelemetry};
+use crate::routes::models::telemetry_config_extractor::ValidateTelemetryConfig;
+use crate::utilities::app_error::AppError;
+
+const PARTITION_PREFIX: &str = "core_twin_telemetry_";
+
+#[derive(Debug, FromQueryResult)]
+pub struct TelemetryPoint {
+    pub field: String,
+    pub time: DateTime<Utc>,
+    pub value: f64,
+}
+
+pub struct TelemetryRange {
+    pub field: Option<String>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    //None returns the raw points, otherwise points are grouped into buckets of this many seconds
+    pub bucket_secs: Option<i64>,
+    //One of avg, min, max, sum or count, checked by the caller
+    pub aggregate: String,
+    pub limit: u64,
+}
+
+//Replace the model's telemetry fields and retention in one go
+pub async fn set_telemetry_config(
+    db: &DatabaseConnection,
+    model: core_model::Model,
+    user_id: Uuid,
+    config: ValidateTelemetryConfig,
+) -> Result<(core_model::Model, Vec<core_model_telemetry_field::Model>), AppError> {
+    let txn = db.begin().await.map_err(|error| {
+        eprintln!("Error beginning transaction: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error beginning transaction",
+        )
+    })?;
+
+    let model_id = model.id;
+    let mut model = model.into_active_model();
+    model.telemetry_retention_days = Set(config.retention_days);
+    model.updated_by = Set(Some(user_id));
+    let model = model.update(&txn).await.map_err(|error| {
+        eprintln!("Error saving telemetry retention: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error saving telemetry config",
+        )
+    })?;
+
+    TelemetryFields::delete_many()
+        .filter(core_model_telemetry_field::Column::ModelId.eq(model_id))
+        .exec(&txn)
+        .await
+        .map_err(|error| {
+            eprintln!("Error removing telemetry fields: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error saving telemetry config",
+            )
+        })?;
+
+    let mut fields = vec![];
+    for field in config.fields.unwrap_or_default() {
+        let new_field = core_model_telemetry_field::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            model_id: Set(model_id),
+            name: Set(field.name.unwrap()),
+            json_path: Set(field.json_path.unwrap()),
+            component_alias: Set(field.component_alias),
+            end_point: Set(field.end_point),
+            created_by: Set(user_id),
+            ..Default::default()
+        };
+
+        fields.push(new_field.insert(&txn).await.map_err(|error| {
+            eprintln!("Error saving telemetry field: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error saving telemetry config",
+            )
+        })?);
+    }
+
+    txn.commit().await.map_err(|error| {
+        eprintln!("Error committing transaction: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error committing transaction",
+        )
+    })?;
+
+    Ok((model, fields))
+}
+
+pub async fn get_telemetry_fields(
+    db: &DatabaseConnection,
+    model_id: Uuid,
+) -> Result<Vec<core_model_telemetry_field::Model>, AppError> {
+    TelemetryFields::find()
+        .filter(core_model_telemetry_field::Column::ModelId.eq(model_id))
+        .order_by_asc(core_model_telemetry_field::Column::Name)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting telemetry fields by model id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the telemetry fields",
+            )
+        })
+}
+
+pub async fn store_telemetry_points(
+    db: &DatabaseConnection,
+    points: Vec<core_twin_telemetry::ActiveModel>,
+) -> Result<(), AppError> {
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    TwinTelemetry::insert_many(points)
+        .exec(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error saving telemetry: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error saving telemetry",
+            )
+        })?;
+
+    Ok(())
+}
+
+pub async fn query_telemetry(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    range: TelemetryRange,
+) -> Result<Vec<TelemetryPoint>, AppError> {
+    let mut values = vec![twin_id.into(), range.from.into(), range.to.into()];
+
+    //The aggregate name is whitelisted by the caller, everything else is bound
+    let (select, group_by) = match range.bucket_secs {
+        Some(bucket_secs) => {
+            values.push((bucket_secs as f64).into());
+            (
+                format!(
+                    r#"to_timestamp(floor(extract(epoch from "recordedAt") / $4) * $4) AS time, {}(value)::double precision AS value"#,
+                    range.aggregate
+                ),
+                " GROUP BY field, time",
+            )
+        }
+        None => (r#""recordedAt" AS time, value"#.to_string(), ""),
+    };
+
+    let mut sql = format!(
+        r#"SELECT "fieldName" AS field, {} FROM core_twin_telemetry WHERE "twinId" = $1 AND "recordedAt" >= $2 AND "recordedAt" < $3"#,
+        select
+    );
+    if let Some(field) = range.field {
+        values.push(field.into());
+        sql += &format!(r#" AND "fieldName" = ${}"#, values.len());
+    }
+    sql += group_by;
+    sql += &format!(" ORDER BY time, field LIMIT {}", range.limit);
+
+    TelemetryPoint::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &sql,
+        values,
+    ))
+    .all(db)
+    .await
+    .map_err(|error| {
+        eprintln!("Error querying telemetry: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "There was an error getting the telemetry",
+        )
+    })
+}
+
+//Make sure the current and next month have their own partition before data arrives for them
+pub async fn ensure_telemetry_partitions(
+    db: &DatabaseConnection,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let map_error = |error: sea_orm::DbErr| {
+        eprintln!("Error creating telemetry partition: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error creating telemetry partition",
+        )
+    };
+
+    #[derive(FromQueryResult)]
+    struct ExistingPartition {
+        name: Option<String>,
+    }
+
+    let this_month = first_of_month(now.date_naive());
+    let next_month = add_month(this_month);
+
+    for start in [this_month, next_month] {
+        let partition = format!("{}{}", PARTITION_PREFIX, start.format("%Y_%m"));
+        let end = add_month(start);
+
+        let existing = ExistingPartition::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT to_regclass($1)::text AS name",
+            vec![partition.clone().into()],
+        ))
+        .one(db)
+        .await
+        .map_err(map_error)?
+        .and_then(|existing| existing.name);
+        if existing.is_some() {
+            continue;
+        }
+
+        //Rows of this month that landed in the default partition, e.g. while the task was not running,
+        //would make the new partition fail. They are moved over in the same transaction
+        let txn = db.begin().await.map_err(map_error)?;
+        for sql in [
+            format!(
+                r#"CREATE TEMPORARY TABLE core_twin_telemetry_moved ON COMMIT DROP AS
+                SELECT * FROM core_twin_telemetry_default WHERE "recordedAt" >= '{}' AND "recordedAt" < '{}'"#,
+                start, end
+            ),
+            format!(
+                r#"DELETE FROM core_twin_telemetry_default WHERE "recordedAt" >= '{}' AND "recordedAt" < '{}'"#,
+                start, end
+            ),
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} PARTITION OF core_twin_telemetry FOR VALUES FROM ('{}') TO ('{}')",
+                partition, start, end
+            ),
+            "INSERT INTO core_twin_telemetry SELECT * FROM core_twin_telemetry_moved".to_string(),
+        ] {
+            txn.execute(Statement::from_string(DbBackend::Postgres, sql))
+                .await
+                .map_err(map_error)?;
+        }
+        txn.commit().await.map_err(map_error)?;
+    }
+
+    Ok(())
+}
+
+//Delete points past their model's retention and drop monthly partitions no model needs anymore
+pub async fn prune_telemetry(
+    db: &DatabaseConnection,
+    default_retention_days: i32,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let map_error = |error: sea_orm::DbErr| {
+        eprintln!("Error pruning telemetry: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error pruning telemetry",
+        )
+    };
+
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"DELETE FROM core_twin_telemetry t USING core_twin tw, core_model m
+        WHERE t."twinId" = tw.id AND tw."modelId" = m.id
+        AND t."recordedAt" < $2 - make_interval(days => COALESCE(m."telemetryRetentionDays", $1))"#,
+        vec![default_retention_days.into(), now.into()],
+    ))
+    .await
+    .map_err(map_error)?;
+
+    #[derive(FromQueryResult)]
+    struct MaxRetention {
+        days: i32,
+    }
+    let max_retention = MaxRetention::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT GREATEST(COALESCE(MAX("telemetryRetentionDays"), $1), $1) AS days FROM core_model"#,
+        vec![default_retention_days.into()],
+    ))
+    .one(db)
+    .await
+    .map_err(map_error)?
+    .map(|max_retention| max_retention.days)
+    .unwrap_or(default_retention_days);
+
+    #[derive(FromQueryResult)]
+    struct Partition {
+        name: String,
+    }
+    let partitions = Partition::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        r#"SELECT child.relname::text AS name FROM pg_inherits
+        JOIN pg_class parent ON parent.oid = pg_inherits.inhparent
+        JOIN pg_class child ON child.oid = pg_inherits.inhrelid
+        WHERE parent.relname = 'core_twin_telemetry'"#
+            .to_string(),
+    ))
+    .all(db)
+    .await
+    .map_err(map_error)?;
+
+    let cutoff = (now - chrono::Duration::days(max_retention as i64)).date_naive();
+    for partition in partitions {
+        let start = match partition
+            .name
+            .strip_prefix(PARTITION_PREFIX)
+            .and_then(|month| NaiveDate::parse_from_str(&format!("{}_01", month), "%Y_%m_%d").ok())
+        {
+            Some(start) => start,
+            None => continue,
+        };
+
+        if add_month(start) <= cutoff {
+            db.execute(Statement::from_string(
+                DbBackend::Postgres,
+                format!("DROP TABLE IF EXISTS {}", partition.name),
+            ))
+            .await
+            .map_err(map_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap()
+}
+
+fn add_month(date: NaiveDate) -> NaiveDate {
+    match date.month() {
+        12 => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1).unwrap(),
+        month => NaiveDate::from_ymd_opt(date.year(), month + 1, 1).unwrap(),
+    }
+}
@@ -1,7 +1,7 @@
 use axum::http::{Response, StatusCode};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
-    TransactionTrait, TryIntoModel,
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Set, TransactionTrait, TryIntoModel,
 };
 use serde_json::json;
 use uuid::Uuid;
@@ -193,6 +193,51 @@ pub async fn find_twin_by_id(
     })
 }
 
+pub async fn get_exposed_twin_components(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+) -> Result<Vec<core_twin_component::Model>, AppError> {
+    TwinComponents::find()
+        .filter(core_twin_component::Column::TwinId.eq(twin_id))
+        .filter(core_twin_component::Column::IsExposed.eq(true))
+        .filter(core_twin_component::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting twin components by twin id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your twin components",
+            )
+        })
+}
+
+pub async fn find_exposed_twin_component_by_alias(
+    db: &DatabaseConnection,
+    twin_id: Uuid,
+    component_alias: &str,
+) -> Result<core_twin_component::Model, AppError> {
+    let component = TwinComponents::find()
+        .filter(core_twin_component::Column::TwinId.eq(twin_id))
+        .filter(core_twin_component::Column::ComponentAlias.eq(component_alias))
+        .filter(core_twin_component::Column::IsExposed.eq(true))
+        .filter(core_twin_component::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting twin component by alias: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting your twin component",
+            )
+        })?;
+
+    component.ok_or_else(|| {
+        eprintln!("Could not find exposed twin component {}", component_alias);
+        AppError::new(StatusCode::NOT_FOUND, "Component not found")
+    })
+}
+
 pub async fn find_twin_by_model_id(
     db: &DatabaseConnection,
     model_id: Uuid,
@@ -219,6 +264,52 @@ pub async fn find_twin_by_model_id(
     })
 }
 
+//Owners reach their subscribers' twins through the model rather than the user
+pub async fn find_model_twin(
+    db: &DatabaseConnection,
+    id: Uuid,
+    model_id: Uuid,
+) -> Result<(core_twin::Model, Vec<core_twin_component::Model>), AppError> {
+    let model = Models::find_by_id(id)
+        .filter(core_twin::Column::ModelId.eq(model_id))
+        .filter(core_twin::Column::DeletedAt.is_null())
+        .find_with_related(TwinComponents)
+        .all(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting twin by id and model id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the digital twin",
+            )
+        })?;
+
+    model.into_iter().next().ok_or_else(|| {
+        eprintln!("Could not find digital twin by id and model id");
+        AppError::new(StatusCode::NOT_FOUND, "not found")
+    })
+}
+
+//Move the twin to Deactivated, only the call that actually changes its status gets true
+pub async fn deactivate_twin(db: &DatabaseConnection, id: Uuid) -> Result<bool, AppError> {
+    let now: sea_orm::prelude::DateTimeWithTimeZone = chrono::Utc::now().into();
+
+    let result = Models::update_many()
+        .col_expr(core_twin::Column::TwinStatusId, Expr::value(3))
+        .col_expr(core_twin::Column::UpdatedAt, Expr::value(now))
+        .filter(core_twin::Column::Id.eq(id))
+        .filter(core_twin::Column::TwinStatusId.is_not_in([3, 4]))
+        .filter(core_twin::Column::DeletedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error deactivating twin: {:?}", error);
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error saving twin")
+        })?;
+
+    Ok(result.rows_affected == 1)
+}
+
 pub async fn get_all_user_twins(
     db: &DatabaseConnection,
     user_id: Uuid,
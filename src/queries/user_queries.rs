@@ -12,8 +12,10 @@ use crate::{
 };
 use axum::{http::StatusCode, Json};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, TryIntoModel,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set, TryIntoModel,
 };
+use serde_json::Value;
 use uuid::Uuid;
 
 use super::role_queries::find_role_by_name;
@@ -135,6 +137,41 @@ pub async fn find_user_by_email(
         .ok_or_else(|| AppError::new(StatusCode::BAD_REQUEST, "Incorrect email and/or password"))
 }
 
+pub async fn find_user_by_id(db: &DatabaseConnection, id: Uuid) -> Result<UserModel, AppError> {
+    Users::find_by_id(id)
+        .filter(core_user::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|error| {
+            eprintln!("Error getting user by id: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "There was an error getting the user",
+            )
+        })?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "User not found"))
+}
+
+pub async fn update_user_attributes(
+    db: &DatabaseConnection,
+    user: UserModel,
+    attributes: Value,
+    updated_by: Uuid,
+) -> Result<UserModel, AppError> {
+    let mut user = user.into_active_model();
+    user.attributes = Set(attributes);
+    user.updated_by = Set(Some(updated_by));
+    user.updated_at = Set(Some(chrono::Utc::now().into()));
+
+    user.update(db).await.map_err(|error| {
+        eprintln!("Error updating user attributes: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "There was an error saving the user attributes",
+        )
+    })
+}
+
 fn convert_active_to_model(active_user: core_user::ActiveModel) -> Result<UserModel, AppError> {
     active_user.try_into_model().map_err(|error| {
         eprintln!("Error converting task active model to model: {:?}", error);
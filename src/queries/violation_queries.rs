@@ -0,0 +1,139 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::database::core_policy_action::{self, Entity as PolicyActions};
+use crate::database::core_policy_violation::{self, Entity as PolicyViolations};
+use crate::utilities::app_error::AppError;
+
+pub struct PolicyViolation {
+    pub user_id: Uuid,
+    pub model_id: Uuid,
+    pub policy_id: Uuid,
+    pub action_id: Uuid,
+    pub twin_id: Uuid,
+    //quota_exceeded or the reason the validity window refused the call
+    pub reason: String,
+}
+
+//Every field narrows the result, None leaves it open
+#[derive(Default)]
+pub struct ViolationFilter {
+    pub model_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub twin_id: Option<Uuid>,
+    pub policy_id: Option<Uuid>,
+    pub end_point: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+pub async fn create_policy_violation(
+    db: &DatabaseConnection,
+    violation: PolicyViolation,
+) -> Result<core_policy_violation::Model, AppError> {
+    let new_violation = core_policy_violation::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(violation.user_id),
+        model_id: Set(violation.model_id),
+        policy_id: Set(violation.policy_id),
+        action_id: Set(violation.action_id),
+        twin_id: Set(violation.twin_id),
+        reason: Set(Some(violation.reason)),
+        ..Default::default()
+    };
+
+    new_violation.insert(db).await.map_err(|error| {
+        eprintln!("Error saving policy violation: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error saving policy violation",
+        )
+    })
+}
+
+//Newest first, page is zero based. Returns the page and the total number of matching violations
+pub async fn get_policy_violations(
+    db: &DatabaseConnection,
+    filter: ViolationFilter,
+    page: u64,
+    page_size: u64,
+) -> Result<
+    (
+        Vec<(
+            core_policy_violation::Model,
+            Option<core_policy_action::Model>,
+        )>,
+        u64,
+    ),
+    AppError,
+> {
+    let mut query = PolicyViolations::find().find_also_related(PolicyActions);
+    if let Some(model_id) = filter.model_id {
+        query = query.filter(core_policy_violation::Column::ModelId.eq(model_id));
+    }
+    if let Some(user_id) = filter.user_id {
+        query = query.filter(core_policy_violation::Column::UserId.eq(user_id));
+    }
+    if let Some(twin_id) = filter.twin_id {
+        query = query.filter(core_policy_violation::Column::TwinId.eq(twin_id));
+    }
+    if let Some(policy_id) = filter.policy_id {
+        query = query.filter(core_policy_violation::Column::PolicyId.eq(policy_id));
+    }
+    if let Some(end_point) = filter.end_point {
+        query = query.filter(core_policy_action::Column::EndPoint.eq(end_point));
+    }
+    if let Some(from) = filter.from {
+        query = query.filter(core_policy_violation::Column::ViolatedAt.gte(from));
+    }
+    if let Some(to) = filter.to {
+        query = query.filter(core_policy_violation::Column::ViolatedAt.lt(to));
+    }
+
+    let paginator = query
+        .order_by_desc(core_policy_violation::Column::ViolatedAt)
+        .paginate(db, page_size);
+
+    let map_error = |error| {
+        eprintln!("Error getting policy violations: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "There was an error getting the policy violations",
+        )
+    };
+
+    let total = paginator.num_items().await.map_err(map_error)?;
+    let violations = paginator.fetch_page(page).await.map_err(map_error)?;
+
+    Ok((violations, total))
+}
+
+//Violations of the policy by the user on the twin, counting only those after `since` when given
+pub async fn count_policy_violations(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    policy_id: Uuid,
+    twin_id: Uuid,
+    since: Option<DateTimeWithTimeZone>,
+) -> Result<u64, AppError> {
+    let mut query = PolicyViolations::find()
+        .filter(core_policy_violation::Column::UserId.eq(user_id))
+        .filter(core_policy_violation::Column::PolicyId.eq(policy_id))
+        .filter(core_policy_violation::Column::TwinId.eq(twin_id));
+    if let Some(since) = since {
+        query = query.filter(core_policy_violation::Column::ViolatedAt.gt(since));
+    }
+
+    query.count(db).await.map_err(|error| {
+        eprintln!("Error counting policy violations: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "There was an error counting the policy violations",
+        )
+    })
+}
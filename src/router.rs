@@ -1,55 +1,135 @@
 use axum::{
     middleware,
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Extension, Router,
 };
 
 use crate::{
     app_state::AppState,
-    middleware::require_authentication::require_authentication,
+    middleware::{request_id::propagate_request_id, require_authentication::require_authentication},
     routes::{
         models::{
             create_model::create_model,
             delete_model::delete_model,
             get_all_models::{get_all_owner_models, get_all_publsihed_models},
+            get_owner_events::get_owner_events,
+            model_console::{get_console_calls, owner_console_handler},
+            model_openapi::{
+                get_openapi_documents, get_openapi_policy_actions, upload_openapi_document,
+            },
+            model_telemetry::{get_telemetry_config, set_telemetry_config},
             publish_model::publish_model,
             unpublish_model::unpublish_model,
         },
+        invocations::get_invocation::get_invocation,
+        pipelines::{
+            create_pipeline::create_pipeline,
+            delete_pipeline::delete_pipeline,
+            get_pipelines::{get_pipeline, get_pipelines},
+            run_pipeline::run_pipeline,
+        },
         policys::{
             create_policy::create_policy,
             get_latest_policy::{get_all_model_policies, get_latest_model_policy},
+            get_policy_violations::{get_model_violations, get_twin_violations},
+            policy_blocks::{get_block_decisions, reinstate_user_twin},
+            policy_documents::{export_policy_document, import_policy_document},
+            policy_odrl::{export_odrl_policy, import_odrl_policy},
         },
         twins::{
             delete_twin::soft_delete_twin,
             get_all_user_twins::get_all_user_twins,
+            get_twin_events::get_twin_events,
             get_one_user_twin::get_one_user_twin,
             subscribe_to_model::subscribe,
+            twin_batch::batch_request_handler,
+            twin_mqtt_bindings::{create_mqtt_binding, delete_mqtt_binding, get_mqtt_bindings},
             twin_operations::{start_twins, stop_twins},
-            twin_usage::remote_request_handler,
+            twin_schedules::{
+                create_schedule, delete_schedule, get_schedule_results, get_schedules,
+            },
+            twin_shadow::{
+                get_shadow_for_twin, get_twin_shadow, report_twin_state, update_desired_state,
+            },
+            twin_streaming::{component_stream_handler, remote_stream_handler},
+            twin_telemetry::get_twin_telemetry,
+            twin_usage::{component_request_handler, remote_request_handler},
         },
         users::{
             login::login,
             logout::logout,
             signup::{signup_owner, signup_user},
+            user_attributes::{get_user_attributes, set_user_attributes},
         },
     },
 };
-use hyper::Client;
+use crate::helpers::{
+    mqtt_bridge_helpers::MqttBridge, schedule_helpers::start_schedule_runner,
+    shadow_helpers::shadow_callbacks_enabled, telemetry_helpers::start_telemetry_retention,
+};
+use crate::utilities::{
+    upstream_client::{UpstreamClient, UpstreamConfig},
+    webhook_client::WebhookClient,
+};
 use tower_cookies::CookieManagerLayer;
 
 pub async fn create_router(app_state: AppState) -> Router {
-    let client = Client::new();
+    let client =
+        UpstreamClient::new(UpstreamConfig::from_env()).with_events(app_state.redis_url.clone());
+    let webhook_client = WebhookClient::from_env();
+    if webhook_client.is_none() {
+        eprintln!("WEBHOOK_SECRET is not set, async invocations can only be polled");
+    }
+    start_telemetry_retention(app_state.db.clone());
+    start_schedule_runner(
+        app_state.db.clone(),
+        app_state.redis_url.clone(),
+        client.clone(),
+    );
+    //Stays disabled unless MQTT_HOST is set
+    let mqtt_bridge = MqttBridge::start(
+        app_state.db.clone(),
+        app_state.redis_url.clone(),
+        client.clone(),
+    )
+    .await;
 
-    Router::new()
+    let router = Router::new()
         .route(
             "/user/twins/:twin_id/action/:endpoint_id",
-            post(remote_request_handler),
+            post(remote_request_handler).get(remote_stream_handler),
+        )
+        .route(
+            "/user/twins/:twin_id/components/:alias/action/*path",
+            post(component_request_handler).get(component_stream_handler),
+        )
+        .route("/user/twins/:twin_id/batch", post(batch_request_handler))
+        .route("/user/pipelines/:pipeline_id/run", post(run_pipeline))
+        .route(
+            "/owner/:model_id/console/action/*path",
+            post(owner_console_handler),
+        )
+        .route(
+            "/user/twins/:twin_id/shadow/desired",
+            patch(update_desired_state),
+        )
+        .route(
+            "/user/twins/:twin_id/mqtt-bindings",
+            post(create_mqtt_binding).get(get_mqtt_bindings),
+        )
+        .route(
+            "/user/twins/:twin_id/mqtt-bindings/:binding_id",
+            delete(delete_mqtt_binding),
         )
         .layer(Extension(client))
+        .layer(Extension(webhook_client))
+        .layer(Extension(mqtt_bridge))
+        .route("/user/invocations/:invocation_id", get(get_invocation))
         .route("/api/users/logout", post(logout))
         .route("/user/hello", get(|| async { "Hello, World!" }))
         .route("/owner/bye", get(|| async { "Goodbye, World!" }))
         .route("/owner/models", get(get_all_owner_models))
+        .route("/owner/events", get(get_owner_events))
         .route("/user/models", get(get_all_publsihed_models))
         .route("/user/models/:model_id/subscribe", post(subscribe))
         .route("/user/twins", get(get_all_user_twins))
@@ -57,13 +137,73 @@ pub async fn create_router(app_state: AppState) -> Router {
         .route("/user/twins/:twin_id", delete(soft_delete_twin))
         .route("/user/twins/:twin_id/start", put(start_twins))
         .route("/user/twins/:twin_id/stop", put(stop_twins))
+        .route("/user/twins/:twin_id/telemetry", get(get_twin_telemetry))
+        .route("/user/twins/:twin_id/shadow", get(get_twin_shadow))
+        .route("/user/twins/:twin_id/events", get(get_twin_events))
+        .route("/user/twins/:twin_id/violations", get(get_twin_violations))
+        .route(
+            "/user/twins/:twin_id/schedules",
+            post(create_schedule).get(get_schedules),
+        )
+        .route(
+            "/user/twins/:twin_id/schedules/:schedule_id",
+            delete(delete_schedule),
+        )
+        .route(
+            "/user/twins/:twin_id/schedules/:schedule_id/results",
+            get(get_schedule_results),
+        )
+        .route(
+            "/user/pipelines",
+            post(create_pipeline).get(get_pipelines),
+        )
+        .route(
+            "/user/pipelines/:pipeline_id",
+            get(get_pipeline).delete(delete_pipeline),
+        )
         .route("/owner/deploy", post(create_model))
         .route("/owner/:model_id/publish", put(publish_model))
         .route("/owner/:model_id/unpublish", put(unpublish_model))
         .route("/owner/:model_id/policy", post(create_policy))
         .route("/owner/:model_id/policy", get(get_latest_model_policy))
         .route("/owner/:model_id/policies", get(get_all_model_policies))
+        .route(
+            "/owner/:model_id/policy/document",
+            post(import_policy_document),
+        )
+        .route(
+            "/owner/:model_id/policies/:policy_version/document",
+            get(export_policy_document),
+        )
+        .route("/owner/:model_id/policy/odrl", post(import_odrl_policy))
+        .route(
+            "/owner/:model_id/policies/:policy_version/odrl",
+            get(export_odrl_policy),
+        )
+        .route("/owner/:model_id/violations", get(get_model_violations))
+        .route("/owner/:model_id/blocks", get(get_block_decisions))
+        .route(
+            "/owner/:model_id/twins/:twin_id/reinstate",
+            post(reinstate_user_twin),
+        )
+        .route("/owner/:model_id/console/calls", get(get_console_calls))
+        .route(
+            "/owner/:model_id/telemetry",
+            put(set_telemetry_config).get(get_telemetry_config),
+        )
+        .route(
+            "/owner/:model_id/openapi",
+            put(upload_openapi_document).get(get_openapi_documents),
+        )
+        .route(
+            "/owner/:model_id/openapi/actions",
+            get(get_openapi_policy_actions),
+        )
         .route("/owner/:model_id", delete(delete_model))
+        .route(
+            "/admin/users/:user_id/attributes",
+            put(set_user_attributes).get(get_user_attributes),
+        )
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
             require_authentication,
@@ -71,7 +211,20 @@ pub async fn create_router(app_state: AppState) -> Router {
         .route("/login", get(login))
         .route("/users/signup", post(signup_user))
         .route("/owners/signup", post(signup_owner))
-        .route("/users/login", post(login))
+        .route("/users/login", post(login));
+
+    //Twins authenticate with their own token instead of a user session, there are no tokens without SHADOW_SECRET
+    let router = if shadow_callbacks_enabled() {
+        router
+            .route("/twins/:twin_id/shadow", get(get_shadow_for_twin))
+            .route("/twins/:twin_id/shadow/reported", post(report_twin_state))
+    } else {
+        eprintln!("SHADOW_SECRET is not set, twin shadow callbacks are disabled");
+        router
+    };
+
+    router
         .layer(CookieManagerLayer::new())
+        .layer(middleware::from_fn(propagate_request_id))
         .with_state(app_state.clone())
 }
@@ -0,0 +1,24 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    database::core_user::Model as UserModel,
+    helpers::async_invocation_helpers,
+    utilities::{app_error::AppError, redis_connection_wrapper::RedisConnWrapper},
+};
+
+use super::ResponseInvocation;
+
+pub async fn get_invocation(
+    Path(invocation_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(redis_url): State<RedisConnWrapper>,
+) -> Result<Json<ResponseInvocation>, AppError> {
+    let invocation =
+        async_invocation_helpers::get_invocation(redis_url, invocation_id, user.id).await?;
+
+    Ok(Json(ResponseInvocation { data: invocation }))
+}
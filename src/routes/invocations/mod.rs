@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::async_invocation_helpers::InvocationRecord;
+
+pub mod get_invocation;
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseInvocation {
+    pub data: InvocationRecord,
+}
@@ -1,4 +1,6 @@
+pub mod invocations;
 pub mod models;
+pub mod pipelines;
 pub mod policys;
 pub mod twins;
 pub mod users;
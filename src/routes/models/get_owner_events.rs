@@ -0,0 +1,33 @@
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    Extension,
+};
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+    database::core_user,
+    utilities::{
+        app_error::AppError, redis_connection_wrapper::RedisConnWrapper,
+        twin_events::subscribe_owner_events,
+    },
+};
+
+//Stream notifications about the subscribers' twins of every model the owner publishes, such as automatic blocks
+pub async fn get_owner_events(
+    Extension(user): Extension<core_user::Model>,
+    State(redis_url): State<RedisConnWrapper>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let events = subscribe_owner_events(redis_url, user.id).await?;
+
+    let stream = events.map(|event| {
+        Ok(Event::default()
+            .event(event.kind.clone())
+            .json_data(&event)
+            .unwrap_or_default())
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15))))
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
+use crate::helpers::openapi_helpers::DraftPolicyAction;
+
 use self::{
     create_model_component_extractor::ValidateCreateModelComponent,
     create_model_extractor::ValidateCreateModel,
@@ -11,7 +14,12 @@ pub mod create_model_component_extractor;
 pub mod create_model_extractor;
 pub mod delete_model;
 pub mod get_all_models;
+pub mod get_owner_events;
+pub mod model_console;
+pub mod model_openapi;
+pub mod model_telemetry;
 pub mod publish_model;
+pub mod telemetry_config_extractor;
 pub mod unpublish_model;
 
 #[derive(Serialize, Deserialize)]
@@ -35,3 +43,97 @@ pub struct RequestModelValidated {
     pub model_info: ValidateCreateModel,
     pub comp_info: Vec<ValidateCreateModelComponent>,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct ConsoleQuery {
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    //Console calls are not captured as shared data unless the owner asks for it
+    #[serde(rename = "shareData")]
+    pub share_data: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseConsoleCall {
+    pub id: Uuid,
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    #[serde(rename = "statusCode")]
+    pub status_code: i32,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: i32,
+    #[serde(rename = "errorMessage")]
+    pub error_message: Option<String>,
+    #[serde(rename = "dataShared")]
+    pub data_shared: bool,
+    #[serde(rename = "calledAt")]
+    pub called_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseConsoleCalls {
+    pub data: Vec<ResponseConsoleCall>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseTelemetryField {
+    pub name: String,
+    #[serde(rename = "jsonPath")]
+    pub json_path: String,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseTelemetryConfig {
+    //None falls back to TELEMETRY_RETENTION_DAYS
+    #[serde(rename = "retentionDays")]
+    pub retention_days: Option<i32>,
+    pub fields: Vec<ResponseTelemetryField>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseTelemetryConfigData {
+    pub data: ResponseTelemetryConfig,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestOpenApiDocument {
+    //None attaches the document to the twin's default entry point
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    pub document: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseOpenApiDocument {
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    //upload or component when it was fetched at publish
+    pub source: String,
+    pub operations: usize,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+    pub document: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseOpenApiDocuments {
+    pub data: Vec<ResponseOpenApiDocument>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseOpenApiDocumentData {
+    pub data: ResponseOpenApiDocument,
+}
+
+#[derive(Serialize)]
+pub struct ResponseDraftPolicyActions {
+    pub data: Vec<DraftPolicyAction>,
+}
@@ -0,0 +1,146 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use hyper::{Body, Request as HyperRequest};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::core_user,
+    helpers::twin_invocation_helpers::{invoke_twin_endpoint_with, InvocationOptions, TwinRequest},
+    queries::{
+        console_queries::{self, ConsoleCall},
+        model_queries, twin_queries,
+    },
+    utilities::{
+        app_error::AppError, redis_connection_wrapper::RedisConnWrapper,
+        upstream_client::UpstreamClient,
+    },
+};
+
+use super::{ConsoleQuery, ResponseConsoleCall, ResponseConsoleCalls};
+
+const CONSOLE_CALLS_LIMIT: u64 = 100;
+
+//Lets owners try their published model on the twin created at publish time, without subscribing
+pub async fn owner_console_handler(
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Extension(client): Extension<UpstreamClient>,
+    Extension(user): Extension<core_user::Model>,
+    Path(path_params): Path<(Uuid, String)>,
+    Query(query): Query<ConsoleQuery>,
+    mut req: HyperRequest<Body>,
+) -> Result<Response, AppError> {
+    let (model_id, path) = path_params;
+
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+    if !model.is_published {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Model is not published",
+        ));
+    }
+
+    let (twin, _twin_components) =
+        twin_queries::find_twin_by_model_id(&db, model_id, user.id).await?;
+    if twin.twin_status_id != 2 {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Twin is not running",
+        ));
+    }
+
+    let input_body_bytes = hyper::body::to_bytes(req.body_mut())
+        .await
+        .map_err(|error| {
+            eprintln!("Error reading request body: {:?}", error);
+            AppError::new(StatusCode::BAD_REQUEST, "Could not read request body")
+        })?;
+
+    let endpoint_id = path.trim_start_matches('/').to_string();
+    let share_data = query.share_data.unwrap_or(false);
+
+    //Owners are not bound by their own policy and always get a fresh answer from the twin
+    let options = InvocationOptions {
+        enforce_policy: false,
+        use_cache: false,
+        capture_data: Some(share_data),
+    };
+
+    let started_at = Instant::now();
+    let result = invoke_twin_endpoint_with(
+        &db,
+        redis_url,
+        &client,
+        user.id,
+        &twin,
+        TwinRequest {
+            component_alias: query.component_alias.clone(),
+            endpoint_id: endpoint_id.clone(),
+            headers: req.headers().clone(),
+            body: input_body_bytes,
+        },
+        &options,
+    )
+    .await;
+
+    let (status_code, error_message) = match &result {
+        Ok(response) => (response.status.as_u16(), None),
+        Err(error) => (error.code().as_u16(), Some(error.message().to_string())),
+    };
+
+    //Bypassing the policy still leaves a trace of every call
+    let log_result = console_queries::log_console_call(
+        &db,
+        ConsoleCall {
+            owner_id: user.id,
+            model_id,
+            twin_id: twin.id,
+            component_alias: query.component_alias,
+            end_point: endpoint_id,
+            status_code,
+            duration_ms: started_at.elapsed().as_millis(),
+            error_message,
+            data_shared: share_data,
+        },
+    )
+    .await;
+    if let Err(error) = log_result {
+        eprintln!("Error logging console call: {:?}", error);
+    }
+
+    Ok(result?.into_response().into_response())
+}
+
+pub async fn get_console_calls(
+    Path(model_id): Path<Uuid>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseConsoleCalls>, AppError> {
+    let calls =
+        console_queries::get_console_calls(&db, model_id, user.id, CONSOLE_CALLS_LIMIT).await?;
+
+    let calls = calls
+        .into_iter()
+        .map(|call| ResponseConsoleCall {
+            id: call.id,
+            twin_id: call.twin_id,
+            component_alias: call.component_alias,
+            end_point: call.end_point,
+            status_code: call.status_code,
+            duration_ms: call.duration_ms,
+            error_message: call.error_message,
+            data_shared: call.data_shared,
+            called_at: call.called_at.to_rfc3339(),
+        })
+        .collect::<Vec<ResponseConsoleCall>>();
+
+    Ok(Json(ResponseConsoleCalls { data: calls }))
+}
@@ -0,0 +1,112 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_model_openapi, core_user},
+    helpers::openapi_helpers::{
+        draft_policy_actions, openapi_operations, validate_openapi_document,
+    },
+    queries::{
+        model_queries,
+        openapi_queries::{self, OPENAPI_SOURCE_UPLOAD},
+    },
+    utilities::app_error::AppError,
+};
+
+use super::{
+    RequestOpenApiDocument, ResponseDraftPolicyActions, ResponseOpenApiDocument,
+    ResponseOpenApiDocumentData, ResponseOpenApiDocuments,
+};
+
+pub async fn upload_openapi_document(
+    Path(model_id): Path<Uuid>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+    Json(upload): Json<RequestOpenApiDocument>,
+) -> Result<Json<ResponseOpenApiDocumentData>, AppError> {
+    let (model, model_components) = model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    validate_openapi_document(&upload.document)?;
+
+    if let Some(component_alias) = upload.component_alias.as_deref() {
+        let known = model_components.iter().any(|component| {
+            component.is_exposed && component.component_alias.as_deref() == Some(component_alias)
+        });
+        if !known {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Unknown exposed component: {}", component_alias),
+            ));
+        }
+    }
+
+    let document = openapi_queries::save_openapi_document(
+        &db,
+        model.id,
+        upload.component_alias,
+        upload.document,
+        OPENAPI_SOURCE_UPLOAD,
+        user.id,
+    )
+    .await?
+    .ok_or_else(|| {
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error saving openapi document",
+        )
+    })?;
+
+    Ok(Json(ResponseOpenApiDocumentData {
+        data: to_response_document(document),
+    }))
+}
+
+pub async fn get_openapi_documents(
+    Path(model_id): Path<Uuid>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseOpenApiDocuments>, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let documents = openapi_queries::get_model_openapi_documents(&db, model.id)
+        .await?
+        .into_iter()
+        .map(to_response_document)
+        .collect::<Vec<ResponseOpenApiDocument>>();
+
+    Ok(Json(ResponseOpenApiDocuments { data: documents }))
+}
+
+//Draft actions for every documented operation, to be completed with quotas before creating a policy
+pub async fn get_openapi_policy_actions(
+    Path(model_id): Path<Uuid>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseDraftPolicyActions>, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let actions = openapi_queries::get_model_openapi_documents(&db, model.id)
+        .await?
+        .iter()
+        .flat_map(draft_policy_actions)
+        .collect();
+
+    Ok(Json(ResponseDraftPolicyActions { data: actions }))
+}
+
+fn to_response_document(document: core_model_openapi::Model) -> ResponseOpenApiDocument {
+    ResponseOpenApiDocument {
+        component_alias: document.component_alias,
+        source: document.source,
+        operations: openapi_operations(&document.document).len(),
+        updated_at: document.updated_at.to_rfc3339(),
+        document: document.document,
+    }
+}
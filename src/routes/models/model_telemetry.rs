@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_model_telemetry_field, core_user},
+    queries::{model_queries, telemetry_queries},
+    utilities::{app_error::AppError, json_path::parse_json_path},
+};
+
+use super::{
+    telemetry_config_extractor::ValidateTelemetryConfig, ResponseTelemetryConfig,
+    ResponseTelemetryConfigData, ResponseTelemetryField,
+};
+
+pub async fn set_telemetry_config(
+    Path(model_id): Path<Uuid>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+    config: ValidateTelemetryConfig,
+) -> Result<Json<ResponseTelemetryConfigData>, AppError> {
+    let (model, model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let mut names = HashSet::new();
+    for field in config.fields.iter().flatten() {
+        let name = field.name.as_deref().unwrap_or_default();
+        if !names.insert(name) {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Duplicate telemetry field: {}", name),
+            ));
+        }
+
+        parse_json_path(field.json_path.as_deref().unwrap_or_default())?;
+
+        if let Some(component_alias) = field.component_alias.as_deref() {
+            let known = model_components.iter().any(|component| {
+                component.is_exposed && component.component_alias.as_deref() == Some(component_alias)
+            });
+            if !known {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown exposed component: {}", component_alias),
+                ));
+            }
+        }
+    }
+
+    let (model, fields) =
+        telemetry_queries::set_telemetry_config(&db, model, user.id, config).await?;
+
+    Ok(Json(ResponseTelemetryConfigData {
+        data: to_response_config(model.telemetry_retention_days, fields),
+    }))
+}
+
+pub async fn get_telemetry_config(
+    Path(model_id): Path<Uuid>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseTelemetryConfigData>, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+    let fields = telemetry_queries::get_telemetry_fields(&db, model.id).await?;
+
+    Ok(Json(ResponseTelemetryConfigData {
+        data: to_response_config(model.telemetry_retention_days, fields),
+    }))
+}
+
+fn to_response_config(
+    retention_days: Option<i32>,
+    fields: Vec<core_model_telemetry_field::Model>,
+) -> ResponseTelemetryConfig {
+    ResponseTelemetryConfig {
+        retention_days,
+        fields: fields
+            .into_iter()
+            .map(|field| ResponseTelemetryField {
+                name: field.name,
+                json_path: field.json_path,
+                component_alias: field.component_alias,
+                end_point: field.end_point,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,79 @@
+use crate::utilities::app_error::AppError;
+use axum::{
+    async_trait,
+    body::HttpBody,
+    extract::FromRequest,
+    http::{Request, StatusCode},
+    BoxError, Json, RequestExt,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Validate, Deserialize, Serialize)]
+pub struct ValidateTelemetryField {
+    #[validate(required(message = "missing telemetry field name"))]
+    pub name: Option<String>,
+    #[validate(required(message = "missing telemetry field jsonPath"))]
+    #[serde(rename = "jsonPath")]
+    pub json_path: Option<String>,
+    //Both unset means the field is read from every response of the twin
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: Option<String>,
+}
+
+#[derive(Debug, Validate, Deserialize, Serialize)]
+pub struct ValidateTelemetryConfig {
+    #[validate(range(min = 1, max = 3650, message = "retentionDays must be between 1 and 3650"))]
+    #[serde(rename = "retentionDays")]
+    pub retention_days: Option<i32>,
+    #[validate(required(message = "missing telemetry fields"))]
+    pub fields: Option<Vec<ValidateTelemetryField>>,
+}
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for ValidateTelemetryConfig
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(
+        req: Request<B>,
+        _state: &S,
+    ) -> Result<ValidateTelemetryConfig, Self::Rejection> {
+        let Json(config) = req
+            .extract::<Json<ValidateTelemetryConfig>, _>()
+            .await
+            .map_err(|error| {
+                eprintln!("Error extracting telemetry config: {:?}", error);
+                AppError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Something went wrong, please try again",
+                )
+            })?;
+
+        let field_results = config
+            .fields
+            .iter()
+            .flatten()
+            .map(|field| field.validate());
+        for result in std::iter::once(config.validate()).chain(field_results) {
+            if let Err(errors) = result {
+                let field_errors = errors.field_errors();
+                if let Some((_, error)) = field_errors.into_iter().next() {
+                    return Err(AppError::new(
+                        StatusCode::BAD_REQUEST,
+                        error.first().unwrap().clone().message.unwrap().to_string(), // feel safe unwrapping because we know there is at least one error, and we only care about the first for this api
+                    ));
+                }
+            }
+        }
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,42 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    database::core_user::Model as UserModel,
+    helpers::pipeline_helpers::validate_pipeline_steps,
+    queries::{pipeline_queries, twin_queries},
+    utilities::app_error::AppError,
+};
+
+use super::{
+    create_pipeline_extractor::ValidateCreatePipeline, get_pipelines::to_response_pipeline,
+    ResponsePipelineData,
+};
+
+pub async fn create_pipeline(
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+    pipeline: ValidateCreatePipeline,
+) -> Result<(StatusCode, Json<ResponsePipelineData>), AppError> {
+    let steps = pipeline.steps.as_deref().unwrap_or_default();
+    validate_pipeline_steps(steps)?;
+
+    //Every step must target one of the user's own twins
+    for step in steps {
+        let (twin, _twin_status) =
+            twin_queries::get_one_user_twin(&db, step.twin_id, user.id).await?;
+        if let Some(component_alias) = &step.component_alias {
+            twin_queries::find_exposed_twin_component_by_alias(&db, twin.id, component_alias)
+                .await?;
+        }
+    }
+
+    let (pipeline, steps) = pipeline_queries::create_pipeline(&db, user.id, pipeline).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ResponsePipelineData {
+            data: to_response_pipeline(pipeline, steps),
+        }),
+    ))
+}
@@ -0,0 +1,63 @@
+use crate::utilities::app_error::AppError;
+use axum::{
+    async_trait,
+    body::HttpBody,
+    extract::FromRequest,
+    http::{Request, StatusCode},
+    BoxError, Json, RequestExt,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::RequestPipelineStep;
+
+#[derive(Debug, Validate, Deserialize, Serialize)]
+pub struct ValidateCreatePipeline {
+    #[validate(required(message = "missing pipeline name"))]
+    pub name: Option<String>,
+
+    pub description: Option<String>,
+
+    //Run in the given order, each step may only read from the input or earlier steps
+    #[validate(required(message = "missing steps"))]
+    pub steps: Option<Vec<RequestPipelineStep>>,
+}
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for ValidateCreatePipeline
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(
+        req: Request<B>,
+        _state: &S,
+    ) -> Result<ValidateCreatePipeline, Self::Rejection> {
+        let Json(pipeline) = req
+            .extract::<Json<ValidateCreatePipeline>, _>()
+            .await
+            .map_err(|error| {
+                eprintln!("Error extracting new pipeline info: {:?}", error);
+                AppError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Something went wrong, please try again",
+                )
+            })?;
+
+        if let Err(errors) = pipeline.validate() {
+            let field_errors = errors.field_errors();
+            if let Some((_, error)) = field_errors.into_iter().next() {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    error.first().unwrap().clone().message.unwrap().to_string(), // feel safe unwrapping because we know there is at least one error, and we only care about the first for this api
+                ));
+            }
+        }
+
+        Ok(pipeline)
+    }
+}
@@ -0,0 +1,24 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::core_user::Model as UserModel, queries::pipeline_queries,
+    utilities::app_error::AppError,
+};
+
+pub async fn delete_pipeline(
+    Path(pipeline_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<StatusCode, AppError> {
+    let (pipeline, _steps) =
+        pipeline_queries::find_user_pipeline(&db, pipeline_id, user.id).await?;
+    pipeline_queries::delete_pipeline(&db, pipeline, user.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,61 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_pipeline, core_pipeline_step, core_user::Model as UserModel},
+    queries::pipeline_queries,
+    utilities::app_error::AppError,
+};
+
+use super::{ResponsePipeline, ResponsePipelineData, ResponsePipelineStep, ResponsePipelines};
+
+pub async fn get_pipelines(
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponsePipelines>, AppError> {
+    let pipelines = pipeline_queries::get_user_pipelines(&db, user.id)
+        .await?
+        .into_iter()
+        .map(|(pipeline, steps)| to_response_pipeline(pipeline, steps))
+        .collect::<Vec<ResponsePipeline>>();
+
+    Ok(Json(ResponsePipelines { data: pipelines }))
+}
+
+pub async fn get_pipeline(
+    Path(pipeline_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponsePipelineData>, AppError> {
+    let (pipeline, steps) = pipeline_queries::find_user_pipeline(&db, pipeline_id, user.id).await?;
+
+    Ok(Json(ResponsePipelineData {
+        data: to_response_pipeline(pipeline, steps),
+    }))
+}
+
+pub fn to_response_pipeline(
+    pipeline: core_pipeline::Model,
+    steps: Vec<core_pipeline_step::Model>,
+) -> ResponsePipeline {
+    ResponsePipeline {
+        id: pipeline.id,
+        name: pipeline.name,
+        description: pipeline.description,
+        steps: steps
+            .into_iter()
+            .map(|step| ResponsePipelineStep {
+                key: step.step_key,
+                twin_id: step.twin_id,
+                component_alias: step.component_alias,
+                end_point: step.end_point,
+                body: step.body,
+                mappings: serde_json::from_value(step.mappings).unwrap_or_default(),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::helpers::pipeline_helpers::{PipelineMapping, PipelineStepTrace};
+
+pub mod create_pipeline;
+pub mod create_pipeline_extractor;
+pub mod delete_pipeline;
+pub mod get_pipelines;
+pub mod run_pipeline;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RequestPipelineStep {
+    //Unique within the pipeline, later steps refer to this step's output by it
+    pub key: String,
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    //Base request body, mappings are written on top of it
+    pub body: Option<Value>,
+    #[serde(default)]
+    pub mappings: Vec<PipelineMapping>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestPipelineRun {
+    pub input: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponsePipelineStep {
+    pub key: String,
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    pub body: Option<Value>,
+    pub mappings: Vec<PipelineMapping>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponsePipeline {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Vec<ResponsePipelineStep>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponsePipelines {
+    pub data: Vec<ResponsePipeline>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponsePipelineData {
+    pub data: ResponsePipeline,
+}
+
+#[derive(Serialize)]
+pub struct ResponsePipelineRun {
+    #[serde(rename = "pipelineId")]
+    pub pipeline_id: Uuid,
+    //succeeded when every step succeeded, failed otherwise
+    pub status: String,
+    //Output of the last step, when it succeeded
+    pub output: Option<Value>,
+    pub steps: Vec<PipelineStepTrace>,
+}
+
+#[derive(Serialize)]
+pub struct ResponsePipelineRunData {
+    pub data: ResponsePipelineRun,
+}
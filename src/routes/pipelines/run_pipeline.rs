@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    database::core_user::Model as UserModel,
+    helpers::pipeline_helpers::run_pipeline as run_pipeline_steps,
+    queries::pipeline_queries,
+    utilities::{
+        app_error::AppError, redis_connection_wrapper::RedisConnWrapper,
+        upstream_client::UpstreamClient,
+    },
+};
+
+use super::{RequestPipelineRun, ResponsePipelineRun, ResponsePipelineRunData};
+
+pub async fn run_pipeline(
+    Path(pipeline_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    Extension(client): Extension<UpstreamClient>,
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Json(run): Json<RequestPipelineRun>,
+) -> Result<Json<ResponsePipelineRunData>, AppError> {
+    let (pipeline, steps) = pipeline_queries::find_user_pipeline(&db, pipeline_id, user.id).await?;
+
+    let steps = run_pipeline_steps(
+        &db,
+        redis_url,
+        &client,
+        user.id,
+        steps,
+        run.input.unwrap_or(Value::Null),
+    )
+    .await;
+
+    //Failed and skipped steps are reported in the trace, the run itself still answers 200
+    let succeeded = steps.iter().all(|step| step.outcome == "succeeded");
+    let output = steps
+        .last()
+        .filter(|step| step.outcome == "succeeded")
+        .and_then(|step| step.output.clone());
+
+    Ok(Json(ResponsePipelineRunData {
+        data: ResponsePipelineRun {
+            pipeline_id: pipeline.id,
+            status: if succeeded { "succeeded" } else { "failed" }.to_string(),
+            output,
+            steps,
+        },
+    }))
+}
@@ -7,6 +7,7 @@ use axum::{
     BoxError, Json, RequestExt,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use validator::Validate;
 
 #[derive(Debug, Validate, Deserialize, Serialize)]
@@ -29,6 +30,53 @@ pub struct ValidateCreatePolicyAction {
     #[validate(required(message = "missing policy reset frequency"))]
     #[serde(rename = "resetFrequencyId")]
     pub reset_frequency_id: Option<i32>,
+
+    #[serde(rename = "maxConcurrentStreams")]
+    pub max_concurrent_streams: Option<i32>,
+
+    #[serde(rename = "maxStreamDuration")]
+    pub max_stream_duration: Option<i32>,
+
+    #[serde(rename = "isCacheable")]
+    pub is_cacheable: Option<bool>,
+
+    #[serde(rename = "cacheTtl")]
+    #[validate(range(min = 1, message = "cache ttl must be at least one second"))]
+    pub cache_ttl: Option<i32>,
+
+    #[serde(rename = "cacheScope")]
+    pub cache_scope: Option<String>,
+
+    #[serde(rename = "cacheHitsCount")]
+    pub cache_hits_count: Option<bool>,
+
+    #[serde(rename = "requestSchema")]
+    pub request_schema: Option<Value>,
+
+    #[serde(rename = "responseSchema")]
+    pub response_schema: Option<Value>,
+
+    #[serde(rename = "responseValidation")]
+    pub response_validation: Option<String>,
+
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+
+    #[serde(rename = "rateLimitAlgorithm")]
+    pub rate_limit_algorithm: Option<String>,
+
+    #[serde(rename = "rateLimitRequests")]
+    pub rate_limit_requests: Option<i32>,
+
+    #[serde(rename = "rateLimitPeriod")]
+    pub rate_limit_period: Option<String>,
+
+    #[serde(rename = "rateLimitBurst")]
+    pub rate_limit_burst: Option<i32>,
+
+    pub validity: Option<Value>,
+
+    pub conditions: Option<Value>,
 }
 
 #[async_trait]
@@ -0,0 +1,139 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_policy_action, core_policy_violation, core_user},
+    queries::{
+        model_queries, twin_queries,
+        violation_queries::{self, ViolationFilter},
+    },
+    utilities::app_error::AppError,
+};
+
+use super::{ResponsePolicyViolation, ResponsePolicyViolations, ViolationQuery};
+
+const DEFAULT_VIOLATIONS_PAGE_SIZE: u64 = 50;
+const MAX_VIOLATIONS_PAGE_SIZE: u64 = 500;
+
+//Every violation of the model's policies, by any of its subscribers
+pub async fn get_model_violations(
+    Path(model_id): Path<Uuid>,
+    Query(query): Query<ViolationQuery>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponsePolicyViolations>, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let filter = ViolationFilter {
+        model_id: Some(model.id),
+        user_id: query.user_id,
+        twin_id: query.twin_id,
+        ..Default::default()
+    };
+
+    list_violations(&db, filter, query).await
+}
+
+//The caller's own violations on one of their twins
+pub async fn get_twin_violations(
+    Path(twin_id): Path<Uuid>,
+    Query(query): Query<ViolationQuery>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponsePolicyViolations>, AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+
+    let filter = ViolationFilter {
+        user_id: Some(user.id),
+        twin_id: Some(twin.id),
+        ..Default::default()
+    };
+
+    list_violations(&db, filter, query).await
+}
+
+async fn list_violations(
+    db: &DatabaseConnection,
+    mut filter: ViolationFilter,
+    query: ViolationQuery,
+) -> Result<Json<ResponsePolicyViolations>, AppError> {
+    filter.policy_id = query.policy_id;
+    filter.end_point = query
+        .end_point
+        .map(|end_point| end_point.trim_start_matches('/').to_string());
+    filter.from = parse_time(query.from.as_deref(), "from")?;
+    filter.to = parse_time(query.to.as_deref(), "to")?;
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from >= to {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "from must be before to",
+            ));
+        }
+    }
+
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        return Err(AppError::new(StatusCode::BAD_REQUEST, "page starts at 1"));
+    }
+    let page_size = query
+        .page_size
+        .unwrap_or(DEFAULT_VIOLATIONS_PAGE_SIZE)
+        .clamp(1, MAX_VIOLATIONS_PAGE_SIZE);
+
+    let (violations, total) =
+        violation_queries::get_policy_violations(db, filter, page - 1, page_size).await?;
+
+    Ok(Json(ResponsePolicyViolations {
+        data: violations.into_iter().map(to_response_violation).collect(),
+        page,
+        page_size,
+        total,
+    }))
+}
+
+fn to_response_violation(
+    (violation, policy_action): (
+        core_policy_violation::Model,
+        Option<core_policy_action::Model>,
+    ),
+) -> ResponsePolicyViolation {
+    let (component_alias, end_point) = policy_action
+        .map(|policy_action| (policy_action.component_alias, Some(policy_action.end_point)))
+        .unwrap_or_default();
+
+    ResponsePolicyViolation {
+        id: violation.id,
+        user_id: violation.user_id,
+        model_id: violation.model_id,
+        policy_id: violation.policy_id,
+        action_id: violation.action_id,
+        twin_id: violation.twin_id,
+        component_alias,
+        end_point,
+        reason: violation.reason,
+        violated_at: violation.violated_at.to_rfc3339(),
+    }
+}
+
+fn parse_time(value: Option<&str>, name: &str) -> Result<Option<DateTime<Utc>>, AppError> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| {
+                    AppError::new(
+                        StatusCode::BAD_REQUEST,
+                        format!("{} must be an RFC 3339 timestamp", name),
+                    )
+                })
+        })
+        .transpose()
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
+use crate::helpers::policy_odrl_helpers::OdrlUnsupported;
+
 use self::{
     create_policy_action_extractor::ValidateCreatePolicyAction,
     create_policy_extractor::ValidateCreatePolicy,
@@ -10,6 +13,10 @@ pub mod create_policy;
 pub mod create_policy_action_extractor;
 pub mod create_policy_extractor;
 pub mod get_latest_policy;
+pub mod get_policy_violations;
+pub mod policy_blocks;
+pub mod policy_documents;
+pub mod policy_odrl;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RequestPolicyValidated {
@@ -25,6 +32,22 @@ pub struct ResponsePolicyAction {
     pub end_point_verb: String,
     pub action_count: Option<i32>,
     pub reset_frequency: Option<String>,
+    pub max_concurrent_streams: Option<i32>,
+    pub max_stream_duration: Option<i32>,
+    pub is_cacheable: bool,
+    pub cache_ttl: Option<i32>,
+    pub cache_scope: String,
+    pub cache_hits_count: bool,
+    pub request_schema: Option<Value>,
+    pub response_schema: Option<Value>,
+    pub response_validation: String,
+    pub component_alias: Option<String>,
+    pub rate_limit_algorithm: Option<String>,
+    pub rate_limit_requests: Option<i32>,
+    pub rate_limit_period: Option<String>,
+    pub rate_limit_burst: Option<i32>,
+    pub validity: Option<Value>,
+    pub conditions: Option<Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -36,3 +59,115 @@ pub struct ResponsePolicy {
     pub block_after: Option<i32>,
     pub policy_actions: Vec<ResponsePolicyAction>,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct ViolationQuery {
+    //Only honoured on the owner route, users always see their own violations
+    #[serde(rename = "userId")]
+    pub user_id: Option<Uuid>,
+    #[serde(rename = "twinId")]
+    pub twin_id: Option<Uuid>,
+    #[serde(rename = "policyId")]
+    pub policy_id: Option<Uuid>,
+    #[serde(rename = "endPoint")]
+    pub end_point: Option<String>,
+    //RFC 3339 timestamps
+    pub from: Option<String>,
+    pub to: Option<String>,
+    //Starts at 1
+    pub page: Option<u64>,
+    #[serde(rename = "pageSize")]
+    pub page_size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponsePolicyViolation {
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    #[serde(rename = "modelId")]
+    pub model_id: Uuid,
+    #[serde(rename = "policyId")]
+    pub policy_id: Uuid,
+    #[serde(rename = "actionId")]
+    pub action_id: Uuid,
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: Option<String>,
+    pub reason: Option<String>,
+    #[serde(rename = "violatedAt")]
+    pub violated_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponsePolicyViolations {
+    pub data: Vec<ResponsePolicyViolation>,
+    pub page: u64,
+    #[serde(rename = "pageSize")]
+    pub page_size: u64,
+    pub total: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BlockQuery {
+    #[serde(rename = "twinId")]
+    pub twin_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestReinstateTwin {
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseBlockDecision {
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    #[serde(rename = "policyId")]
+    pub policy_id: Uuid,
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    //block or unblock
+    pub decision: String,
+    #[serde(rename = "violationCount")]
+    pub violation_count: i32,
+    pub reason: Option<String>,
+    //None when the twin was blocked automatically
+    #[serde(rename = "decidedBy")]
+    pub decided_by: Option<Uuid>,
+    #[serde(rename = "decidedAt")]
+    pub decided_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseBlockDecisions {
+    pub data: Vec<ResponseBlockDecision>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseBlockDecisionData {
+    pub data: ResponseBlockDecision,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PolicyDocumentQuery {
+    //yaml (default) or json
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OdrlImportQuery {
+    //Store the policy without the terms that can't be enforced instead of refusing it
+    #[serde(rename = "acceptUnsupported")]
+    pub accept_unsupported: Option<bool>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ResponseOdrlImport {
+    pub data: ResponsePolicy,
+    pub unsupported: Vec<OdrlUnsupported>,
+}
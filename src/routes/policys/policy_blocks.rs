@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_policy_block, core_user},
+    helpers::policy_block_helpers::reinstate_twin,
+    queries::{block_queries, model_queries},
+    utilities::{app_error::AppError, redis_connection_wrapper::RedisConnWrapper},
+};
+
+use super::{
+    BlockQuery, RequestReinstateTwin, ResponseBlockDecision, ResponseBlockDecisionData,
+    ResponseBlockDecisions,
+};
+
+const BLOCK_DECISIONS_LIMIT: u64 = 100;
+
+pub async fn get_block_decisions(
+    Path(model_id): Path<Uuid>,
+    Query(query): Query<BlockQuery>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseBlockDecisions>, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let decisions = block_queries::get_model_block_decisions(
+        &db,
+        model.id,
+        query.twin_id,
+        BLOCK_DECISIONS_LIMIT,
+    )
+    .await?;
+
+    Ok(Json(ResponseBlockDecisions {
+        data: decisions.into_iter().map(to_response_decision).collect(),
+    }))
+}
+
+pub async fn reinstate_user_twin(
+    Path((model_id, twin_id)): Path<(Uuid, Uuid)>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Json(request): Json<RequestReinstateTwin>,
+) -> Result<Json<ResponseBlockDecisionData>, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let decision =
+        reinstate_twin(&db, redis_url, user.id, model.id, twin_id, request.reason).await?;
+
+    Ok(Json(ResponseBlockDecisionData {
+        data: to_response_decision(decision),
+    }))
+}
+
+fn to_response_decision(decision: core_policy_block::Model) -> ResponseBlockDecision {
+    ResponseBlockDecision {
+        id: decision.id,
+        user_id: decision.user_id,
+        policy_id: decision.policy_id,
+        twin_id: decision.twin_id,
+        decision: decision.decision,
+        violation_count: decision.violation_count,
+        reason: decision.reason,
+        decided_by: decision.decided_by,
+        decided_at: decision.decided_at.to_rfc3339(),
+    }
+}
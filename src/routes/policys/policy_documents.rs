@@ -0,0 +1,76 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::core_user,
+    helpers::policy_document_helpers::PolicyDocument,
+    queries::{model_queries, policy_queries},
+    utilities::{app_error::AppError, redis_connection_wrapper::RedisConnWrapper},
+};
+
+use super::{PolicyDocumentQuery, ResponsePolicy};
+
+//Takes a YAML or JSON policy document and stores it as the model's next policy version
+pub async fn import_policy_document(
+    Path(model_id): Path<Uuid>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    body: String,
+) -> Result<(StatusCode, Json<ResponsePolicy>), AppError> {
+    let document = PolicyDocument::parse(&body)?;
+
+    let policy = policy_queries::create_model_policy(
+        &db,
+        model_id,
+        &user,
+        document.into_policy_request(),
+        redis_url,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ResponsePolicy {
+            id: policy.id,
+            name: policy.name,
+            description: policy.description,
+            policy_version: Some(policy.policy_version),
+            block_after: Some(policy.block_after),
+            policy_actions: vec![],
+        }),
+    ))
+}
+
+pub async fn export_policy_document(
+    Path((model_id, policy_version)): Path<(Uuid, i32)>,
+    Query(query): Query<PolicyDocumentQuery>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<impl IntoResponse, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let (policy, policy_actions) =
+        policy_queries::find_policy_by_version(&db, model.id, policy_version).await?;
+    let document = PolicyDocument::from_policy(&policy, &policy_actions);
+
+    let (content_type, body) = match query.format.as_deref().unwrap_or("yaml") {
+        "yaml" => ("application/yaml", document.to_yaml()?),
+        "json" => ("application/json", document.to_json()?),
+        _ => {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                "format must be either yaml or json",
+            ))
+        }
+    };
+
+    Ok(([(header::CONTENT_TYPE, content_type)], body))
+}
@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    database::core_user,
+    helpers::{
+        policy_document_helpers::PolicyDocument,
+        policy_odrl_helpers::{self, OdrlImport},
+    },
+    queries::{model_queries, policy_queries},
+    utilities::{app_error::AppError, redis_connection_wrapper::RedisConnWrapper},
+};
+
+use super::{OdrlImportQuery, ResponseOdrlImport, ResponsePolicy};
+
+//Stores an ODRL policy as the model's next policy version. Terms that can't be enforced
+//refuse the import unless the owner accepts storing the policy without them
+pub async fn import_odrl_policy(
+    Path(model_id): Path<Uuid>,
+    Query(query): Query<OdrlImportQuery>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Json(policy): Json<Value>,
+) -> Result<(StatusCode, Json<ResponseOdrlImport>), AppError> {
+    let OdrlImport {
+        document,
+        unsupported,
+    } = policy_odrl_helpers::import_odrl_policy(&policy, model_id)?;
+
+    if !unsupported.is_empty() && !query.accept_unsupported.unwrap_or(false) {
+        return Err(AppError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "ODRL policy has terms the middleware cannot enforce",
+        )
+        .with_details(json!(unsupported)));
+    }
+
+    let policy = policy_queries::create_model_policy(
+        &db,
+        model_id,
+        &user,
+        document.into_policy_request(),
+        redis_url,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ResponseOdrlImport {
+            data: ResponsePolicy {
+                id: policy.id,
+                name: policy.name,
+                description: policy.description,
+                policy_version: Some(policy.policy_version),
+                block_after: Some(policy.block_after),
+                policy_actions: vec![],
+            },
+            unsupported,
+        }),
+    ))
+}
+
+pub async fn export_odrl_policy(
+    Path((model_id, policy_version)): Path<(Uuid, i32)>,
+    Extension(user): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+) -> Result<impl IntoResponse, AppError> {
+    let (model, _model_components) =
+        model_queries::find_model_by_id(&db, model_id, user.id).await?;
+
+    let (policy, policy_actions) =
+        policy_queries::find_policy_by_version(&db, model.id, policy_version).await?;
+    let document = PolicyDocument::from_policy(&policy, &policy_actions);
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/ld+json")],
+        Json(policy_odrl_helpers::export_odrl_policy(&document, model.id)),
+    ))
+}
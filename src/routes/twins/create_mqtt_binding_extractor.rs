@@ -0,0 +1,69 @@
+use crate::utilities::app_error::AppError;
+use axum::{
+    async_trait,
+    body::HttpBody,
+    extract::FromRequest,
+    http::{Request, StatusCode},
+    BoxError, Json, RequestExt,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Validate, Deserialize, Serialize)]
+pub struct ValidateCreateMqttBinding {
+    #[validate(required(message = "missing subscribeTopic"))]
+    #[serde(rename = "subscribeTopic")]
+    pub subscribe_topic: Option<String>,
+
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+
+    #[validate(required(message = "missing endPoint"))]
+    #[serde(rename = "endPoint")]
+    pub end_point: Option<String>,
+
+    #[serde(rename = "publishTopic")]
+    pub publish_topic: Option<String>,
+
+    #[validate(range(min = 0, max = 2, message = "qos must be 0, 1 or 2"))]
+    pub qos: Option<i32>,
+}
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for ValidateCreateMqttBinding
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(
+        req: Request<B>,
+        _state: &S,
+    ) -> Result<ValidateCreateMqttBinding, Self::Rejection> {
+        let Json(binding) = req
+            .extract::<Json<ValidateCreateMqttBinding>, _>()
+            .await
+            .map_err(|error| {
+                eprintln!("Error extracting new mqtt binding info: {:?}", error);
+                AppError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Something went wrong, please try again",
+                )
+            })?;
+
+        if let Err(errors) = binding.validate() {
+            let field_errors = errors.field_errors();
+            if let Some((_, error)) = field_errors.into_iter().next() {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    error.first().unwrap().clone().message.unwrap().to_string(), // feel safe unwrapping because we know there is at least one error, and we only care about the first for this api
+                ));
+            }
+        }
+
+        Ok(binding)
+    }
+}
@@ -0,0 +1,72 @@
+use crate::utilities::app_error::AppError;
+use axum::{
+    async_trait,
+    body::HttpBody,
+    extract::FromRequest,
+    http::{Request, StatusCode},
+    BoxError, Json, RequestExt,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use validator::Validate;
+
+#[derive(Debug, Validate, Deserialize, Serialize)]
+pub struct ValidateCreateSchedule {
+    pub name: Option<String>,
+
+    //Standard five field cron, or six fields with seconds first
+    #[validate(required(message = "missing cron"))]
+    pub cron: Option<String>,
+
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+
+    #[validate(required(message = "missing endPoint"))]
+    #[serde(rename = "endPoint")]
+    pub end_point: Option<String>,
+
+    pub body: Option<Value>,
+
+    //RFC 3339, the schedule stops firing after this
+    #[serde(rename = "endsAt")]
+    pub ends_at: Option<String>,
+}
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for ValidateCreateSchedule
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(
+        req: Request<B>,
+        _state: &S,
+    ) -> Result<ValidateCreateSchedule, Self::Rejection> {
+        let Json(schedule) = req
+            .extract::<Json<ValidateCreateSchedule>, _>()
+            .await
+            .map_err(|error| {
+                eprintln!("Error extracting new schedule info: {:?}", error);
+                AppError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Something went wrong, please try again",
+                )
+            })?;
+
+        if let Err(errors) = schedule.validate() {
+            let field_errors = errors.field_errors();
+            if let Some((_, error)) = field_errors.into_iter().next() {
+                return Err(AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    error.first().unwrap().clone().message.unwrap().to_string(), // feel safe unwrapping because we know there is at least one error, and we only care about the first for this api
+                ));
+            }
+        }
+
+        Ok(schedule)
+    }
+}
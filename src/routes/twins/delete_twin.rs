@@ -8,10 +8,11 @@ use uuid::Uuid;
 
 use crate::{
     database::core_user,
+    helpers::policy_block_helpers::ensure_twin_not_deactivated,
     queries::twin_queries::{self},
     utilities::{
         app_error::AppError, docker_helper::remove_docker_model,
-        redis_connection_wrapper::RedisConnWrapper,
+        redis_connection_wrapper::RedisConnWrapper, twin_events::publish_twin_status,
     },
 };
 
@@ -22,6 +23,8 @@ pub async fn soft_delete_twin(
     State(redis_url): State<RedisConnWrapper>,
 ) -> Result<Response<String>, AppError> {
     let (twin, twin_components) = twin_queries::find_twin_by_id(&db, twin_id, user.id).await?;
+    //Deleting would let the user subscribe again with a fresh twin and walk around the block
+    ensure_twin_not_deactivated(&twin)?;
 
     //Call docker_helper function to remove docker model
     let _remove_msg = remove_docker_model(&twin, twin_components.clone(), redis_url.clone())
@@ -36,6 +39,7 @@ pub async fn soft_delete_twin(
 
     //Delete twin from database
     let response = twin_queries::delete_twin_by_models(&db, twin, twin_components, user.id).await?;
+    publish_twin_status(redis_url, twin_id, "Deleted", None);
 
     Ok(response)
 }
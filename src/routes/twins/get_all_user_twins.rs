@@ -34,6 +34,7 @@ pub async fn get_all_user_twins(
                 name: twin.name,
                 status: status_name,
                 twin_port: twin.twin_port,
+                components: None,
             }
         })
         .collect::<Vec<ResponseTwinModel>>();
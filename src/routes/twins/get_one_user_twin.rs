@@ -9,7 +9,7 @@ use crate::{
     database::core_user::Model as UserModel, queries::twin_queries, utilities::app_error::AppError,
 };
 
-use super::{ResponseTwinDataModel, ResponseTwinModel};
+use super::{ResponseTwinComponent, ResponseTwinDataModel, ResponseTwinModel};
 
 pub async fn get_one_user_twin(
     Path(twin_id): Path<Uuid>,
@@ -22,11 +22,24 @@ pub async fn get_one_user_twin(
         .map(|twin_status| twin_status.name)
         .unwrap_or_else(|| "Stopped".to_string());
 
+    //Every exposed component can be called through /components/:alias/action/*path
+    let components = twin_queries::get_exposed_twin_components(&db, twin.id)
+        .await?
+        .into_iter()
+        .map(|component| ResponseTwinComponent {
+            name: component.name,
+            component_alias: component.component_alias,
+            container_port: component.container_port,
+            host_port: component.host_port,
+        })
+        .collect::<Vec<ResponseTwinComponent>>();
+
     let response_twin = ResponseTwinModel {
         id: twin.id,
         name: twin.name,
         status: status_name,
         twin_port: twin.twin_port,
+        components: Some(components),
     };
 
     Ok(Json(ResponseTwinDataModel {
@@ -0,0 +1,58 @@
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension,
+};
+use futures_util::{stream, Stream, StreamExt};
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    database::core_user::Model as UserModel,
+    queries::twin_queries,
+    utilities::{
+        app_error::AppError,
+        redis_connection_wrapper::RedisConnWrapper,
+        twin_events::{subscribe_twin_events, TwinEvent, TWIN_EVENT_STATUS},
+    },
+};
+
+//Stream the twin's lifecycle, invocation, policy and health events, starting with its current status
+pub async fn get_twin_events(
+    Path(twin_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+
+    //Subscribe before taking the snapshot so no transition slips in between
+    let events = subscribe_twin_events(redis_url, twin.id).await?;
+    let (twin, twin_status) = twin_queries::get_one_user_twin(&db, twin.id, user.id).await?;
+
+    let current = TwinEvent {
+        twin_id: twin.id,
+        kind: TWIN_EVENT_STATUS.to_string(),
+        data: json!({
+            "status": twin_status
+                .map(|twin_status| twin_status.name)
+                .unwrap_or_else(|| "Stopped".to_string()),
+            "error": null,
+        }),
+        at: chrono::Utc::now(),
+    };
+
+    let stream = stream::once(async move { current })
+        .chain(events)
+        .map(|event| {
+            Ok(Event::default()
+                .event(event.kind.clone())
+                .json_data(&event)
+                .unwrap_or_default())
+        });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15))))
+}
@@ -1,12 +1,24 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
+use crate::helpers::schedule_helpers::ScheduleRunRecord;
+
+pub mod create_mqtt_binding_extractor;
+pub mod create_schedule_extractor;
 pub mod create_twin_extractor;
 pub mod delete_twin;
+pub mod get_twin_events;
 pub mod get_all_user_twins;
 pub mod get_one_user_twin;
 pub mod subscribe_to_model;
+pub mod twin_batch;
+pub mod twin_mqtt_bindings;
 pub mod twin_operations;
+pub mod twin_schedules;
+pub mod twin_shadow;
+pub mod twin_streaming;
+pub mod twin_telemetry;
 pub mod twin_usage;
 
 #[derive(Serialize, Deserialize)]
@@ -15,6 +27,135 @@ pub struct ResponseTwinModel {
     pub name: String,
     pub status: String,
     pub twin_port: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<ResponseTwinComponent>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseTwinComponent {
+    pub name: String,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "containerPort")]
+    pub container_port: Option<i32>,
+    #[serde(rename = "hostPort")]
+    pub host_port: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseMqttBinding {
+    pub id: Uuid,
+    #[serde(rename = "subscribeTopic")]
+    pub subscribe_topic: String,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    #[serde(rename = "publishTopic")]
+    pub publish_topic: Option<String>,
+    pub qos: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseMqttBindings {
+    pub data: Vec<ResponseMqttBinding>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseMqttBindingData {
+    pub data: ResponseMqttBinding,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TelemetryQuery {
+    pub field: Option<String>,
+    //RFC 3339 timestamps, the last 24 hours by default
+    pub from: Option<String>,
+    pub to: Option<String>,
+    //Bucket width in seconds, raw points are returned without it
+    pub bucket: Option<i64>,
+    pub agg: Option<String>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseTelemetryPoint {
+    pub field: String,
+    pub time: String,
+    pub value: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseTelemetry {
+    pub data: Vec<ResponseTelemetryPoint>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestShadowUpdate {
+    //JSON merge patch applied to the section, null removes a key
+    pub state: Value,
+    //Only apply the patch when the shadow is still at this version
+    pub version: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseShadow {
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    pub desired: Value,
+    pub reported: Value,
+    pub delta: Value,
+    pub version: i32,
+    #[serde(rename = "desiredUpdatedAt")]
+    pub desired_updated_at: Option<String>,
+    #[serde(rename = "reportedUpdatedAt")]
+    pub reported_updated_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseShadowData {
+    pub data: ResponseShadow,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseSchedule {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub cron: String,
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    #[serde(rename = "endPoint")]
+    pub end_point: String,
+    pub body: Option<Value>,
+    #[serde(rename = "endsAt")]
+    pub ends_at: Option<String>,
+    #[serde(rename = "nextRunAt")]
+    pub next_run_at: Option<String>,
+    #[serde(rename = "lastRunAt")]
+    pub last_run_at: Option<String>,
+    #[serde(rename = "isActive")]
+    pub is_active: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseSchedules {
+    pub data: Vec<ResponseSchedule>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseScheduleData {
+    pub data: ResponseSchedule,
+}
+
+#[derive(Serialize)]
+pub struct ResponseScheduleHistory {
+    pub results: Vec<ScheduleRunRecord>,
+    pub failures: Vec<ScheduleRunRecord>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseScheduleHistoryData {
+    pub data: ResponseScheduleHistory,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -26,3 +167,50 @@ pub struct ResponseTwinDataModels {
 pub struct ResponseTwinDataModel {
     pub data: ResponseTwinModel,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct RequestBatchItem {
+    #[serde(rename = "componentAlias")]
+    pub component_alias: Option<String>,
+    pub endpoint: String,
+    pub body: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestBatch {
+    pub items: Vec<RequestBatchItem>,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BatchQuery {
+    pub format: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseBatchItem {
+    pub index: usize,
+    pub endpoint: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    //Set on items that were not run because an earlier one was denied by the policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseBatch {
+    pub data: Vec<ResponseBatchItem>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ActionQuery {
+    #[serde(rename = "async")]
+    pub run_async: Option<bool>,
+    pub webhook: Option<String>,
+}
@@ -0,0 +1,180 @@
+use std::convert::Infallible;
+
+use axum::{
+    body::StreamBody,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use futures_util::{stream, StreamExt};
+use hyper::body::Bytes;
+use sea_orm::DatabaseConnection;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_twin, core_user},
+    helpers::{
+        batch_helpers::BatchGate,
+        cache_mgmt_helpers::CACHE_STATUS_HEADER,
+        twin_invocation_helpers::{get_running_user_twin, invoke_twin_endpoint, TwinRequest},
+    },
+    utilities::{
+        app_error::AppError, redis_connection_wrapper::RedisConnWrapper,
+        upstream_client::UpstreamClient,
+    },
+};
+
+use super::{BatchQuery, RequestBatch, RequestBatchItem, ResponseBatch, ResponseBatchItem};
+
+const MAX_BATCH_ITEMS: usize = 10000;
+const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+const MAX_BATCH_CONCURRENCY: usize = 32;
+
+pub async fn batch_request_handler(
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Extension(client): Extension<UpstreamClient>,
+    Extension(user): Extension<core_user::Model>,
+    Path(twin_id): Path<Uuid>,
+    Query(query): Query<BatchQuery>,
+    Json(batch): Json<RequestBatch>,
+) -> Result<Response, AppError> {
+    let twin = get_running_user_twin(&db, twin_id, user.id).await?;
+
+    if batch.items.is_empty() {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Batch must contain at least one item",
+        ));
+    }
+
+    if batch.items.len() > MAX_BATCH_ITEMS {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Batch cannot contain more than {} items", MAX_BATCH_ITEMS),
+        ));
+    }
+
+    let concurrency = batch
+        .concurrency
+        .unwrap_or(DEFAULT_BATCH_CONCURRENCY)
+        .clamp(1, MAX_BATCH_CONCURRENCY);
+
+    //Every item goes through the same policy accounting as a single call. Once the quota or validity
+    //window is exhausted, the rest are skipped instead of piling up violations against the user's twin
+    let gate = BatchGate::default();
+    let results = stream::iter(batch.items.into_iter().enumerate()).map(move |(index, item)| {
+        let db = db.clone();
+        let redis_url = redis_url.clone();
+        let client = client.clone();
+        let twin = twin.clone();
+        let gate = gate.clone();
+        async move {
+            if let Some(status) = gate.denied_status() {
+                return skipped_batch_item(index, item, status);
+            }
+
+            let result = run_batch_item(db, redis_url, client, user.id, twin, index, item).await;
+            gate.record(result.status, result.error.is_some());
+            result
+        }
+    });
+
+    if query.format.as_deref() == Some("jsonl") {
+        //Stream results as JSON lines in completion order, each line carries its item index
+        let lines = results.buffer_unordered(concurrency).map(|item| {
+            let mut line = serde_json::to_vec(&item).unwrap_or_default();
+            line.push(b'\n');
+            Ok::<_, Infallible>(Bytes::from(line))
+        });
+
+        return Ok((
+            [(header::CONTENT_TYPE, "application/x-ndjson")],
+            StreamBody::new(lines),
+        )
+            .into_response());
+    }
+
+    let data = results.buffered(concurrency).collect::<Vec<_>>().await;
+
+    Ok(Json(ResponseBatch { data }).into_response())
+}
+
+async fn run_batch_item(
+    db: DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: UpstreamClient,
+    user_id: Uuid,
+    twin: core_twin::Model,
+    index: usize,
+    item: RequestBatchItem,
+) -> ResponseBatchItem {
+    let body = item
+        .body
+        .map(|body| Bytes::from(serde_json::to_vec(&body).unwrap_or_default()))
+        .unwrap_or_default();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+
+    let result = invoke_twin_endpoint(
+        &db,
+        redis_url,
+        &client,
+        user_id,
+        &twin,
+        TwinRequest {
+            component_alias: item.component_alias,
+            endpoint_id: item.endpoint.clone(),
+            headers,
+            body,
+        },
+    )
+    .await;
+
+    match result {
+        Ok(response) => ResponseBatchItem {
+            index,
+            endpoint: item.endpoint,
+            status: response.status.as_u16(),
+            cache: response
+                .headers
+                .get(CACHE_STATUS_HEADER)
+                .and_then(|cache_status| cache_status.to_str().ok())
+                .map(|cache_status| cache_status.to_string()),
+            body: Some(
+                serde_json::from_slice::<Value>(&response.body).unwrap_or_else(|_| {
+                    Value::String(String::from_utf8_lossy(&response.body).to_string())
+                }),
+            ),
+            error: None,
+            skipped: None,
+        },
+        Err(error) => ResponseBatchItem {
+            index,
+            endpoint: item.endpoint,
+            status: error.code().as_u16(),
+            cache: None,
+            body: None,
+            error: Some(error.message().to_string()),
+            skipped: None,
+        },
+    }
+}
+
+fn skipped_batch_item(index: usize, item: RequestBatchItem, status: u16) -> ResponseBatchItem {
+    ResponseBatchItem {
+        index,
+        endpoint: item.endpoint,
+        status,
+        cache: None,
+        body: None,
+        error: Some("Skipped, an earlier item was denied by the policy".to_string()),
+        skipped: Some(true),
+    }
+}
@@ -0,0 +1,107 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_twin_mqtt_binding, core_user::Model as UserModel},
+    helpers::mqtt_bridge_helpers::{validate_binding_topics, BindingTopics, MqttBridge},
+    queries::{mqtt_binding_queries, twin_queries},
+    utilities::app_error::AppError,
+};
+
+use super::{
+    create_mqtt_binding_extractor::ValidateCreateMqttBinding, ResponseMqttBinding,
+    ResponseMqttBindingData, ResponseMqttBindings,
+};
+
+pub async fn create_mqtt_binding(
+    Path(twin_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    Extension(bridge): Extension<MqttBridge>,
+    State(db): State<DatabaseConnection>,
+    binding: ValidateCreateMqttBinding,
+) -> Result<(StatusCode, Json<ResponseMqttBindingData>), AppError> {
+    if !bridge.is_enabled() {
+        return Err(AppError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "MQTT bridge is not configured",
+        ));
+    }
+
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+
+    let existing_bindings = mqtt_binding_queries::get_user_mqtt_bindings(&db, user.id)
+        .await?
+        .into_iter()
+        .map(|binding| BindingTopics {
+            subscribe_topic: binding.subscribe_topic,
+            publish_topic: binding.publish_topic,
+        })
+        .collect::<Vec<_>>();
+    validate_binding_topics(
+        user.id,
+        &BindingTopics {
+            subscribe_topic: binding.subscribe_topic.clone().unwrap_or_default(),
+            publish_topic: binding.publish_topic.clone(),
+        },
+        &existing_bindings,
+    )?;
+
+    //Fail now rather than on every message when the alias does not exist
+    if let Some(component_alias) = &binding.component_alias {
+        twin_queries::find_exposed_twin_component_by_alias(&db, twin.id, component_alias).await?;
+    }
+
+    let binding = mqtt_binding_queries::create_mqtt_binding(&db, twin.id, user.id, binding).await?;
+    bridge.reload().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ResponseMqttBindingData {
+            data: to_response_binding(binding),
+        }),
+    ))
+}
+
+pub async fn get_mqtt_bindings(
+    Path(twin_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseMqttBindings>, AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+
+    let bindings = mqtt_binding_queries::get_twin_mqtt_bindings(&db, twin.id, user.id)
+        .await?
+        .into_iter()
+        .map(to_response_binding)
+        .collect::<Vec<ResponseMqttBinding>>();
+
+    Ok(Json(ResponseMqttBindings { data: bindings }))
+}
+
+pub async fn delete_mqtt_binding(
+    Path((twin_id, binding_id)): Path<(Uuid, Uuid)>,
+    Extension(user): Extension<UserModel>,
+    Extension(bridge): Extension<MqttBridge>,
+    State(db): State<DatabaseConnection>,
+) -> Result<StatusCode, AppError> {
+    mqtt_binding_queries::delete_mqtt_binding(&db, twin_id, binding_id, user.id).await?;
+    bridge.reload().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn to_response_binding(binding: core_twin_mqtt_binding::Model) -> ResponseMqttBinding {
+    ResponseMqttBinding {
+        id: binding.id,
+        subscribe_topic: binding.subscribe_topic,
+        component_alias: binding.component_alias,
+        end_point: binding.end_point,
+        publish_topic: binding.publish_topic,
+        qos: binding.qos,
+    }
+}
@@ -6,25 +6,38 @@ use axum::{
 use sea_orm::{DatabaseConnection, IntoActiveModel, Set};
 use uuid::Uuid;
 
-use crate::utilities::{app_error::AppError, redis_connection_wrapper::RedisConnWrapper};
-use crate::{database::core_user, queries::twin_queries, utilities::docker_helper};
+use crate::utilities::{
+    app_error::AppError, redis_connection_wrapper::RedisConnWrapper,
+    twin_events::publish_twin_status,
+};
+use crate::{
+    database::core_user, helpers::policy_block_helpers::ensure_twin_not_deactivated,
+    queries::twin_queries, utilities::docker_helper,
+};
 
 pub async fn start_twins(
     Path(twin_id): Path<Uuid>,
     Extension(user): Extension<core_user::Model>,
     State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
 ) -> Result<Json<String>, AppError> {
     let (twin, twin_component) = twin_queries::find_twin_by_id(&db, twin_id, user.id).await?;
+    ensure_twin_not_deactivated(&twin)?;
 
     //Check if twin.twin_status_id is equal to 1 (Stopped) then start twin
     if twin.twin_status_id == 1 {
         //Call helper function to start twin
-        let response = docker_helper::start_docker_model(twin_component, &user.email).await?;
+        let response = docker_helper::start_docker_model(twin_component, &user.email)
+            .await
+            .inspect_err(|error| {
+                publish_twin_status(redis_url.clone(), twin_id, "Failed", Some(error))
+            })?;
 
         //Update twin status to "Running"
         let mut twin = twin.into_active_model();
         twin.twin_status_id = Set(2);
         twin_queries::save_active_coretwin(&db, twin).await?;
+        publish_twin_status(redis_url, twin_id, "Started", None);
 
         Ok(response)
     } else {
@@ -39,18 +52,24 @@ pub async fn stop_twins(
     Path(twin_id): Path<Uuid>,
     Extension(user): Extension<core_user::Model>,
     State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
 ) -> Result<Json<String>, AppError> {
     let (twin, twin_component) = twin_queries::find_twin_by_id(&db, twin_id, user.id).await?;
 
     //Check if twin.twin_status_id is equal to 2 (Running) then stop twin
     if twin.twin_status_id == 2 {
         //Call helper function to start twin
-        let response = docker_helper::stop_docker_model(twin_component, &user.email).await?;
+        let response = docker_helper::stop_docker_model(twin_component, &user.email)
+            .await
+            .inspect_err(|error| {
+                publish_twin_status(redis_url.clone(), twin_id, "Failed", Some(error))
+            })?;
 
         //Update twin status to "Stopped"
         let mut twin = twin.into_active_model();
         twin.twin_status_id = Set(1);
         twin_queries::save_active_coretwin(&db, twin).await?;
+        publish_twin_status(redis_url, twin_id, "Stopped", None);
 
         Ok(response)
     } else {
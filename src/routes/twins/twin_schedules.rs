@@ -0,0 +1,144 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_twin_schedule, core_user::Model as UserModel},
+    helpers::schedule_helpers::{
+        check_schedule_interval, get_schedule_history, next_run_after, parse_cron,
+    },
+    queries::{schedule_queries, twin_queries},
+    utilities::{app_error::AppError, redis_connection_wrapper::RedisConnWrapper},
+};
+
+use super::{
+    create_schedule_extractor::ValidateCreateSchedule, ResponseSchedule, ResponseScheduleData,
+    ResponseScheduleHistory, ResponseScheduleHistoryData, ResponseSchedules,
+};
+
+const DEFAULT_MAX_SCHEDULES_PER_TWIN: u64 = 10;
+
+pub async fn create_schedule(
+    Path(twin_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+    schedule: ValidateCreateSchedule,
+) -> Result<(StatusCode, Json<ResponseScheduleData>), AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+
+    let cron = parse_cron(schedule.cron.as_deref().unwrap_or_default())?;
+    check_schedule_interval(&cron)?;
+
+    let ends_at = schedule
+        .ends_at
+        .as_deref()
+        .map(|ends_at| {
+            DateTime::parse_from_rfc3339(ends_at)
+                .map(|ends_at| ends_at.with_timezone(&Utc))
+                .map_err(|_| {
+                    AppError::new(
+                        StatusCode::BAD_REQUEST,
+                        "endsAt must be an RFC 3339 timestamp",
+                    )
+                })
+        })
+        .transpose()?;
+
+    let next_run_at = next_run_after(&cron, Utc::now(), ends_at).ok_or_else(|| {
+        AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Schedule would never run before endsAt",
+        )
+    })?;
+
+    if let Some(component_alias) = &schedule.component_alias {
+        twin_queries::find_exposed_twin_component_by_alias(&db, twin.id, component_alias).await?;
+    }
+
+    let max_schedules = std::env::var("MAX_SCHEDULES_PER_TWIN")
+        .ok()
+        .and_then(|max| max.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_SCHEDULES_PER_TWIN);
+    if schedule_queries::count_active_twin_schedules(&db, twin.id).await? >= max_schedules {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!("A twin cannot have more than {} schedules", max_schedules),
+        ));
+    }
+
+    let schedule =
+        schedule_queries::create_schedule(&db, twin.id, user.id, schedule, ends_at, next_run_at)
+            .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ResponseScheduleData {
+            data: to_response_schedule(schedule),
+        }),
+    ))
+}
+
+pub async fn get_schedules(
+    Path(twin_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseSchedules>, AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+
+    let schedules = schedule_queries::get_twin_schedules(&db, twin.id, user.id)
+        .await?
+        .into_iter()
+        .map(to_response_schedule)
+        .collect::<Vec<ResponseSchedule>>();
+
+    Ok(Json(ResponseSchedules { data: schedules }))
+}
+
+pub async fn get_schedule_results(
+    Path((twin_id, schedule_id)): Path<(Uuid, Uuid)>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+) -> Result<Json<ResponseScheduleHistoryData>, AppError> {
+    let schedule = schedule_queries::find_twin_schedule(&db, twin_id, schedule_id, user.id).await?;
+    let (results, failures) = get_schedule_history(redis_url, schedule.id).await?;
+
+    Ok(Json(ResponseScheduleHistoryData {
+        data: ResponseScheduleHistory { results, failures },
+    }))
+}
+
+pub async fn delete_schedule(
+    Path((twin_id, schedule_id)): Path<(Uuid, Uuid)>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<StatusCode, AppError> {
+    let schedule = schedule_queries::find_twin_schedule(&db, twin_id, schedule_id, user.id).await?;
+    schedule_queries::delete_schedule(&db, schedule, user.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn to_response_schedule(schedule: core_twin_schedule::Model) -> ResponseSchedule {
+    ResponseSchedule {
+        id: schedule.id,
+        name: schedule.name,
+        cron: schedule.cron_expression,
+        component_alias: schedule.component_alias,
+        end_point: schedule.end_point,
+        body: schedule.body,
+        ends_at: schedule.ends_at.map(|ends_at| ends_at.to_rfc3339()),
+        next_run_at: schedule
+            .next_run_at
+            .map(|next_run_at| next_run_at.to_rfc3339()),
+        last_run_at: schedule
+            .last_run_at
+            .map(|last_run_at| last_run_at.to_rfc3339()),
+        is_active: schedule.is_active,
+    }
+}
@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::{core_twin_shadow, core_user::Model as UserModel},
+    helpers::shadow_helpers::{
+        compute_delta, push_shadow_delta, verify_shadow_token, SHADOW_TOKEN_HEADER,
+    },
+    queries::{
+        shadow_queries::{self, ShadowSection},
+        twin_queries,
+    },
+    utilities::{app_error::AppError, upstream_client::UpstreamClient},
+};
+
+use super::{RequestShadowUpdate, ResponseShadow, ResponseShadowData};
+
+pub async fn get_twin_shadow(
+    Path(twin_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseShadowData>, AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+    let shadow = shadow_queries::get_twin_shadow(&db, twin.id).await?;
+
+    Ok(Json(ResponseShadowData {
+        data: to_response_shadow(shadow),
+    }))
+}
+
+//Users only ever write the desired section, a running twin is told about the difference right away
+pub async fn update_desired_state(
+    Path(twin_id): Path<Uuid>,
+    Extension(user): Extension<UserModel>,
+    Extension(client): Extension<UpstreamClient>,
+    State(db): State<DatabaseConnection>,
+    Json(update): Json<RequestShadowUpdate>,
+) -> Result<Json<ResponseShadowData>, AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+    check_state(&update)?;
+
+    let shadow = shadow_queries::update_twin_shadow(
+        &db,
+        twin.id,
+        ShadowSection::Desired,
+        &update.state,
+        update.version,
+    )
+    .await?;
+
+    push_shadow_delta(db, client, twin, &shadow);
+
+    Ok(Json(ResponseShadowData {
+        data: to_response_shadow(shadow),
+    }))
+}
+
+//Called by the twin itself, e.g. on startup to catch up with changes made while it was stopped
+pub async fn get_shadow_for_twin(
+    Path(twin_id): Path<Uuid>,
+    headers: HeaderMap,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseShadowData>, AppError> {
+    authenticate_twin(twin_id, &headers)?;
+    let shadow = shadow_queries::get_twin_shadow(&db, twin_id).await?;
+
+    Ok(Json(ResponseShadowData {
+        data: to_response_shadow(shadow),
+    }))
+}
+
+pub async fn report_twin_state(
+    Path(twin_id): Path<Uuid>,
+    headers: HeaderMap,
+    State(db): State<DatabaseConnection>,
+    Json(update): Json<RequestShadowUpdate>,
+) -> Result<Json<ResponseShadowData>, AppError> {
+    authenticate_twin(twin_id, &headers)?;
+    check_state(&update)?;
+
+    let shadow = shadow_queries::update_twin_shadow(
+        &db,
+        twin_id,
+        ShadowSection::Reported,
+        &update.state,
+        update.version,
+    )
+    .await?;
+
+    Ok(Json(ResponseShadowData {
+        data: to_response_shadow(shadow),
+    }))
+}
+
+fn authenticate_twin(twin_id: Uuid, headers: &HeaderMap) -> Result<(), AppError> {
+    let token = headers
+        .get(SHADOW_TOKEN_HEADER)
+        .and_then(|token| token.to_str().ok())
+        .unwrap_or_default();
+
+    if verify_shadow_token(twin_id, token) {
+        Ok(())
+    } else {
+        Err(AppError::new(
+            StatusCode::UNAUTHORIZED,
+            "Invalid twin token",
+        ))
+    }
+}
+
+fn check_state(update: &RequestShadowUpdate) -> Result<(), AppError> {
+    if update.state.is_object() {
+        Ok(())
+    } else {
+        Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "state must be a JSON object",
+        ))
+    }
+}
+
+fn to_response_shadow(shadow: core_twin_shadow::Model) -> ResponseShadow {
+    ResponseShadow {
+        twin_id: shadow.twin_id,
+        delta: compute_delta(&shadow.desired, &shadow.reported),
+        desired: shadow.desired,
+        reported: shadow.reported,
+        version: shadow.version,
+        desired_updated_at: shadow
+            .desired_updated_at
+            .map(|updated_at| updated_at.to_rfc3339()),
+        reported_updated_at: shadow
+            .reported_updated_at
+            .map(|updated_at| updated_at.to_rfc3339()),
+    }
+}
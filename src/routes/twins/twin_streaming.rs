@@ -0,0 +1,404 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::{IntoResponse, Response},
+    Extension,
+};
+use futures_util::{future, FutureExt, SinkExt, StreamExt};
+use hyper::{body::Bytes, Body, Method, Request as HyperRequest, StatusCode, Uri};
+use sea_orm::DatabaseConnection;
+use tokio::{net::TcpStream, task::JoinHandle};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{self, client::IntoClientRequest, protocol::frame::coding::CloseCode},
+    MaybeTlsStream, WebSocketStream,
+};
+use uuid::Uuid;
+
+use crate::{
+    database::core_user,
+    helpers::{
+        policy_block_helpers::ensure_twin_not_deactivated,
+        policy_mgmt_helpers::{
+            check_policy, check_policy_conditions, policy_action_key, PolicyEndpoint,
+        },
+        twin_invocation_helpers::{report_policy_denial, resolve_twin_authority},
+    },
+    queries::{policy_queries, twin_queries},
+    utilities::{
+        app_error::AppError,
+        redis_connection_wrapper::RedisConnWrapper,
+        redis_helper::{
+            acquire_stream_slot_in_redis, refresh_stream_slot_in_redis,
+            release_stream_slot_in_redis,
+        },
+        request_trace::inject_trace_headers,
+        upstream_client::{ProxyError, UpstreamClient},
+    },
+};
+
+type UpstreamSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+//Handles GET requests on the twin action route, either upgrading to a WebSocket or passing through a streamed (e.g. SSE) response
+pub async fn remote_stream_handler(
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Extension(client): Extension<UpstreamClient>,
+    Extension(user): Extension<core_user::Model>,
+    Path(path_params): Path<(Uuid, String)>,
+    ws: Option<WebSocketUpgrade>,
+    req: HyperRequest<Body>,
+) -> Result<Response, AppError> {
+    let (twin_id, endpoint_id) = path_params;
+
+    let target = StreamTarget {
+        twin_id,
+        component_alias: None,
+        endpoint_id,
+    };
+    handle_stream_request(db, redis_url, client, user, target, ws, req).await
+}
+
+//Same as remote_stream_handler, but addressed to one of the twin's exposed components
+pub async fn component_stream_handler(
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Extension(client): Extension<UpstreamClient>,
+    Extension(user): Extension<core_user::Model>,
+    Path(path_params): Path<(Uuid, String, String)>,
+    ws: Option<WebSocketUpgrade>,
+    req: HyperRequest<Body>,
+) -> Result<Response, AppError> {
+    let (twin_id, component_alias, path) = path_params;
+
+    let target = StreamTarget {
+        twin_id,
+        component_alias: Some(component_alias),
+        endpoint_id: path.trim_start_matches('/').to_string(),
+    };
+    handle_stream_request(db, redis_url, client, user, target, ws, req).await
+}
+
+struct StreamTarget {
+    twin_id: Uuid,
+    component_alias: Option<String>,
+    endpoint_id: String,
+}
+
+async fn handle_stream_request(
+    db: DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: UpstreamClient,
+    user: core_user::Model,
+    target: StreamTarget,
+    ws: Option<WebSocketUpgrade>,
+    mut req: HyperRequest<Body>,
+) -> Result<Response, AppError> {
+    let StreamTarget {
+        twin_id,
+        component_alias,
+        endpoint_id,
+    } = target;
+
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+    ensure_twin_not_deactivated(&twin)?;
+
+    //Check if twin is running
+    if twin.twin_status_id != 2 {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Twin is not running",
+        ));
+    }
+
+    //Streams are checked against the policy once, when the connection is opened
+    let mut max_concurrent_streams = None;
+    let mut max_stream_duration = None;
+    if let Some(policy_id) = twin.policy_id {
+        let policy_endpoint = PolicyEndpoint::new(component_alias.clone(), endpoint_id.clone());
+        let policy_action = policy_queries::get_policy_action_by_policyid_and_endpoint(
+            &db,
+            policy_id,
+            component_alias.as_deref(),
+            endpoint_id.clone(),
+        )
+        .await?;
+
+        //Streams are opened without a body, body conditions see every field as missing
+        check_policy_conditions(
+            &db,
+            redis_url.clone(),
+            twin_id,
+            &policy_endpoint,
+            user.id,
+            &policy_action,
+            &Bytes::new(),
+        )
+        .await
+        .inspect_err(|error| {
+            report_policy_denial(&redis_url, twin_id, user.id, &policy_endpoint, error)
+        })?;
+        check_policy(
+            &db,
+            twin_id,
+            &policy_endpoint,
+            user.id,
+            policy_id,
+            redis_url.clone(),
+        )
+        .await
+        .inspect_err(|error| {
+            report_policy_denial(&redis_url, twin_id, user.id, &policy_endpoint, error)
+        })?;
+        max_concurrent_streams = policy_action.max_concurrent_streams;
+        max_stream_duration = policy_action
+            .max_stream_duration
+            .map(|seconds| Duration::from_secs(seconds.max(0) as u64));
+    }
+
+    let slot = StreamSlot::acquire(
+        redis_url.clone(),
+        user.id,
+        twin_id,
+        &policy_action_key(component_alias.as_deref(), &endpoint_id),
+        max_concurrent_streams,
+    )
+    .await?;
+
+    let authority = resolve_twin_authority(&db, &twin, component_alias.as_deref()).await?;
+
+    match ws {
+        Some(ws) => {
+            let uri = format!("ws://{}/{}", authority, endpoint_id);
+            let mut upstream_request = uri.into_client_request().map_err(|error| {
+                eprintln!("Error building twin websocket request: {:?}", error);
+                AppError::new(StatusCode::BAD_REQUEST, "Invalid twin endpoint")
+            })?;
+            inject_trace_headers(upstream_request.headers_mut());
+
+            let (upstream, _response) = connect_async(upstream_request).await.map_err(|error| {
+                eprintln!("Error connecting to twin websocket: {:?}", error);
+                AppError::new(
+                    StatusCode::BAD_GATEWAY,
+                    "Could not open a websocket to the twin",
+                )
+            })?;
+
+            Ok(ws.on_upgrade(move |socket| async move {
+                //Keep the slot alive until both sides of the websocket are closed
+                let _slot = slot;
+                pipe_websockets(socket, upstream, max_stream_duration).await;
+            }))
+        }
+        None => {
+            let uri = format!("http://{}/{}", authority, endpoint_id);
+            *req.uri_mut() = Uri::try_from(uri).map_err(|error| {
+                eprintln!("Error building twin uri: {:?}", error);
+                AppError::new(StatusCode::BAD_REQUEST, "Invalid twin endpoint")
+            })?;
+            *req.method_mut() = Method::GET;
+
+            let upstream = client
+                .open_stream(twin_id, req)
+                .await
+                .map_err(ProxyError::into_app_error)?;
+
+            //Pass the body through without buffering so SSE events reach the client as they are produced
+            let (parts, body) = upstream.into_parts();
+            let body = limit_stream(body, slot, max_stream_duration);
+
+            Ok(hyper::Response::from_parts(parts, body).into_response())
+        }
+    }
+}
+
+//A slot that has not been refreshed for this long is considered abandoned
+const STREAM_SLOT_STALE_AFTER: Duration = Duration::from_secs(60);
+
+//A concurrent stream counted against the user's policy, kept alive by a heartbeat and released when dropped
+struct StreamSlot {
+    redis_url: RedisConnWrapper,
+    slot: Option<(String, String)>,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl StreamSlot {
+    async fn acquire(
+        redis_url: RedisConnWrapper,
+        user_id: Uuid,
+        twin_id: Uuid,
+        action_key: &str,
+        max_concurrent_streams: Option<i32>,
+    ) -> Result<StreamSlot, AppError> {
+        let max_concurrent_streams = match max_concurrent_streams {
+            Some(max_concurrent_streams) => max_concurrent_streams as i64,
+            None => {
+                return Ok(StreamSlot {
+                    redis_url,
+                    slot: None,
+                    heartbeat: None,
+                })
+            }
+        };
+
+        let mut store_key = "Streams:Users:".to_string();
+        store_key += &user_id.to_string();
+        store_key += ":";
+        store_key += &twin_id.to_string();
+        store_key += ":";
+        store_key += action_key;
+        let member = Uuid::new_v4().to_string();
+        let stale_ms = STREAM_SLOT_STALE_AFTER.as_millis() as u64;
+
+        let acquired = acquire_stream_slot_in_redis(
+            redis_url.clone(),
+            store_key.clone(),
+            member.clone(),
+            max_concurrent_streams,
+            stale_ms,
+        )
+        .await?;
+        if !acquired {
+            return Err(AppError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "Maximum number of concurrent streams reached",
+            ));
+        }
+
+        let heartbeat = tokio::spawn({
+            let redis_url = redis_url.clone();
+            let store_key = store_key.clone();
+            let member = member.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(STREAM_SLOT_STALE_AFTER / 3).await;
+                    let _redis_response = refresh_stream_slot_in_redis(
+                        redis_url.clone(),
+                        store_key.clone(),
+                        member.clone(),
+                        stale_ms,
+                    )
+                    .await;
+                }
+            }
+        });
+
+        Ok(StreamSlot {
+            redis_url,
+            slot: Some((store_key, member)),
+            heartbeat: Some(heartbeat),
+        })
+    }
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.abort();
+        }
+        if let Some((key, member)) = self.slot.take() {
+            let redis_url = self.redis_url.clone();
+            tokio::spawn(async move {
+                let _redis_response = release_stream_slot_in_redis(redis_url, key, member).await;
+            });
+        }
+    }
+}
+
+fn limit_stream(body: Body, slot: StreamSlot, max_duration: Option<Duration>) -> Body {
+    let deadline = match max_duration {
+        Some(max_duration) => tokio::time::sleep(max_duration).boxed(),
+        None => future::pending().boxed(),
+    };
+
+    let stream = body.take_until(deadline).map(move |chunk| {
+        let _slot = &slot;
+        chunk
+    });
+
+    Body::wrap_stream(stream)
+}
+
+async fn pipe_websockets(
+    client_socket: WebSocket,
+    upstream: UpstreamSocket,
+    max_duration: Option<Duration>,
+) {
+    let (mut client_tx, mut client_rx) = client_socket.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+
+    let client_to_upstream = async {
+        while let Some(Ok(message)) = client_rx.next().await {
+            if upstream_tx.send(to_upstream_message(message)).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    let upstream_to_client = async {
+        while let Some(Ok(message)) = upstream_rx.next().await {
+            if let Some(message) = from_upstream_message(message) {
+                if client_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    };
+
+    let pipes = async {
+        tokio::select! {
+            _ = client_to_upstream => {},
+            _ = upstream_to_client => {},
+        }
+    };
+
+    let timed_out = match max_duration {
+        Some(max_duration) => tokio::time::timeout(max_duration, pipes).await.is_err(),
+        None => {
+            pipes.await;
+            false
+        }
+    };
+
+    if timed_out {
+        let _close_response = client_tx
+            .send(Message::Close(Some(CloseFrame {
+                code: u16::from(CloseCode::Policy),
+                reason: "Stream duration limit reached".into(),
+            })))
+            .await;
+    }
+}
+
+fn to_upstream_message(message: Message) -> tungstenite::Message {
+    match message {
+        Message::Text(text) => tungstenite::Message::Text(text),
+        Message::Binary(data) => tungstenite::Message::Binary(data),
+        Message::Ping(data) => tungstenite::Message::Ping(data),
+        Message::Pong(data) => tungstenite::Message::Pong(data),
+        Message::Close(frame) => {
+            tungstenite::Message::Close(frame.map(|frame| tungstenite::protocol::CloseFrame {
+                code: CloseCode::from(frame.code),
+                reason: frame.reason,
+            }))
+        }
+    }
+}
+
+fn from_upstream_message(message: tungstenite::Message) -> Option<Message> {
+    match message {
+        tungstenite::Message::Text(text) => Some(Message::Text(text)),
+        tungstenite::Message::Binary(data) => Some(Message::Binary(data)),
+        tungstenite::Message::Ping(data) => Some(Message::Ping(data)),
+        tungstenite::Message::Pong(data) => Some(Message::Pong(data)),
+        tungstenite::Message::Close(frame) => Some(Message::Close(frame.map(|frame| CloseFrame {
+            code: u16::from(frame.code),
+            reason: frame.reason.into_owned().into(),
+        }))),
+        tungstenite::Message::Frame(_) => None,
+    }
+}
@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::core_user::Model as UserModel,
+    queries::{
+        telemetry_queries::{self, TelemetryRange},
+        twin_queries,
+    },
+    utilities::app_error::AppError,
+};
+
+use super::{ResponseTelemetry, ResponseTelemetryPoint, TelemetryQuery};
+
+const DEFAULT_TELEMETRY_LIMIT: u64 = 1000;
+const MAX_TELEMETRY_LIMIT: u64 = 10000;
+const AGGREGATES: [&str; 5] = ["avg", "min", "max", "sum", "count"];
+
+pub async fn get_twin_telemetry(
+    Path(twin_id): Path<Uuid>,
+    Query(query): Query<TelemetryQuery>,
+    Extension(user): Extension<UserModel>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseTelemetry>, AppError> {
+    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
+
+    let to = parse_time(query.to.as_deref(), "to")?.unwrap_or_else(Utc::now);
+    let from = parse_time(query.from.as_deref(), "from")?.unwrap_or(to - Duration::hours(24));
+    if from >= to {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "from must be before to",
+        ));
+    }
+
+    if query.bucket.is_some_and(|bucket| bucket <= 0) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "bucket must be a positive number of seconds",
+        ));
+    }
+
+    let aggregate = query.agg.unwrap_or_else(|| "avg".to_string()).to_lowercase();
+    if !AGGREGATES.contains(&aggregate.as_str()) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "agg must be one of avg, min, max, sum or count",
+        ));
+    }
+
+    let points = telemetry_queries::query_telemetry(
+        &db,
+        twin.id,
+        TelemetryRange {
+            field: query.field,
+            from,
+            to,
+            bucket_secs: query.bucket,
+            aggregate,
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_TELEMETRY_LIMIT)
+                .min(MAX_TELEMETRY_LIMIT),
+        },
+    )
+    .await?
+    .into_iter()
+    .map(|point| ResponseTelemetryPoint {
+        field: point.field,
+        time: point.time.to_rfc3339(),
+        value: point.value,
+    })
+    .collect::<Vec<ResponseTelemetryPoint>>();
+
+    Ok(Json(ResponseTelemetry { data: points }))
+}
+
+fn parse_time(value: Option<&str>, name: &str) -> Result<Option<DateTime<Utc>>, AppError> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| {
+                    AppError::new(
+                        StatusCode::BAD_REQUEST,
+                        format!("{} must be an RFC 3339 timestamp", name),
+                    )
+                })
+        })
+        .transpose()
+}
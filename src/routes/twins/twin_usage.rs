@@ -1,120 +1,153 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::header,
     response::{IntoResponse, Response},
     Extension, Json,
 };
 use sea_orm::DatabaseConnection;
-use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
     database::core_user,
-    helpers::policy_mgmt_helpers::check_policy,
-    queries::{
-        policy_queries,
-        shared_data_queries::{self, SharedData},
-        twin_queries,
+    helpers::{
+        async_invocation_helpers::{start_async_invocation, AsyncInvocation},
+        twin_invocation_helpers::{get_running_user_twin, invoke_twin_endpoint, TwinRequest},
     },
+    routes::invocations::ResponseInvocation,
     utilities::{
         app_error::AppError,
         redis_connection_wrapper::RedisConnWrapper,
-        redis_helper::{get_token_from_redis, get_token_ttl, store_token_in_redis},
+        upstream_client::UpstreamClient,
+        webhook_client::{parse_webhook_url, WebhookClient},
     },
 };
-use hyper::{Body, Client, Method, Request as HyperRequest, StatusCode, Uri};
+
+use super::ActionQuery;
+use hyper::{Body, Request as HyperRequest, StatusCode};
 
 pub async fn remote_request_handler(
     State(db): State<DatabaseConnection>,
     State(redis_url): State<RedisConnWrapper>,
-    Extension(client): Extension<Client<hyper::client::HttpConnector>>,
+    Extension(client): Extension<UpstreamClient>,
     Extension(user): Extension<core_user::Model>,
     Path(path_params): Path<(Uuid, String)>,
     // Path(endpoint_id): Path<String>,
-    mut req: HyperRequest<Body>,
-) -> Result<Response<Body>, AppError> {
+    Query(query): Query<ActionQuery>,
+    req: HyperRequest<Body>,
+) -> Result<Response, AppError> {
     let (twin_id, endpoint_id) = path_params;
 
-    //Get twin from db by using appropriate twin_queries
-    let (twin, _twin_status) = twin_queries::get_one_user_twin(&db, twin_id, user.id).await?;
-
-    //Check if twin is running
-    if twin.twin_status_id != 2 {
-        return Err(AppError::new(
-            StatusCode::BAD_REQUEST,
-            "Twin is not running",
-        ));
-    }
-
-    //Check if twin.policy_id is not null
-    if !twin.policy_id.is_none() {
-        //Call async fn check_policy to check if user has access to endpoint
-        let _policy_check = check_policy(
-            &db,
-            twin_id.clone(),
-            endpoint_id.clone(),
-            user.id.clone(),
-            twin.policy_id.clone().unwrap(),
-            redis_url.clone(),
-        )
-        .await?;
-    }
-
-    // Construct URI
-    let uri = format!(
-        "http://localhost:{}/{}",
-        twin.twin_port.unwrap(),
-        endpoint_id
-    );
-
-    // Prepare request and return
-    *req.uri_mut() = Uri::try_from(uri).unwrap();
-
-    // dbg!(&twin.enable_data_sharing);
-
-    //Check redis for policy
-    let mut store_key_model = "Policy:Models:".to_string();
-    store_key_model = store_key_model + &twin.policy_id.unwrap().to_string();
-    store_key_model = store_key_model + ":Verb:" + &endpoint_id.clone();
-
-    let http_method = get_token_from_redis(redis_url.clone(), store_key_model.clone()).await?;
-
-    *req.method_mut() = Method::from_bytes(http_method.as_bytes()).unwrap(); //Need to change this to match the request method; source would be core_policy_action.end_point_verb
-                                                                             // dbg!(&req.body());
-    let input_body_bytes = hyper::body::to_bytes(req.body_mut()).await.unwrap();
-    *req.body_mut() = input_body_bytes.clone().into();
+    let target = ActionTarget {
+        twin_id,
+        component_alias: None,
+        endpoint_id,
+    };
+    handle_action_request(db, redis_url, client, user.id, target, query, req).await
+}
 
-    let input_body = String::from_utf8(input_body_bytes.to_vec()).unwrap();
-    // let input_body = serde_json::from_slice(&input_body_bytes).unwrap();
+//Same as remote_request_handler, but addressed to one of the twin's exposed components
+pub async fn component_request_handler(
+    State(db): State<DatabaseConnection>,
+    State(redis_url): State<RedisConnWrapper>,
+    Extension(client): Extension<UpstreamClient>,
+    Extension(user): Extension<core_user::Model>,
+    Path(path_params): Path<(Uuid, String, String)>,
+    Query(query): Query<ActionQuery>,
+    req: HyperRequest<Body>,
+) -> Result<Response, AppError> {
+    let (twin_id, component_alias, path) = path_params;
+
+    let target = ActionTarget {
+        twin_id,
+        component_alias: Some(component_alias),
+        endpoint_id: path.trim_start_matches('/').to_string(),
+    };
+    handle_action_request(db, redis_url, client, user.id, target, query, req).await
+}
 
-    // dbg!(&input_body);
-    // if let Value::Object(obj) = input_body {
-    //     process_object(obj);
-    // } else {
-    //     println!("Invalid JSON object");
-    // }
+struct ActionTarget {
+    twin_id: Uuid,
+    component_alias: Option<String>,
+    endpoint_id: String,
+}
 
-    let return_result = client.request(req).await.unwrap();
-    // dbg!(&return_result.body());
-    let return_body_bytes = hyper::body::to_bytes(return_result.into_body())
+async fn handle_action_request(
+    db: DatabaseConnection,
+    redis_url: RedisConnWrapper,
+    client: UpstreamClient,
+    user_id: Uuid,
+    target: ActionTarget,
+    query: ActionQuery,
+    mut req: HyperRequest<Body>,
+) -> Result<Response, AppError> {
+    let twin = get_running_user_twin(&db, target.twin_id, user_id).await?;
+
+    let webhook_client = req
+        .extensions()
+        .get::<Option<WebhookClient>>()
+        .cloned()
+        .ok_or_else(|| {
+            eprintln!("Webhook client is missing from the request extensions");
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong, please try again",
+            )
+        })?;
+
+    //Reject a bad callback url before anything is queued
+    let webhook_url = match query.webhook.as_deref() {
+        Some(_) if webhook_client.is_none() => {
+            return Err(AppError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Webhooks are not enabled on this server, poll the invocation instead",
+            ))
+        }
+        Some(webhook) => Some(parse_webhook_url(webhook).await?),
+        None => None,
+    };
+
+    let input_body_bytes = hyper::body::to_bytes(req.body_mut())
         .await
-        .unwrap();
-    let return_result = Response::new(hyper::Body::from(return_body_bytes.clone()));
-    let return_body = String::from_utf8(return_body_bytes.to_vec()).unwrap();
-
-    // dbg!(&return_body);
-    if twin.enable_data_sharing {
-        let _store_result = shared_data_queries::store_usage_data(
-            db.clone(),
-            SharedData {
-                model_id: Some(twin.model_id),
-                input_data: Some(input_body),
-                output_response: Some(return_body),
+        .map_err(|error| {
+            eprintln!("Error reading request body: {:?}", error);
+            AppError::new(StatusCode::BAD_REQUEST, "Could not read request body")
+        })?;
+
+    let twin_request = TwinRequest {
+        component_alias: target.component_alias,
+        endpoint_id: target.endpoint_id,
+        headers: req.headers().clone(),
+        body: input_body_bytes,
+    };
+
+    if query.run_async.unwrap_or(false) {
+        let invocation = start_async_invocation(
+            db,
+            redis_url,
+            AsyncInvocation {
+                client,
+                webhook_client,
+                webhook_url,
             },
+            user_id,
+            twin,
+            twin_request,
         )
         .await?;
+
+        let location = format!("/user/invocations/{}", invocation.id);
+        return Ok((
+            StatusCode::ACCEPTED,
+            [(header::LOCATION, location)],
+            Json(ResponseInvocation { data: invocation }),
+        )
+            .into_response());
     }
 
-    Ok(return_result)
+    let upstream_response =
+        invoke_twin_endpoint(&db, redis_url, &client, user_id, &twin, twin_request).await?;
+
+    Ok(upstream_response.into_response().into_response())
 }
 
 // fn process_object(obj: serde_json::Map<String, Value>) {
@@ -3,7 +3,9 @@ pub mod login;
 pub mod logout;
 pub mod signin_user_extractor;
 pub mod signup;
+pub mod user_attributes;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize)]
@@ -27,3 +29,20 @@ pub struct RequestCreateUser {
     pub last_name: String,
     pub password: String,
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct RequestUserAttributes {
+    pub attributes: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseUserAttributes {
+    pub id: Uuid,
+    pub email: String,
+    pub attributes: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResponseUserAttributesData {
+    pub data: ResponseUserAttributes,
+}
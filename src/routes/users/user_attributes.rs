@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::{database::core_user, queries::user_queries, utilities::app_error::AppError};
+
+use super::{RequestUserAttributes, ResponseUserAttributes, ResponseUserAttributesData};
+
+pub async fn get_user_attributes(
+    Path(user_id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ResponseUserAttributesData>, AppError> {
+    let user = user_queries::find_user_by_id(&db, user_id).await?;
+
+    Ok(Json(to_response_attributes(user)))
+}
+
+//Admins set the attributes policy conditions check with source user, users can't vouch for themselves
+pub async fn set_user_attributes(
+    Path(user_id): Path<Uuid>,
+    Extension(admin): Extension<core_user::Model>,
+    State(db): State<DatabaseConnection>,
+    Json(request): Json<RequestUserAttributes>,
+) -> Result<Json<ResponseUserAttributesData>, AppError> {
+    if !request.attributes.is_object() {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "attributes must be a JSON object",
+        ));
+    }
+
+    let user = user_queries::find_user_by_id(&db, user_id).await?;
+    let user =
+        user_queries::update_user_attributes(&db, user, request.attributes, admin.id).await?;
+
+    Ok(Json(to_response_attributes(user)))
+}
+
+fn to_response_attributes(user: core_user::Model) -> ResponseUserAttributesData {
+    ResponseUserAttributesData {
+        data: ResponseUserAttributes {
+            id: user.id,
+            email: user.email,
+            attributes: user.attributes,
+        },
+    }
+}
@@ -1,14 +1,17 @@
 use axum::{
-    http::StatusCode,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug)]
 pub struct AppError {
     code: StatusCode,
     message: String,
+    details: Option<Value>,
+    headers: Vec<(HeaderName, HeaderValue)>,
 }
 
 impl AppError {
@@ -16,16 +19,40 @@ impl AppError {
         Self {
             code,
             message: message.into(),
+            details: None,
+            headers: Vec::new(),
         }
     }
+
+    //Attach machine readable context, e.g. field level validation errors
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    //Sent along with the error, e.g. Retry-After on a 429
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    pub fn code(&self) -> StatusCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         (
             self.code,
+            self.headers.into_iter().collect::<HeaderMap>(),
             Json(ErrorResponse {
                 error: self.message.clone(),
+                details: self.details,
             }),
         )
             .into_response()
@@ -35,4 +62,6 @@ impl IntoResponse for AppError {
 #[derive(Deserialize, Serialize)]
 struct ErrorResponse {
     error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
 }
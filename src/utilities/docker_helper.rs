@@ -11,7 +11,7 @@ use maplit::hashmap;
 // use std::collections::HashMap;
 // use std::convert::Infallible;
 use bollard::models::EndpointSettings;
-use bollard::network::{ConnectNetworkOptions, CreateNetworkOptions};
+use bollard::network::{ConnectNetworkOptions, CreateNetworkOptions, DisconnectNetworkOptions};
 use sea_orm::DatabaseConnection;
 use sea_orm::IntoActiveModel;
 use sea_orm::Set;
@@ -20,9 +20,11 @@ use std::default::Default;
 use crate::database::core_model_component;
 use crate::database::core_twin;
 use crate::database::core_twin_component;
+use crate::helpers::shadow_helpers::shadow_env;
 use crate::queries::twin_queries;
 
 use super::app_error::AppError;
+use super::proxy_network::{proxy_container_name, publish_host_ports, ProxyNetworkMode};
 use super::redis_connection_wrapper::RedisConnWrapper;
 use super::redis_helper::delete_token_from_redis;
 use super::redis_helper::get_token_from_redis;
@@ -71,6 +73,59 @@ async fn create_docker_network(network_name: String) -> Result<(), AppError> {
     Ok(())
 }
 
+//Attach the middleware's own container to a twin network so it can reach the components directly
+async fn connect_proxy_to_network(network_name: &str) -> Result<(), AppError> {
+    // Create a Docker client
+    let docker = Docker::connect_with_local_defaults().map_err(|error| {
+        eprintln!("Error connecting to docker daemon: {:?}", error);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Connection error")
+    })?;
+
+    let proxy_container = proxy_container_name()?;
+    let config = ConnectNetworkOptions {
+        container: proxy_container.as_str(),
+        endpoint_config: EndpointSettings::default(),
+    };
+    docker
+        .connect_network(network_name, config)
+        .await
+        .map_err(|error| {
+            eprintln!("Error connecting proxy to network: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error connecting proxy to network",
+            )
+        })?;
+
+    Ok(())
+}
+
+async fn disconnect_proxy_from_network(network_name: &str) -> Result<(), AppError> {
+    // Create a Docker client
+    let docker = Docker::connect_with_local_defaults().map_err(|error| {
+        eprintln!("Error connecting to docker daemon: {:?}", error);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Connection error")
+    })?;
+
+    let proxy_container = proxy_container_name()?;
+    let config = DisconnectNetworkOptions {
+        container: proxy_container.as_str(),
+        force: true,
+    };
+    docker
+        .disconnect_network(network_name, config)
+        .await
+        .map_err(|error| {
+            eprintln!("Error disconnecting proxy from network: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error disconnecting proxy from network",
+            )
+        })?;
+
+    Ok(())
+}
+
 async fn create_container(
     network_name: String,
     image_name: String,
@@ -78,6 +133,7 @@ async fn create_container(
     component_alias: String,
     container_port: i32,
     host_port: i32,
+    env: Vec<String>,
 ) -> Result<String, AppError> {
     // Create a Docker client
     let docker = Docker::connect_with_local_defaults().map_err(|error| {
@@ -134,11 +190,14 @@ async fn create_container(
 
     let host_config = HostConfig {
         port_bindings: Some(port_bindings),
+        //Lets twins call back into the middleware on Linux hosts too
+        extra_hosts: Some(vec!["host.docker.internal:host-gateway".to_string()]),
         ..Default::default()
     };
 
     let alpine_config = Config {
         image: Some(image_name.as_str()),
+        env: Some(env.iter().map(|variable| variable.as_str()).collect()),
         host_config: Some(host_config),
         tty: Some(true),
         ..Default::default()
@@ -310,6 +369,10 @@ pub async fn create_docker_model(
     network_name = network_name.to_lowercase();
 
     create_docker_network(network_name.clone()).await?;
+    if ProxyNetworkMode::from_env() == ProxyNetworkMode::Network {
+        connect_proxy_to_network(&network_name).await?;
+    }
+    let publish_ports = publish_host_ports();
 
     //Iterate through model_components and create docker containers using create_container()
     for comp in model_components.clone() {
@@ -324,7 +387,8 @@ pub async fn create_docker_model(
         //Check if model is exposed
         //If exposed, generate a random number between 8000 and 9999, and assign it to host_port
         //If not exposed, assign host_port to 0
-        let host_port = if comp.is_exposed {
+        //In network mode the proxy reaches the container directly, so publishing is optional
+        let host_port = if comp.is_exposed && publish_ports {
             //recursively generate a random number between 8000 and 9999, and check if it is already in use
             //if it is in use, generate another random number
             //if it is not in use, assign it to host_port
@@ -354,6 +418,7 @@ pub async fn create_docker_model(
             comp.component_alias.clone().unwrap_or_default(),
             comp.container_port.clone().unwrap_or_default(),
             host_port,
+            shadow_env(model.id),
         )
         .await?;
         //store host_port in redis if host_port is not 0
@@ -375,7 +440,10 @@ pub async fn create_docker_model(
             comp.container_name = Set(Some(container_name));
             twin_queries::save_active_coretwin_component(&db, comp).await?;
 
-            twin_port = host_port;
+            //The first exposed component stays the default entry point, the others are reached by alias
+            if twin_port == 0 {
+                twin_port = host_port;
+            }
         } else {
             //Update component's container_name
             let mut comp = twin_component.into_active_model();
@@ -457,9 +525,11 @@ pub async fn remove_docker_model(
         //Stop container
         remove_container(&comp.container_name.unwrap()).await?;
         //Delete redis key
-        let mut store_key = "Ports:host:".to_string();
-        store_key = store_key + comp.host_port.unwrap_or_default().to_string().as_str();
-        delete_token_from_redis(redis_url.clone(), store_key).await?;
+        if let Some(host_port) = comp.host_port {
+            let mut store_key = "Ports:host:".to_string();
+            store_key += &host_port.to_string();
+            delete_token_from_redis(redis_url.clone(), store_key).await?;
+        }
     }
 
     //obtain network name from model.name by replacing whitespaces with underscores
@@ -469,6 +539,15 @@ pub async fn remove_docker_model(
     // network_name.push_str(formatted_username);
     // network_name = network_name.to_lowercase();
 
+    //The proxy has to leave the network before docker lets it be removed
+    if ProxyNetworkMode::from_env() == ProxyNetworkMode::Network {
+        if let Err(error) =
+            disconnect_proxy_from_network(model.network_name.as_ref().unwrap()).await
+        {
+            eprintln!("Proxy could not leave twin network: {:?}", error);
+        }
+    }
+
     let _msg = remove_docker_network(&model.clone().network_name.unwrap()).await?;
     Ok(Json("Containers removed successfully!".to_string()))
 }
@@ -0,0 +1,111 @@
+use axum::http::StatusCode;
+use serde_json::Value;
+
+use super::app_error::AppError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+//Parse the subset of JSONPath we support: $.a.b, $['a b'] and $.a[0]
+pub fn parse_json_path(path: &str) -> Result<Vec<PathSegment>, AppError> {
+    let invalid = || {
+        AppError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid json path: {}", path),
+        )
+    };
+
+    let mut rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments = vec![];
+
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            let key = &after_dot[..end];
+            if key.is_empty() {
+                return Err(invalid());
+            }
+            segments.push(PathSegment::Key(key.to_string()));
+            rest = &after_dot[end..];
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let end = after_bracket.find(']').ok_or_else(invalid)?;
+            let inner = after_bracket[..end].trim();
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|inner| inner.strip_suffix('\''))
+                .or_else(|| {
+                    inner
+                        .strip_prefix('"')
+                        .and_then(|inner| inner.strip_suffix('"'))
+                });
+            match quoted {
+                Some(key) => segments.push(PathSegment::Key(key.to_string())),
+                None => segments.push(PathSegment::Index(
+                    inner.parse::<usize>().map_err(|_| invalid())?,
+                )),
+            }
+            rest = &after_bracket[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(segments)
+}
+
+pub fn select<'a>(value: &'a Value, segments: &[PathSegment]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(value, |current, segment| match segment {
+            PathSegment::Key(key) => current.get(key.as_str()),
+            PathSegment::Index(index) => current.get(*index),
+        })
+}
+
+//Numbers are taken as is, numeric strings are parsed and booleans count as 1 or 0
+pub fn select_number(value: &Value, path: &str) -> Option<f64> {
+    let segments = parse_json_path(path).ok()?;
+
+    match select(value, &segments)? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok(),
+        Value::Bool(flag) => Some(if *flag { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+    .filter(|number| number.is_finite())
+}
+
+//Write value at the path, creating objects along the way; "$" replaces the whole document
+pub fn set(target: &mut Value, segments: &[PathSegment], value: Value) -> Result<(), AppError> {
+    let Some((segment, rest)) = segments.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+
+    let next = match segment {
+        PathSegment::Key(key) => {
+            if !target.is_object() {
+                *target = Value::Object(Default::default());
+            }
+            target
+                .as_object_mut()
+                .unwrap()
+                .entry(key.clone())
+                .or_insert(Value::Null)
+        }
+        PathSegment::Index(index) => target
+            .as_array_mut()
+            .and_then(|items| items.get_mut(*index))
+            .ok_or_else(|| {
+                AppError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("Array index {} does not exist in the target", index),
+                )
+            })?,
+    };
+
+    set(next, rest, value)
+}
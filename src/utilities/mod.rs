@@ -1,8 +1,14 @@
 pub mod app_error;
 pub mod docker_helper;
 pub mod hash;
+pub mod json_path;
 pub mod jwt;
+pub mod proxy_network;
 pub mod redis_connection_wrapper;
 pub mod redis_helper;
+pub mod request_trace;
 pub mod token_duration_wrapper;
 pub mod token_wrapper;
+pub mod twin_events;
+pub mod upstream_client;
+pub mod webhook_client;
@@ -0,0 +1,50 @@
+use axum::http::StatusCode;
+
+use super::app_error::AppError;
+
+//How the middleware reaches twin containers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProxyNetworkMode {
+    //Through host ports published on the docker host, the middleware calls localhost
+    Host,
+    //The middleware container joins every twin network and calls components directly
+    Network,
+}
+
+impl ProxyNetworkMode {
+    pub fn from_env() -> Self {
+        match std::env::var("PROXY_NETWORK_MODE")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "network" => ProxyNetworkMode::Network,
+            _ => ProxyNetworkMode::Host,
+        }
+    }
+}
+
+//Host ports are required in host mode, in network mode they are only published when asked for
+pub fn publish_host_ports() -> bool {
+    match ProxyNetworkMode::from_env() {
+        ProxyNetworkMode::Host => true,
+        ProxyNetworkMode::Network => std::env::var("PUBLISH_HOST_PORTS")
+            .map(|value| value.trim().eq_ignore_ascii_case("true"))
+            .unwrap_or(false),
+    }
+}
+
+//Name of the container the middleware runs in, needed to attach it to twin networks
+pub fn proxy_container_name() -> Result<String, AppError> {
+    std::env::var("PROXY_CONTAINER_NAME")
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+        .ok_or_else(|| {
+            eprintln!("PROXY_CONTAINER_NAME must be set when PROXY_NETWORK_MODE is network");
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Proxy network is not configured",
+            )
+        })
+}
@@ -39,6 +39,27 @@ pub async fn store_token_in_redis(
     Ok(())
 }
 
+pub async fn store_token_in_redis_for_seconds(
+    redis_url: RedisConnWrapper,
+    key: String,
+    value: String,
+    expires_in_seconds: usize,
+) -> Result<(), AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+
+    con.set_ex::<_, _, ()>(key, value, expires_in_seconds)
+        .await
+        .map_err(|error| {
+            eprintln!("Error setting token in redis: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong, please try again",
+            )
+        })?;
+
+    Ok(())
+}
+
 // pub async fn get_token_from_redis(
 //     redis_url: RedisConnWrapper,
 //     key: String,
@@ -88,6 +109,355 @@ pub async fn delete_token_from_redis(
     Ok(())
 }
 
+//Open streams are members of a sorted set scored by their last heartbeat,
+//so slots held by a process that died without releasing them age out on their own
+const ACQUIRE_STREAM_SLOT_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[2])
+local stale_ms = tonumber(ARGV[3])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - stale_ms)
+local allowed = 0
+if redis.call('ZCARD', KEYS[1]) < limit then
+    redis.call('ZADD', KEYS[1], now, ARGV[1])
+    allowed = 1
+end
+redis.call('PEXPIRE', KEYS[1], stale_ms)
+return allowed
+"#;
+
+const REFRESH_STREAM_SLOT_SCRIPT: &str = r#"
+local stale_ms = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('ZADD', KEYS[1], now, ARGV[1])
+redis.call('PEXPIRE', KEYS[1], stale_ms)
+return 1
+"#;
+
+pub async fn acquire_stream_slot_in_redis(
+    redis_url: RedisConnWrapper,
+    key: String,
+    member: String,
+    limit: i64,
+    stale_ms: u64,
+) -> Result<bool, AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+    let allowed: i64 = redis::Script::new(ACQUIRE_STREAM_SLOT_SCRIPT)
+        .key(key)
+        .arg(member)
+        .arg(limit)
+        .arg(stale_ms)
+        .invoke_async(&mut con)
+        .await
+        .map_err(|error| {
+            eprintln!("Error acquiring stream slot in redis: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong, please try again",
+            )
+        })?;
+
+    Ok(allowed == 1)
+}
+
+pub async fn refresh_stream_slot_in_redis(
+    redis_url: RedisConnWrapper,
+    key: String,
+    member: String,
+    stale_ms: u64,
+) -> Result<(), AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+    let _: i64 = redis::Script::new(REFRESH_STREAM_SLOT_SCRIPT)
+        .key(key)
+        .arg(member)
+        .arg(stale_ms)
+        .invoke_async(&mut con)
+        .await
+        .map_err(|error| {
+            eprintln!("Error refreshing stream slot in redis: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong, please try again",
+            )
+        })?;
+
+    Ok(())
+}
+
+pub async fn release_stream_slot_in_redis(
+    redis_url: RedisConnWrapper,
+    key: String,
+    member: String,
+) -> Result<(), AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+    let _: i64 = con.zrem(key, member).await.map_err(|error| {
+        eprintln!("Error releasing stream slot in redis: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again",
+        )
+    })?;
+
+    Ok(())
+}
+
+//Check and count a call in one round trip so concurrent calls can never go past the limit.
+//The window starts with the first counted call and is never re-armed by later ones
+const CONSUME_QUOTA_SCRIPT: &str = r#"
+local used = tonumber(redis.call('GET', KEYS[1]) or '0')
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local allowed = 0
+if used < limit then
+    used = redis.call('INCR', KEYS[1])
+    allowed = 1
+end
+local ttl = redis.call('TTL', KEYS[1])
+if ttl == -1 and window > 0 then
+    redis.call('EXPIRE', KEYS[1], window)
+    ttl = window
+end
+return {allowed, used, ttl}
+"#;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub allowed: bool,
+    pub used: i64,
+    pub limit: i64,
+    //Seconds until the counter resets, None when it never does
+    pub reset_in: Option<u64>,
+}
+
+//A window of 0 keeps the counter forever
+pub async fn consume_quota_in_redis(
+    redis_url: RedisConnWrapper,
+    key: String,
+    limit: i64,
+    window_secs: u64,
+) -> Result<QuotaUsage, AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+
+    let (allowed, used, ttl): (i64, i64, i64) = redis::Script::new(CONSUME_QUOTA_SCRIPT)
+        .key(key)
+        .arg(limit)
+        .arg(window_secs)
+        .invoke_async(&mut con)
+        .await
+        .map_err(|error| {
+            eprintln!("Error consuming quota in redis: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong, please try again",
+            )
+        })?;
+
+    Ok(QuotaUsage {
+        allowed: allowed == 1,
+        used,
+        limit,
+        reset_in: u64::try_from(ttl).ok(),
+    })
+}
+
+//Refill continuously at one token per refill_ms up to capacity, a call takes one token.
+//Times come from the redis server so every instance shares the same clock
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local refill_ms = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'at')
+local tokens = tonumber(state[1]) or capacity
+local at = tonumber(state[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - at) / refill_ms)
+local allowed = 0
+local retry_ms = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    retry_ms = math.ceil((1 - tokens) * refill_ms)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'at', now)
+local reset_ms = math.ceil((capacity - tokens) * refill_ms)
+redis.call('PEXPIRE', KEYS[1], math.max(reset_ms, 1))
+return {allowed, math.floor(tokens), retry_ms, reset_ms}
+"#;
+
+//Keep the time of every counted call within the window, KEYS[2] only hands out unique members
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[1])
+local window_ms = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window_ms)
+local count = redis.call('ZCARD', KEYS[1])
+local allowed = 0
+if count < limit then
+    local sequence = redis.call('INCR', KEYS[2])
+    redis.call('ZADD', KEYS[1], now, now .. ':' .. sequence)
+    count = count + 1
+    allowed = 1
+end
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+local reset_ms = 0
+if oldest[2] then
+    reset_ms = tonumber(oldest[2]) + window_ms - now
+end
+local retry_ms = 0
+if allowed == 0 then
+    retry_ms = reset_ms
+end
+redis.call('PEXPIRE', KEYS[1], window_ms)
+redis.call('PEXPIRE', KEYS[2], window_ms)
+return {allowed, limit - count, retry_ms, reset_ms}
+"#;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimitUsage {
+    pub allowed: bool,
+    pub limit: i64,
+    pub remaining: i64,
+    //Milliseconds until a refused call would be let through
+    pub retry_after_ms: u64,
+    //Milliseconds until the limiter is back to its full allowance
+    pub reset_ms: u64,
+}
+
+pub async fn consume_token_bucket_in_redis(
+    redis_url: RedisConnWrapper,
+    key: String,
+    capacity: i64,
+    refill_ms: f64,
+) -> Result<RateLimitUsage, AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+
+    let script = redis::Script::new(TOKEN_BUCKET_SCRIPT);
+    let mut invocation = script.key(key);
+    invocation.arg(capacity).arg(refill_ms);
+    let result = invoke_rate_limit_script(&mut con, &invocation).await?;
+
+    Ok(to_rate_limit_usage(result, capacity))
+}
+
+pub async fn consume_sliding_window_in_redis(
+    redis_url: RedisConnWrapper,
+    key: String,
+    limit: i64,
+    window_ms: u64,
+) -> Result<RateLimitUsage, AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+
+    let script = redis::Script::new(SLIDING_WINDOW_SCRIPT);
+    let mut invocation = script.key(&key);
+    invocation
+        .key(format!("{}:Sequence", key))
+        .arg(limit)
+        .arg(window_ms);
+    let result = invoke_rate_limit_script(&mut con, &invocation).await?;
+
+    Ok(to_rate_limit_usage(result, limit))
+}
+
+async fn invoke_rate_limit_script(
+    con: &mut redis::aio::Connection,
+    invocation: &redis::ScriptInvocation<'_>,
+) -> Result<(i64, i64, i64, i64), AppError> {
+    invocation.invoke_async(con).await.map_err(|error| {
+        eprintln!("Error consuming rate limit in redis: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again",
+        )
+    })
+}
+
+fn to_rate_limit_usage(
+    (allowed, remaining, retry_ms, reset_ms): (i64, i64, i64, i64),
+    limit: i64,
+) -> RateLimitUsage {
+    RateLimitUsage {
+        allowed: allowed == 1,
+        limit,
+        remaining: remaining.max(0),
+        retry_after_ms: retry_ms.max(0) as u64,
+        reset_ms: reset_ms.max(0) as u64,
+    }
+}
+
+//Keep only the newest max_len entries, newest first
+pub async fn push_to_redis_list(
+    redis_url: RedisConnWrapper,
+    key: String,
+    value: String,
+    max_len: isize,
+) -> Result<(), AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+
+    redis::pipe()
+        .atomic()
+        .lpush(&key, value)
+        .ignore()
+        .ltrim(&key, 0, max_len - 1)
+        .ignore()
+        .query_async::<_, ()>(&mut con)
+        .await
+        .map_err(|error| {
+            eprintln!("Error pushing to redis list: {:?}", error);
+            AppError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong, please try again",
+            )
+        })?;
+
+    Ok(())
+}
+
+pub async fn get_redis_list(
+    redis_url: RedisConnWrapper,
+    key: String,
+) -> Result<Vec<String>, AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+    let values = con.lrange(key, 0, -1).await.unwrap_or_default();
+
+    Ok(values)
+}
+
+pub async fn publish_to_redis(
+    redis_url: RedisConnWrapper,
+    channel: String,
+    message: String,
+) -> Result<(), AppError> {
+    let mut con = get_redis_connection(redis_url).await?;
+    let _receivers: i64 = con.publish(channel, message).await.map_err(|error| {
+        eprintln!("Error publishing to redis: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again",
+        )
+    })?;
+
+    Ok(())
+}
+
+pub async fn subscribe_to_redis(
+    redis_url: RedisConnWrapper,
+    channel: String,
+) -> Result<redis::aio::PubSub, AppError> {
+    let mut pubsub = get_redis_connection(redis_url).await?.into_pubsub();
+    pubsub.subscribe(channel).await.map_err(|error| {
+        eprintln!("Error subscribing to redis channel: {:?}", error);
+        AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Something went wrong, please try again",
+        )
+    })?;
+
+    Ok(pubsub)
+}
+
 async fn get_redis_connection(
     redis_url: RedisConnWrapper,
 ) -> Result<redis::aio::Connection, AppError> {
@@ -0,0 +1,123 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    sdk::{propagation::TraceContextPropagator, trace, Resource},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+//Install the tracing subscriber, spans are exported over OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set
+pub fn init_tracing() {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer());
+
+    let endpoint = match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) if !endpoint.trim().is_empty() => endpoint,
+        _ => {
+            registry.init();
+            return;
+        }
+    };
+
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "digital_twin_mw".to_string());
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                service_name,
+            )])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio);
+
+    match tracer {
+        Ok(tracer) => registry
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .init(),
+        Err(error) => {
+            registry.init();
+            tracing::error!("Error installing OTLP exporter: {:?}", error);
+        }
+    }
+}
+
+//Flush spans that are still buffered before the process exits
+pub fn shutdown_tracing() {
+    global::shutdown_tracer_provider();
+}
+
+//Run a future with the request id of the incoming call, so twin calls made on its behalf carry it too
+pub async fn with_request_id<F: std::future::Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+}
+
+//Continue the caller's trace when it sent a traceparent header
+pub fn set_parent_from_headers(span: &Span, headers: &HeaderMap) {
+    let parent_context =
+        global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    span.set_parent(parent_context);
+}
+
+//Add x-request-id and the current span's traceparent to a request going to a twin
+pub fn inject_trace_headers(headers: &mut HeaderMap) {
+    if let Some(request_id) =
+        current_request_id().and_then(|request_id| HeaderValue::from_str(&request_id).ok())
+    {
+        headers.insert(REQUEST_ID_HEADER, request_id);
+    }
+
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl<'a> Injector for HeaderInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::{
+    app_error::AppError,
+    redis_connection_wrapper::RedisConnWrapper,
+    redis_helper::{publish_to_redis, subscribe_to_redis},
+};
+
+pub const TWIN_EVENT_STATUS: &str = "status";
+pub const TWIN_EVENT_POLICY_DENIED: &str = "policy_denied";
+pub const TWIN_EVENT_INVOCATION: &str = "invocation";
+pub const TWIN_EVENT_HEALTH: &str = "health";
+//Sent to the model owner about one of their subscribers' twins
+pub const OWNER_EVENT_TWIN_BLOCKED: &str = "twin_blocked";
+pub const OWNER_EVENT_TWIN_REINSTATED: &str = "twin_reinstated";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TwinEvent {
+    #[serde(rename = "twinId")]
+    pub twin_id: Uuid,
+    pub kind: String,
+    pub data: Value,
+    pub at: DateTime<Utc>,
+}
+
+//Fire and forget, a missing subscriber or a redis hiccup must never fail the code path emitting the event
+pub fn publish_twin_event(redis_url: RedisConnWrapper, twin_id: Uuid, kind: &str, data: Value) {
+    publish_event(redis_url, twin_channel(twin_id), twin_id, kind, data);
+}
+
+pub fn publish_owner_event(
+    redis_url: RedisConnWrapper,
+    owner_id: Uuid,
+    twin_id: Uuid,
+    kind: &str,
+    data: Value,
+) {
+    publish_event(redis_url, owner_channel(owner_id), twin_id, kind, data);
+}
+
+fn publish_event(
+    redis_url: RedisConnWrapper,
+    channel: String,
+    twin_id: Uuid,
+    kind: &str,
+    data: Value,
+) {
+    let event = TwinEvent {
+        twin_id,
+        kind: kind.to_string(),
+        data,
+        at: Utc::now(),
+    };
+
+    tokio::spawn(async move {
+        let message = match serde_json::to_string(&event) {
+            Ok(message) => message,
+            Err(error) => {
+                eprintln!("Error encoding twin event: {:?}", error);
+                return;
+            }
+        };
+
+        if let Err(error) = publish_to_redis(redis_url, channel, message).await {
+            eprintln!("Error publishing twin event: {:?}", error);
+        }
+    });
+}
+
+//Status names match core_twin_status, plus Failed when a lifecycle operation did not go through
+pub fn publish_twin_status(
+    redis_url: RedisConnWrapper,
+    twin_id: Uuid,
+    status: &str,
+    error: Option<&AppError>,
+) {
+    publish_twin_event(
+        redis_url,
+        twin_id,
+        TWIN_EVENT_STATUS,
+        json!({
+            "status": status,
+            "error": error.map(|error| error.message()),
+        }),
+    );
+}
+
+//Events published for the twin from now on, across every instance sharing the redis server
+pub async fn subscribe_twin_events(
+    redis_url: RedisConnWrapper,
+    twin_id: Uuid,
+) -> Result<impl Stream<Item = TwinEvent>, AppError> {
+    subscribe_events(redis_url, twin_channel(twin_id)).await
+}
+
+//Events about the twins of every model the owner has published
+pub async fn subscribe_owner_events(
+    redis_url: RedisConnWrapper,
+    owner_id: Uuid,
+) -> Result<impl Stream<Item = TwinEvent>, AppError> {
+    subscribe_events(redis_url, owner_channel(owner_id)).await
+}
+
+async fn subscribe_events(
+    redis_url: RedisConnWrapper,
+    channel: String,
+) -> Result<impl Stream<Item = TwinEvent>, AppError> {
+    let pubsub = subscribe_to_redis(redis_url, channel).await?;
+
+    Ok(pubsub.into_on_message().filter_map(|message| async move {
+        let payload = message.get_payload::<String>().ok()?;
+        serde_json::from_str::<TwinEvent>(&payload).ok()
+    }))
+}
+
+fn twin_channel(twin_id: Uuid) -> String {
+    let mut channel = "Events:Twins:".to_string();
+    channel += &twin_id.to_string();
+    channel
+}
+
+fn owner_channel(owner_id: Uuid) -> String {
+    let mut channel = "Events:Owners:".to_string();
+    channel += &owner_id.to_string();
+    channel
+}
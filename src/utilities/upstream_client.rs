@@ -0,0 +1,330 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::http::{HeaderMap, Method, Response, StatusCode, Uri};
+use hyper::{body::Bytes, client::HttpConnector, header, Body, Client, Request};
+use serde_json::json;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use super::{
+    app_error::AppError,
+    redis_connection_wrapper::RedisConnWrapper,
+    request_trace::inject_trace_headers,
+    twin_events::{publish_twin_event, TWIN_EVENT_HEALTH},
+};
+
+#[derive(Clone, Debug)]
+pub struct UpstreamConfig {
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub max_retries: u32,
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl UpstreamConfig {
+    //Read the proxy settings from the environment, falling back to defaults for anything unset
+    pub fn from_env() -> Self {
+        Self {
+            connect_timeout: Duration::from_millis(env_or("UPSTREAM_CONNECT_TIMEOUT_MS", 2000)),
+            request_timeout: Duration::from_millis(env_or("UPSTREAM_TIMEOUT_MS", 30000)),
+            max_retries: env_or("UPSTREAM_RETRIES", 2) as u32,
+            breaker_threshold: env_or("CIRCUIT_BREAKER_THRESHOLD", 5) as u32,
+            breaker_cooldown: Duration::from_secs(env_or("CIRCUIT_BREAKER_COOLDOWN_SECS", 30)),
+        }
+    }
+}
+
+fn env_or(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+#[derive(Debug)]
+pub enum ProxyError {
+    CircuitOpen,
+    Timeout,
+    Connect(hyper::Error),
+    InvalidRequest(String),
+}
+
+//An explicit conversion, a From impl would leave the error type of untyped Ok(..) blocks ambiguous
+impl ProxyError {
+    pub fn into_app_error(self) -> AppError {
+        match self {
+            ProxyError::CircuitOpen => AppError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Twin is currently unavailable, please try again later",
+            ),
+            ProxyError::Timeout => {
+                AppError::new(StatusCode::GATEWAY_TIMEOUT, "Twin did not respond in time")
+            }
+            ProxyError::Connect(error) => {
+                eprintln!("Error calling twin: {:?}", error);
+                AppError::new(StatusCode::BAD_GATEWAY, "Could not reach the twin")
+            }
+            ProxyError::InvalidRequest(message) => {
+                eprintln!("Error building twin request: {}", message);
+                AppError::new(StatusCode::BAD_REQUEST, "Invalid twin request")
+            }
+        }
+    }
+}
+
+pub struct UpstreamResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl UpstreamResponse {
+    pub fn into_response(self) -> Response<Body> {
+        let mut response = Response::new(Body::from(self.body));
+        *response.status_mut() = self.status;
+
+        for (name, value) in self.headers.iter() {
+            if name == header::CONNECTION
+                || name == header::TRANSFER_ENCODING
+                || name == header::CONTENT_LENGTH
+            {
+                continue;
+            }
+            response.headers_mut().append(name, value.clone());
+        }
+
+        response
+    }
+}
+
+//Per twin breaker, opened after threshold consecutive failures and probed again once the cooldown has passed
+#[derive(Default, Debug)]
+pub struct CircuitBreaker {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    //A probe whose call was dropped never reports back, so another one is let through a cooldown later
+    probe_started_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn is_open(&self) -> bool {
+        self.opened_at.is_some()
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    //Fail fast while the breaker is open, letting a single probe through per cooldown
+    pub fn before_request(&mut self, now: Instant, cooldown: Duration) -> Result<(), ProxyError> {
+        let Some(opened_at) = self.opened_at else {
+            return Ok(());
+        };
+
+        let waiting_since = self.probe_started_at.unwrap_or(opened_at);
+        if now.saturating_duration_since(waiting_since) < cooldown {
+            return Err(ProxyError::CircuitOpen);
+        }
+        self.probe_started_at = Some(now);
+
+        Ok(())
+    }
+
+    //true when the breaker was open and is now closed again
+    pub fn record_success(&mut self) -> bool {
+        let recovered = self.is_open();
+        *self = Self::default();
+        recovered
+    }
+
+    //true when this failure opened the breaker
+    pub fn record_failure(&mut self, now: Instant, threshold: u32) -> bool {
+        let was_open = self.is_open();
+
+        self.consecutive_failures += 1;
+        if self.probe_started_at.is_some() || self.consecutive_failures >= threshold {
+            self.opened_at = Some(now);
+            self.probe_started_at = None;
+        }
+
+        !was_open && self.is_open()
+    }
+}
+
+//Shared client used to call twins, wrapping hyper with timeouts, retries and a circuit breaker per twin
+#[derive(Clone)]
+pub struct UpstreamClient {
+    client: Client<HttpConnector>,
+    config: UpstreamConfig,
+    breakers: Arc<Mutex<HashMap<Uuid, CircuitBreaker>>>,
+    //Where breaker transitions are reported as twin health events
+    events: Option<RedisConnWrapper>,
+}
+
+impl UpstreamClient {
+    pub fn new(config: UpstreamConfig) -> Self {
+        let mut connector = HttpConnector::new();
+        connector.set_connect_timeout(Some(config.connect_timeout));
+
+        Self {
+            client: Client::builder().build(connector),
+            config,
+            breakers: Arc::new(Mutex::new(HashMap::new())),
+            events: None,
+        }
+    }
+
+    pub fn with_events(mut self, redis_url: RedisConnWrapper) -> Self {
+        self.events = Some(redis_url);
+        self
+    }
+
+    //Send a buffered request to a twin, retrying idempotent verbs on transport failures
+    #[tracing::instrument(name = "upstream_call", skip(self, headers, body), fields(status), err(Debug))]
+    pub async fn send(
+        &self,
+        twin_id: Uuid,
+        method: Method,
+        uri: Uri,
+        mut headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<UpstreamResponse, ProxyError> {
+        inject_trace_headers(&mut headers);
+
+        let attempts = if is_idempotent(&method) {
+            self.config.max_retries + 1
+        } else {
+            1
+        };
+
+        let mut last_error = ProxyError::Timeout;
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(100 * 2u64.pow(attempt - 1))).await;
+            }
+
+            self.before_request(twin_id)?;
+
+            let mut req = Request::builder()
+                .method(method.clone())
+                .uri(uri.clone())
+                .body(Body::from(body.clone()))
+                .map_err(|error| ProxyError::InvalidRequest(error.to_string()))?;
+            *req.headers_mut() = headers.clone();
+
+            let result = tokio::time::timeout(self.config.request_timeout, async {
+                let response = self.client.request(req).await?;
+                let (parts, body) = response.into_parts();
+                let body = hyper::body::to_bytes(body).await?;
+                Ok::<_, hyper::Error>(UpstreamResponse {
+                    status: parts.status,
+                    headers: parts.headers,
+                    body,
+                })
+            })
+            .await;
+
+            match result {
+                Ok(Ok(response)) => {
+                    tracing::Span::current().record("status", response.status.as_u16());
+                    if response.status.is_server_error() {
+                        self.record_failure(twin_id);
+                    } else {
+                        self.record_success(twin_id);
+                    }
+                    return Ok(response);
+                }
+                Ok(Err(error)) => {
+                    self.record_failure(twin_id);
+                    last_error = ProxyError::Connect(error);
+                }
+                Err(_elapsed) => {
+                    self.record_failure(twin_id);
+                    last_error = ProxyError::Timeout;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    //Open a streamed request to a twin; only the wait for the response head is timed
+    pub async fn open_stream(
+        &self,
+        twin_id: Uuid,
+        mut req: Request<Body>,
+    ) -> Result<Response<Body>, ProxyError> {
+        self.before_request(twin_id)?;
+
+        let span = tracing::info_span!("upstream_stream", %twin_id, uri = %req.uri());
+        span.in_scope(|| inject_trace_headers(req.headers_mut()));
+
+        let response = tokio::time::timeout(self.config.request_timeout, self.client.request(req))
+            .instrument(span)
+            .await;
+        match response {
+            Ok(Ok(response)) => {
+                self.record_success(twin_id);
+                Ok(response)
+            }
+            Ok(Err(error)) => {
+                self.record_failure(twin_id);
+                Err(ProxyError::Connect(error))
+            }
+            Err(_elapsed) => {
+                self.record_failure(twin_id);
+                Err(ProxyError::Timeout)
+            }
+        }
+    }
+
+    fn before_request(&self, twin_id: Uuid) -> Result<(), ProxyError> {
+        let mut breakers = self.breakers.lock().unwrap();
+        breakers
+            .entry(twin_id)
+            .or_default()
+            .before_request(Instant::now(), self.config.breaker_cooldown)
+    }
+
+    fn record_success(&self, twin_id: Uuid) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let recovered = breakers
+            .remove(&twin_id)
+            .is_some_and(|mut breaker| breaker.record_success());
+
+        if recovered {
+            self.publish_health(twin_id, json!({ "healthy": true }));
+        }
+    }
+
+    fn record_failure(&self, twin_id: Uuid) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let breaker = breakers.entry(twin_id).or_default();
+
+        if breaker.record_failure(Instant::now(), self.config.breaker_threshold) {
+            let consecutive_failures = breaker.consecutive_failures();
+            self.publish_health(
+                twin_id,
+                json!({ "healthy": false, "consecutiveFailures": consecutive_failures }),
+            );
+        }
+    }
+
+    fn publish_health(&self, twin_id: Uuid, data: serde_json::Value) {
+        if let Some(redis_url) = self.events.clone() {
+            publish_twin_event(redis_url, twin_id, TWIN_EVENT_HEALTH, data);
+        }
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE | Method::TRACE
+    )
+}
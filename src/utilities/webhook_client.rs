@@ -0,0 +1,211 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use axum::http::{header, Method, StatusCode, Uri};
+use hmac::{Hmac, Mac};
+use hyper::{client::HttpConnector, Body, Client, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use sha2::Sha256;
+
+use super::app_error::AppError;
+
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+//Client used to notify user supplied urls, every payload is signed with HMAC-SHA256
+#[derive(Clone)]
+pub struct WebhookClient {
+    client: Client<HttpsConnector<HttpConnector>>,
+    secret: String,
+}
+
+impl WebhookClient {
+    //Payloads are signed with their own key, never with the one that signs user tokens, so webhooks are off without WEBHOOK_SECRET
+    pub fn from_env() -> Option<Self> {
+        std::env::var("WEBHOOK_SECRET")
+            .ok()
+            .filter(|secret| !secret.trim().is_empty())
+            .map(Self::new)
+    }
+
+    pub fn new(secret: String) -> Self {
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Self {
+            client: Client::builder().build(connector),
+            secret,
+        }
+    }
+
+    //Receivers recompute the signature over "{timestamp}.{body}" to check the payload is ours and fresh
+    pub fn sign(&self, timestamp: i64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(body);
+
+        format!("sha256={:x}", mac.finalize().into_bytes())
+    }
+
+    //Post the payload, retrying a few times when the receiver is unreachable or answers with a 5xx
+    pub async fn deliver(&self, url: &Uri, body: Vec<u8>) -> Result<(), AppError> {
+        //The host may resolve somewhere else by now than when the url was accepted
+        check_webhook_host(url).await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = self.sign(timestamp, &body);
+
+        let mut last_error = String::new();
+        for attempt in 0..WEBHOOK_ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+            }
+
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri(url.clone())
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(WEBHOOK_TIMESTAMP_HEADER, timestamp)
+                .header(WEBHOOK_SIGNATURE_HEADER, &signature)
+                .body(Body::from(body.clone()))
+                .map_err(|error| {
+                    eprintln!("Error building webhook request: {:?}", error);
+                    AppError::new(StatusCode::BAD_REQUEST, "Invalid webhook url")
+                })?;
+
+            match tokio::time::timeout(WEBHOOK_TIMEOUT, self.client.request(req)).await {
+                Ok(Ok(response)) if !response.status().is_server_error() => return Ok(()),
+                Ok(Ok(response)) => last_error = format!("status {}", response.status()),
+                Ok(Err(error)) => last_error = error.to_string(),
+                Err(_elapsed) => last_error = "timed out".to_string(),
+            }
+        }
+
+        eprintln!("Error delivering webhook to {}: {}", url, last_error);
+        Err(AppError::new(
+            StatusCode::BAD_GATEWAY,
+            "Could not deliver webhook",
+        ))
+    }
+}
+
+//Only absolute http(s) urls on public hosts can be called back
+pub async fn parse_webhook_url(url: &str) -> Result<Uri, AppError> {
+    let uri = Uri::try_from(url).map_err(|error| {
+        eprintln!("Error parsing webhook url: {:?}", error);
+        AppError::new(StatusCode::BAD_REQUEST, "Invalid webhook url")
+    })?;
+
+    match (uri.scheme_str(), uri.host()) {
+        (Some("http" | "https"), Some(_)) => {
+            check_webhook_host(&uri).await?;
+            Ok(uri)
+        }
+        _ => Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Webhook url must be an absolute http or https url",
+        )),
+    }
+}
+
+//Every address the host resolves to must be public, unless WEBHOOK_ALLOWED_HOSTS lists the host
+async fn check_webhook_host(uri: &Uri) -> Result<(), AppError> {
+    let host = uri
+        .host()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    if webhook_allowed_hosts()
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    {
+        return Ok(());
+    }
+
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("https") {
+            443
+        } else {
+            80
+        });
+    let addresses = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|error| {
+            eprintln!("Error resolving webhook host {}: {:?}", host, error);
+            AppError::new(
+                StatusCode::BAD_REQUEST,
+                "Webhook host could not be resolved",
+            )
+        })?
+        .collect::<Vec<_>>();
+
+    if addresses.is_empty()
+        || addresses
+            .iter()
+            .any(|address| !is_public_address(address.ip()))
+    {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "Webhook url must point to a public host",
+        ));
+    }
+
+    Ok(())
+}
+
+fn webhook_allowed_hosts() -> Vec<String> {
+    std::env::var("WEBHOOK_ALLOWED_HOSTS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+//Loopback, private, link-local (cloud metadata), shared and reserved ranges are never called back
+pub fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => is_public_ipv4(address),
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(mapped),
+            None => is_public_ipv6(address),
+        },
+    }
+}
+
+fn is_public_ipv4(address: Ipv4Addr) -> bool {
+    let [first, second, ..] = address.octets();
+
+    !(address.is_unspecified()
+        || address.is_loopback()
+        || address.is_private()
+        || address.is_link_local()
+        || address.is_broadcast()
+        || address.is_documentation()
+        || address.is_multicast()
+        || first == 0
+        || (first == 100 && (64..128).contains(&second))
+        || first >= 240)
+}
+
+fn is_public_ipv6(address: Ipv6Addr) -> bool {
+    let first_segment = address.segments()[0];
+
+    !(address.is_unspecified()
+        || address.is_loopback()
+        || address.is_multicast()
+        //Unique local fc00::/7 and link-local fe80::/10
+        || (first_segment & 0xfe00) == 0xfc00
+        || (first_segment & 0xffc0) == 0xfe80)
+}
@@ -0,0 +1,64 @@
+use digital_twin_mw::helpers::batch_helpers::{stops_batch, BatchGate};
+
+//Runs the items in order through the gate, returning the status each one ended with and
+//whether it was skipped
+fn run_batch(items: &[(u16, bool)]) -> Vec<(u16, bool)> {
+    let gate = BatchGate::default();
+    items
+        .iter()
+        .map(|&(status, failed)| match gate.denied_status() {
+            Some(denied) => (denied, true),
+            None => {
+                gate.record(status, failed);
+                (status, false)
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn only_exhausted_quotas_stop_a_batch() {
+    assert!(stops_batch(451));
+    assert!(!stops_batch(403));
+    assert!(!stops_batch(429));
+    assert!(!stops_batch(500));
+}
+
+#[test]
+fn a_failed_condition_only_affects_its_own_item() {
+    let results = run_batch(&[
+        (200, false),
+        (403, true),
+        (200, false),
+        (429, true),
+        (200, false),
+    ]);
+
+    assert_eq!(
+        results,
+        vec![
+            (200, false),
+            (403, false),
+            (200, false),
+            (429, false),
+            (200, false)
+        ]
+    );
+}
+
+#[test]
+fn an_exhausted_quota_skips_the_rest_of_the_batch() {
+    let results = run_batch(&[(200, false), (403, true), (451, true), (200, false)]);
+
+    assert_eq!(
+        results,
+        vec![(200, false), (403, false), (451, false), (451, true)]
+    );
+}
+
+#[test]
+fn the_twins_own_451_does_not_stop_a_batch() {
+    let results = run_batch(&[(451, false), (200, false)]);
+
+    assert_eq!(results, vec![(451, false), (200, false)]);
+}
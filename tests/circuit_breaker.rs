@@ -0,0 +1,98 @@
+use std::time::{Duration, Instant};
+
+use digital_twin_mw::utilities::upstream_client::{CircuitBreaker, ProxyError};
+
+const COOLDOWN: Duration = Duration::from_secs(30);
+const THRESHOLD: u32 = 3;
+
+fn open_breaker(now: Instant) -> CircuitBreaker {
+    let mut breaker = CircuitBreaker::default();
+    for _ in 0..THRESHOLD {
+        breaker.record_failure(now, THRESHOLD);
+    }
+    breaker
+}
+
+#[test]
+fn opens_after_threshold_consecutive_failures() {
+    let now = Instant::now();
+    let mut breaker = CircuitBreaker::default();
+
+    assert!(!breaker.record_failure(now, THRESHOLD));
+    assert!(!breaker.record_failure(now, THRESHOLD));
+    assert!(breaker.before_request(now, COOLDOWN).is_ok());
+
+    //Only the failure that opens the breaker reports it
+    assert!(breaker.record_failure(now, THRESHOLD));
+    assert!(breaker.is_open());
+    assert_eq!(breaker.consecutive_failures(), THRESHOLD);
+    assert!(matches!(
+        breaker.before_request(now + Duration::from_secs(1), COOLDOWN),
+        Err(ProxyError::CircuitOpen)
+    ));
+}
+
+#[test]
+fn a_success_resets_the_failure_count() {
+    let now = Instant::now();
+    let mut breaker = CircuitBreaker::default();
+
+    breaker.record_failure(now, THRESHOLD);
+    breaker.record_failure(now, THRESHOLD);
+    assert!(!breaker.record_success());
+    assert!(!breaker.record_failure(now, THRESHOLD));
+    assert!(!breaker.is_open());
+}
+
+#[test]
+fn lets_a_single_probe_through_after_the_cooldown() {
+    let now = Instant::now();
+    let mut breaker = open_breaker(now);
+
+    let after_cooldown = now + COOLDOWN;
+    assert!(breaker.before_request(after_cooldown, COOLDOWN).is_ok());
+    assert!(matches!(
+        breaker.before_request(after_cooldown, COOLDOWN),
+        Err(ProxyError::CircuitOpen)
+    ));
+
+    //A successful probe closes the breaker
+    assert!(breaker.record_success());
+    assert!(!breaker.is_open());
+    assert!(breaker.before_request(after_cooldown, COOLDOWN).is_ok());
+}
+
+#[test]
+fn a_failed_probe_reopens_for_another_cooldown() {
+    let now = Instant::now();
+    let mut breaker = open_breaker(now);
+
+    let probe_at = now + COOLDOWN;
+    assert!(breaker.before_request(probe_at, COOLDOWN).is_ok());
+    assert!(!breaker.record_failure(probe_at, THRESHOLD));
+    assert!(breaker.is_open());
+
+    assert!(breaker
+        .before_request(probe_at + COOLDOWN / 2, COOLDOWN)
+        .is_err());
+    assert!(breaker
+        .before_request(probe_at + COOLDOWN, COOLDOWN)
+        .is_ok());
+}
+
+#[test]
+fn a_dropped_probe_does_not_keep_the_breaker_open_forever() {
+    let now = Instant::now();
+    let mut breaker = open_breaker(now);
+
+    //The probe's call is cancelled and never records a result
+    let probe_at = now + COOLDOWN;
+    assert!(breaker.before_request(probe_at, COOLDOWN).is_ok());
+
+    assert!(breaker
+        .before_request(probe_at + COOLDOWN - Duration::from_secs(1), COOLDOWN)
+        .is_err());
+    assert!(breaker
+        .before_request(probe_at + COOLDOWN, COOLDOWN)
+        .is_ok());
+}
@@ -0,0 +1,11 @@
+use digital_twin_mw::utilities::redis_connection_wrapper::RedisConnWrapper;
+
+//Needs the redis from docker-compose, REDIS_URL overrides the default.
+//Tests using it are ignored by default, run them with `cargo test -- --ignored`
+pub fn redis_url() -> RedisConnWrapper {
+    dotenvy::dotenv().ok();
+    let redis_url =
+        std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+
+    RedisConnWrapper(redis_url)
+}
@@ -0,0 +1,83 @@
+use digital_twin_mw::utilities::json_path::{
+    parse_json_path, select, select_number, set, PathSegment,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn paths_are_parsed_into_segments() {
+    assert_eq!(parse_json_path("$").unwrap(), vec![]);
+    assert_eq!(
+        parse_json_path("$.rooms[2]['living room'][\"set point\"]").unwrap(),
+        vec![
+            PathSegment::Key("rooms".to_string()),
+            PathSegment::Index(2),
+            PathSegment::Key("living room".to_string()),
+            PathSegment::Key("set point".to_string()),
+        ]
+    );
+
+    for path in ["", "rooms", "$.", "$..rooms", "$[abc]", "$[0", "$rooms"] {
+        assert!(parse_json_path(path).is_err(), "{} should be invalid", path);
+    }
+}
+
+#[test]
+fn values_are_selected_by_key_and_index() {
+    let document = json!({ "rooms": [{ "name": "kitchen" }, { "name": "hall" }] });
+
+    let segments = parse_json_path("$.rooms[1].name").unwrap();
+    assert_eq!(select(&document, &segments), Some(&json!("hall")));
+
+    let segments = parse_json_path("$.rooms[5].name").unwrap();
+    assert_eq!(select(&document, &segments), None);
+
+    assert_eq!(select(&document, &[]), Some(&document));
+}
+
+#[test]
+fn numbers_are_read_from_numbers_strings_and_booleans() {
+    let document = json!({ "a": 1.5, "b": " 2 ", "c": true, "d": "warm", "e": null });
+
+    assert_eq!(select_number(&document, "$.a"), Some(1.5));
+    assert_eq!(select_number(&document, "$.b"), Some(2.0));
+    assert_eq!(select_number(&document, "$.c"), Some(1.0));
+    assert_eq!(select_number(&document, "$.d"), None);
+    assert_eq!(select_number(&document, "$.e"), None);
+    assert_eq!(select_number(&document, "$.missing"), None);
+}
+
+#[test]
+fn set_creates_objects_along_the_path() {
+    let mut target = Value::Null;
+
+    set(
+        &mut target,
+        &parse_json_path("$.heating.target").unwrap(),
+        json!(21),
+    )
+    .unwrap();
+    assert_eq!(target, json!({ "heating": { "target": 21 } }));
+
+    set(&mut target, &parse_json_path("$").unwrap(), json!("all")).unwrap();
+    assert_eq!(target, json!("all"));
+}
+
+#[test]
+fn set_only_writes_to_existing_array_items() {
+    let mut target = json!({ "rooms": [{}, {}] });
+
+    set(
+        &mut target,
+        &parse_json_path("$.rooms[1].target").unwrap(),
+        json!(19),
+    )
+    .unwrap();
+    assert_eq!(target, json!({ "rooms": [{}, { "target": 19 }] }));
+
+    assert!(set(
+        &mut target,
+        &parse_json_path("$.rooms[2]").unwrap(),
+        json!(19)
+    )
+    .is_err());
+}
@@ -0,0 +1,97 @@
+use digital_twin_mw::helpers::mqtt_bridge_helpers::{
+    binding_topic_prefix, validate_binding_topics, BindingTopics,
+};
+use uuid::Uuid;
+
+fn binding(subscribe_topic: &str, publish_topic: Option<&str>) -> BindingTopics {
+    BindingTopics {
+        subscribe_topic: subscribe_topic.to_string(),
+        publish_topic: publish_topic.map(str::to_string),
+    }
+}
+
+#[test]
+fn topics_must_stay_in_the_users_namespace() {
+    let user_id = Uuid::new_v4();
+    let prefix = binding_topic_prefix(user_id);
+
+    let own = binding(
+        &format!("{}sensors/+/temperature", prefix),
+        Some(&format!("{}twins/forecast", prefix)),
+    );
+    assert!(validate_binding_topics(user_id, &own, &[]).is_ok());
+
+    let other_user = binding_topic_prefix(Uuid::new_v4());
+    for refused in [
+        binding("#", None),
+        binding("users/+/sensors/#", None),
+        binding(&format!("{}sensors/#", other_user), None),
+        binding(&prefix, None),
+        binding(
+            &format!("{}sensors/#", prefix),
+            Some(&format!("{}results", other_user)),
+        ),
+    ] {
+        assert!(
+            validate_binding_topics(user_id, &refused, &[]).is_err(),
+            "{} -> {:?} is refused",
+            refused.subscribe_topic,
+            refused.publish_topic
+        );
+    }
+}
+
+#[test]
+fn a_binding_must_not_read_its_own_output() {
+    let user_id = Uuid::new_v4();
+    let prefix = binding_topic_prefix(user_id);
+
+    let echo = binding(
+        &format!("{}sensors/#", prefix),
+        Some(&format!("{}sensors/results", prefix)),
+    );
+    assert!(validate_binding_topics(user_id, &echo, &[]).is_err());
+
+    //Failures go to publishTopic/error, which counts as output too
+    let errors = binding(
+        &format!("{}results/error", prefix),
+        Some(&format!("{}results", prefix)),
+    );
+    assert!(validate_binding_topics(user_id, &errors, &[]).is_err());
+}
+
+#[test]
+fn cycles_across_bindings_are_refused() {
+    let user_id = Uuid::new_v4();
+    let prefix = binding_topic_prefix(user_id);
+    let topic = |name: &str| format!("{}{}", prefix, name);
+
+    //a -> b -> c exists, c -> a would close the loop
+    let existing = vec![
+        binding(&topic("a"), Some(&topic("b"))),
+        binding(&topic("b"), Some(&topic("c"))),
+    ];
+    assert!(
+        validate_binding_topics(user_id, &binding(&topic("c"), Some(&topic("a"))), &existing)
+            .is_err()
+    );
+
+    //Wildcards downstream count as well
+    let existing = vec![binding(&topic("b/#"), Some(&topic("a")))];
+    assert!(validate_binding_topics(
+        user_id,
+        &binding(&topic("a"), Some(&topic("b/x"))),
+        &existing
+    )
+    .is_err());
+
+    //A chain that ends somewhere else is fine
+    let existing = vec![
+        binding(&topic("a"), Some(&topic("b"))),
+        binding(&topic("b"), None),
+    ];
+    assert!(
+        validate_binding_topics(user_id, &binding(&topic("c"), Some(&topic("a"))), &existing)
+            .is_ok()
+    );
+}
@@ -0,0 +1,115 @@
+use axum::http::StatusCode;
+use digital_twin_mw::helpers::{
+    openapi_helpers::{check_actions_against_documents, openapi_operations, path_matches},
+    policy_document_helpers::PolicyDocument,
+};
+use serde_json::{json, Value};
+
+fn solver_document() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "paths": {
+            "/simulate": {
+                "post": { "summary": "Run a simulation" },
+                "parameters": [],
+            },
+            "/runs/{runId}": {
+                "get": { "operationId": "getRun" },
+                "delete": {},
+            },
+        },
+    })
+}
+
+fn policy_document(actions: &str) -> PolicyDocument {
+    let document = format!(
+        "format: dt-policy/v1\nname: Solver\ndescription: Solver access\nactions:\n{}",
+        actions
+    );
+    PolicyDocument::parse(&document).unwrap()
+}
+
+#[test]
+fn operations_are_listed_per_path_and_verb() {
+    let document = solver_document();
+
+    let mut operations = openapi_operations(&document)
+        .into_iter()
+        .map(|(path, verb, _)| (path, verb))
+        .collect::<Vec<_>>();
+    operations.sort();
+
+    //Path level fields such as parameters are not operations
+    assert_eq!(
+        operations,
+        vec![
+            ("/runs/{runId}", "delete"),
+            ("/runs/{runId}", "get"),
+            ("/simulate", "post"),
+        ]
+    );
+    assert!(openapi_operations(&json!({ "openapi": "3.0.3" })).is_empty());
+}
+
+#[test]
+fn templated_segments_match_any_value() {
+    assert!(path_matches("/runs/{runId}", "runs/42"));
+    assert!(path_matches("/Simulate/", "simulate"));
+    assert!(!path_matches("/runs/{runId}", "runs"));
+    assert!(!path_matches("/runs/{runId}", "runs/42/log"));
+    assert!(!path_matches("/runs/{runId}", "jobs/42"));
+}
+
+#[test]
+fn actions_must_name_an_operation_of_their_entry_point() {
+    let document = solver_document();
+    let documents = [(Some("solver"), &document)];
+
+    let request = policy_document(
+        r#"
+  - endPoint: simulate
+    componentAlias: solver
+    verb: POST
+    description: Simulate
+    quota: { count: 10, reset: daily }
+  - endPoint: runs/latest
+    componentAlias: solver
+    verb: get
+    description: Latest run
+    quota: { count: 10, reset: daily }
+"#,
+    )
+    .into_policy_request();
+    assert!(check_actions_against_documents(&documents, &request.policy_action_info).is_ok());
+
+    let request = policy_document(
+        r#"
+  - endPoint: simulate
+    componentAlias: solver
+    verb: get
+    description: Simulate
+    quota: { count: 10, reset: daily }
+"#,
+    )
+    .into_policy_request();
+    let error =
+        check_actions_against_documents(&documents, &request.policy_action_info).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn entry_points_without_a_document_are_not_checked() {
+    let document = solver_document();
+    let documents = [(Some("solver"), &document)];
+
+    let request = policy_document(
+        r#"
+  - endPoint: anything
+    verb: patch
+    description: Default entry point
+    quota: { count: 10, reset: daily }
+"#,
+    )
+    .into_policy_request();
+    assert!(check_actions_against_documents(&documents, &request.policy_action_info).is_ok());
+}
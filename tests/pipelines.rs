@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use digital_twin_mw::helpers::pipeline_helpers::{
+    build_step_body, validate_step_graph, PipelineMapping, PIPELINE_INPUT,
+};
+use serde_json::{json, Value};
+
+fn mapping(from: &str, path: &str, to: &str) -> PipelineMapping {
+    PipelineMapping {
+        from: from.to_string(),
+        path: path.to_string(),
+        to: to.to_string(),
+    }
+}
+
+#[test]
+fn steps_may_read_from_the_input_and_earlier_steps() {
+    let read_input = [mapping(PIPELINE_INPUT, "$.city", "$.location")];
+    let read_forecast = [mapping("forecast", "$.temperature", "$.target")];
+
+    assert!(validate_step_graph(&[
+        ("forecast", &read_input[..]),
+        ("heating", &read_forecast[..])
+    ])
+    .is_ok());
+}
+
+#[test]
+fn steps_cannot_read_from_themselves_or_later_steps() {
+    let read_heating = [mapping("heating", "$", "$")];
+
+    let error =
+        validate_step_graph(&[("forecast", &read_heating[..]), ("heating", &[][..])]).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+
+    let error = validate_step_graph(&[("heating", &read_heating[..])]).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn step_keys_must_be_unique_and_not_the_input() {
+    for steps in [
+        vec![("forecast", &[][..]), (" forecast ", &[][..])],
+        vec![(PIPELINE_INPUT, &[][..])],
+        vec![("", &[][..])],
+        vec![],
+    ] {
+        let error = validate_step_graph(&steps).unwrap_err();
+        assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[test]
+fn mappings_with_invalid_paths_are_rejected() {
+    let bad_path = [mapping(PIPELINE_INPUT, "city", "$")];
+
+    let error = validate_step_graph(&[("forecast", &bad_path[..])]).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn mapped_values_are_written_over_the_base_body() {
+    let outputs = HashMap::from([
+        (PIPELINE_INPUT.to_string(), json!({ "city": "Berlin" })),
+        (
+            "forecast".to_string(),
+            json!({ "hours": [{ "temperature": 4.5 }] }),
+        ),
+    ]);
+    let mappings = [
+        mapping(PIPELINE_INPUT, "$.city", "$.location.city"),
+        mapping(
+            "forecast",
+            "$.hours[0].temperature",
+            "$['target temperature']",
+        ),
+    ];
+
+    let body = build_step_body(Some(json!({ "mode": "eco" })), &mappings, &outputs).unwrap();
+    assert_eq!(
+        body,
+        Some(json!({
+            "mode": "eco",
+            "location": { "city": "Berlin" },
+            "target temperature": 4.5,
+        }))
+    );
+}
+
+#[test]
+fn steps_without_mappings_keep_their_body() {
+    let outputs = HashMap::from([(PIPELINE_INPUT.to_string(), Value::Null)]);
+
+    assert_eq!(build_step_body(None, &[], &outputs).unwrap(), None);
+    assert_eq!(
+        build_step_body(Some(json!([1, 2])), &[], &outputs).unwrap(),
+        Some(json!([1, 2]))
+    );
+}
+
+#[test]
+fn a_missing_source_value_fails_the_step() {
+    let outputs = HashMap::from([(PIPELINE_INPUT.to_string(), json!({ "city": "Berlin" }))]);
+    let mappings = [mapping(PIPELINE_INPUT, "$.country", "$.country")];
+
+    let error = build_step_body(None, &mappings, &outputs).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,59 @@
+use digital_twin_mw::helpers::{
+    policy_document_helpers::PolicyDocument, policy_mgmt_helpers::build_policy_action,
+};
+use sea_orm::Set;
+use uuid::Uuid;
+
+const DOCUMENT: &str = r#"
+format: dt-policy/v1
+name: Solver
+description: Solver access
+actions:
+  - endPoint: simulate
+    verb: post
+    description: Run a simulation
+    quota:
+      count: 100
+      reset: weekly
+"#;
+
+fn reset_token(tokens: &[(String, String)]) -> &str {
+    tokens
+        .iter()
+        .find(|(key, _)| key.contains(":Reset:"))
+        .map(|(_, token)| token.as_str())
+        .unwrap()
+}
+
+#[test]
+fn omitted_reset_frequency_resets_daily_in_the_row_and_in_redis() {
+    let mut request = PolicyDocument::parse(DOCUMENT)
+        .unwrap()
+        .into_policy_request();
+    let mut action = request.policy_action_info.remove(0);
+    action.reset_frequency_id = None;
+
+    let (policy_action, tokens) =
+        build_policy_action(Uuid::new_v4(), Uuid::new_v4(), action, &[]).unwrap();
+
+    assert_eq!(policy_action.reset_frequency_id, Set(1));
+    assert_eq!(reset_token(&tokens), (24 * 3600).to_string());
+}
+
+#[test]
+fn given_reset_frequency_is_kept() {
+    let mut request = PolicyDocument::parse(DOCUMENT)
+        .unwrap()
+        .into_policy_request();
+    let action = request.policy_action_info.remove(0);
+
+    let policy_id = Uuid::new_v4();
+    let (policy_action, tokens) =
+        build_policy_action(policy_id, Uuid::new_v4(), action, &[]).unwrap();
+
+    assert_eq!(policy_action.reset_frequency_id, Set(2));
+    assert_eq!(reset_token(&tokens), (7 * 24 * 3600).to_string());
+    assert!(tokens
+        .iter()
+        .all(|(key, _)| key.starts_with(&format!("Policy:Models:{}:", policy_id))));
+}
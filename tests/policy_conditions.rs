@@ -0,0 +1,112 @@
+use digital_twin_mw::helpers::policy_condition_helpers::{
+    conditions_from_request, first_failed_condition,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn first_failing_condition_is_reported_with_the_actual_value() {
+    let conditions = conditions_from_request(&json!([
+        { "source": "body", "field": "horizon", "op": "lte", "value": 3600 },
+        { "source": "body", "field": "params.alpha", "op": "between", "value": [0, 1] },
+        { "source": "body", "field": "params.mode", "op": "in", "value": ["fast", "accurate"] },
+        { "source": "body", "field": "scenario", "op": "matches", "value": "^[a-z_]+$" },
+    ]))
+    .unwrap();
+
+    let allowed = json!({
+        "horizon": 1800,
+        "params": { "alpha": 0.5, "mode": "fast" },
+        "scenario": "base_case",
+    });
+    assert_eq!(
+        first_failed_condition(&conditions, &allowed, &Value::Null),
+        None
+    );
+
+    let refused = json!({
+        "horizon": 1800,
+        "params": { "alpha": 1.5, "mode": "fast" },
+        "scenario": "base_case",
+    });
+    let failure = first_failed_condition(&conditions, &refused, &Value::Null).unwrap();
+    assert_eq!(failure.index, 1);
+    assert_eq!(failure.actual, Some(json!(1.5)));
+    assert_eq!(failure.details()["field"], "params.alpha");
+}
+
+#[test]
+fn missing_fields_only_satisfy_exists_false() {
+    let conditions = conditions_from_request(&json!([
+        { "source": "body", "field": "debug", "op": "exists", "value": false },
+        { "source": "body", "field": "items.0.id", "op": "ne", "value": 0 },
+    ]))
+    .unwrap();
+
+    let failure =
+        first_failed_condition(&conditions, &json!({ "items": [] }), &Value::Null).unwrap();
+    assert_eq!(failure.index, 1);
+    assert_eq!(failure.actual, None);
+
+    let failure =
+        first_failed_condition(&conditions, &json!({ "debug": true }), &Value::Null).unwrap();
+    assert_eq!(failure.index, 0);
+
+    assert_eq!(
+        first_failed_condition(
+            &conditions,
+            &json!({ "items": [{ "id": 7 }] }),
+            &Value::Null
+        ),
+        None
+    );
+}
+
+#[test]
+fn user_conditions_check_the_user_document() {
+    let conditions = conditions_from_request(&json!([
+        { "source": "user", "field": "attributes.tier", "op": "in", "value": ["gold", "research"] },
+        { "source": "user", "field": "email", "op": "matches", "value": "@example\\.org$" },
+    ]))
+    .unwrap();
+
+    let user = json!({
+        "email": "ada@example.org",
+        "role": "User",
+        "attributes": { "tier": "research" },
+    });
+    assert_eq!(
+        first_failed_condition(&conditions, &Value::Null, &user),
+        None
+    );
+
+    let user = json!({
+        "email": "ada@example.org",
+        "role": "User",
+        "attributes": {},
+    });
+    assert_eq!(
+        first_failed_condition(&conditions, &Value::Null, &user)
+            .unwrap()
+            .index,
+        0
+    );
+}
+
+#[test]
+fn invalid_conditions_are_refused() {
+    for condition in [
+        json!({ "source": "header", "field": "a", "op": "eq", "value": 1 }),
+        json!({ "source": "body", "field": "a..b", "op": "eq", "value": 1 }),
+        json!({ "source": "body", "field": "a", "op": "like", "value": 1 }),
+        json!({ "source": "body", "field": "a", "op": "lt", "value": [1] }),
+        json!({ "source": "body", "field": "a", "op": "between", "value": [5, 1] }),
+        json!({ "source": "body", "field": "a", "op": "in", "value": "x" }),
+        json!({ "source": "body", "field": "a", "op": "matches", "value": "(" }),
+    ] {
+        assert!(
+            conditions_from_request(&json!([condition])).is_err(),
+            "{}",
+            condition
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use digital_twin_mw::helpers::policy_document_helpers::PolicyDocument;
+
+const DOCUMENT: &str = r#"
+format: dt-policy/v1
+name: Research licence
+description: Business hours simulations for research partners
+blockAfter: 3
+actions:
+  - endPoint: simulate
+    verb: post
+    description: Run a simulation
+    quota:
+      count: 100
+      reset: daily
+    rateLimit:
+      algorithm: token_bucket
+      requests: 10
+      period: minute
+      burst: 20
+    validity:
+      timezone: Europe/Berlin
+      weekdays: [mon, tue, wed, thu, fri]
+      hours:
+        - from: "09:00"
+          to: "17:00"
+    conditions:
+      - source: body
+        field: horizon
+        op: lte
+        value: 3600
+      - source: user
+        field: attributes.tier
+        op: in
+        value: [research]
+  - endPoint: status
+    componentAlias: solver
+    verb: GET
+    description: Solver status
+    quota:
+      count: 1000
+      reset: never
+    cache:
+      ttl: 30
+"#;
+
+#[test]
+fn yaml_document_compiles_into_a_policy_request() {
+    let document = PolicyDocument::parse(DOCUMENT).unwrap();
+    let request = document.into_policy_request();
+
+    assert_eq!(
+        request.policy_info.name.as_deref(),
+        Some("Research licence")
+    );
+    assert_eq!(request.policy_info.block_after, Some(3));
+    assert_eq!(request.policy_action_info.len(), 2);
+
+    let simulate = &request.policy_action_info[0];
+    assert_eq!(simulate.action_count, Some(100));
+    assert_eq!(simulate.reset_frequency_id, Some(1));
+    assert_eq!(
+        simulate.rate_limit_algorithm.as_deref(),
+        Some("token_bucket")
+    );
+    assert_eq!(simulate.rate_limit_burst, Some(20));
+    assert!(simulate.validity.is_some());
+    assert_eq!(
+        simulate
+            .conditions
+            .as_ref()
+            .and_then(|c| c.as_array())
+            .map(Vec::len),
+        Some(2)
+    );
+    assert_eq!(simulate.is_cacheable, None);
+
+    let status = &request.policy_action_info[1];
+    assert_eq!(status.component_alias.as_deref(), Some("solver"));
+    assert_eq!(status.reset_frequency_id, Some(5));
+    assert_eq!(status.is_cacheable, Some(true));
+    assert_eq!(status.cache_ttl, Some(30));
+    assert_eq!(status.rate_limit_algorithm, None);
+}
+
+#[test]
+fn documents_survive_a_yaml_and_json_round_trip() {
+    let document = PolicyDocument::parse(DOCUMENT).unwrap();
+
+    let from_yaml = PolicyDocument::parse(&document.to_yaml().unwrap()).unwrap();
+    assert_eq!(from_yaml, document);
+
+    let from_json = PolicyDocument::parse(&document.to_json().unwrap()).unwrap();
+    assert_eq!(from_json, document);
+}
+
+#[test]
+fn errors_name_the_offending_action() {
+    let document = DOCUMENT.replace("period: minute", "period: hour");
+    let error = PolicyDocument::parse(&document).unwrap_err();
+    assert!(
+        error.message().starts_with("actions[0] (POST simulate): "),
+        "{}",
+        error.message()
+    );
+
+    let document = DOCUMENT.replace("reset: never", "reset: hourly");
+    let error = PolicyDocument::parse(&document).unwrap_err();
+    assert!(
+        error.message().starts_with("actions[1] (GET status): "),
+        "{}",
+        error.message()
+    );
+}
+
+#[test]
+fn unknown_formats_and_fields_are_refused() {
+    assert!(PolicyDocument::parse(&DOCUMENT.replace("dt-policy/v1", "dt-policy/v2")).is_err());
+    assert!(PolicyDocument::parse(&DOCUMENT.replace("blockAfter", "blockAfterr")).is_err());
+    assert!(PolicyDocument::parse(&DOCUMENT.replace("ttl: 30", "ttl: 30\n      size: 5")).is_err());
+}
@@ -0,0 +1,188 @@
+use digital_twin_mw::helpers::{
+    policy_document_helpers::PolicyDocument,
+    policy_odrl_helpers::{export_odrl_policy, import_odrl_policy},
+};
+use serde_json::json;
+use uuid::Uuid;
+
+fn target(model_id: Uuid, endpoint: &str) -> String {
+    format!("urn:dt:model:{}:endpoint:POST:{}", model_id, endpoint)
+}
+
+#[test]
+fn partner_policy_imports_counts_dates_and_prohibitions() {
+    let model_id = Uuid::new_v4();
+    let policy = json!({
+        "@context": "http://www.w3.org/ns/odrl.jsonld",
+        "@type": "Agreement",
+        "uid": "https://partner.example/policies/42",
+        "permission": [{
+            "target": target(model_id, "simulate"),
+            "action": "odrl:use",
+            "constraint": [
+                { "leftOperand": "count", "operator": "lteq", "rightOperand": 500, "unit": "dt:monthly" },
+                { "leftOperand": "dateTime", "operator": "gteq", "rightOperand": { "@value": "2024-01-01", "@type": "xsd:date" } },
+                { "leftOperand": "odrl:dateTime", "operator": "lt", "rightOperand": "2025-01-01" },
+            ],
+        }],
+        "prohibition": [{
+            "target": { "uid": target(model_id, "simulate") },
+            "action": "use",
+            "constraint": { "leftOperand": "dt:body.horizon", "operator": "gt", "rightOperand": 3600 },
+        }],
+    });
+
+    let import = import_odrl_policy(&policy, model_id).unwrap();
+    assert!(import.unsupported.is_empty(), "{:?}", import.unsupported);
+
+    let document = import.document;
+    assert_eq!(document.name, "https://partner.example/policies/42");
+    let action = &document.actions[0];
+    assert_eq!(action.end_point, "simulate");
+    assert_eq!(action.verb, "POST");
+    assert_eq!(action.quota.count, 500);
+    assert_eq!(action.quota.reset, "monthly");
+
+    let validity = action.validity.as_ref().unwrap();
+    assert_eq!(validity.from.as_deref(), Some("2024-01-01"));
+    assert_eq!(validity.until.as_deref(), Some("2024-12-31"));
+
+    //The prohibition is negated into a condition on the permission
+    assert_eq!(action.conditions.len(), 1);
+    assert_eq!(action.conditions[0].field, "horizon");
+    assert_eq!(action.conditions[0].op, "lte");
+    assert_eq!(action.conditions[0].value, json!(3600));
+}
+
+#[test]
+fn terms_that_cannot_be_enforced_are_reported_with_their_path() {
+    let model_id = Uuid::new_v4();
+    let policy = json!({
+        "uid": "urn:policy:1",
+        "permission": [{
+            "target": target(model_id, "simulate"),
+            "action": "use",
+            "assignee": "https://partner.example/people/ada",
+            "constraint": [
+                { "leftOperand": "purpose", "operator": "eq", "rightOperand": "research" },
+                { "leftOperand": "dateTime", "operator": "lteq", "rightOperand": "2024-12-31T12:00:00Z" },
+                { "or": [] },
+            ],
+            "duty": [{ "action": "compensate" }],
+        }, {
+            "target": target(model_id, "export"),
+            "action": "distribute",
+        }],
+        "obligation": [{ "action": "delete" }],
+    });
+
+    let import = import_odrl_policy(&policy, model_id).unwrap();
+    let paths = import
+        .unsupported
+        .iter()
+        .map(|unsupported| unsupported.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            "obligation[0]",
+            "permission[0].assignee",
+            "permission[0].constraint[0]",
+            "permission[0].constraint[1]",
+            "permission[0].constraint[2]",
+            "permission[0].duty[0]",
+            "permission[1].action",
+        ]
+    );
+    assert!(import.unsupported[0].reason.contains("delete"));
+}
+
+#[test]
+fn invalid_targets_and_conflicts_are_refused() {
+    let model_id = Uuid::new_v4();
+
+    let other_model = json!({
+        "uid": "urn:policy:2",
+        "permission": [{ "target": target(Uuid::new_v4(), "simulate"), "action": "use" }],
+    });
+    assert!(import_odrl_policy(&other_model, model_id).is_err());
+
+    let conflict = json!({
+        "uid": "urn:policy:3",
+        "permission": [{ "target": target(model_id, "simulate"), "action": "use" }],
+        "prohibition": [{ "target": target(model_id, "simulate"), "action": "use" }],
+    });
+    assert!(import_odrl_policy(&conflict, model_id).is_err());
+}
+
+#[test]
+fn exported_policies_import_back_unchanged() {
+    let model_id = Uuid::new_v4();
+    let document = PolicyDocument::parse(
+        r#"
+format: dt-policy/v1
+name: Research licence
+description: Business hours simulations
+blockAfter: 3
+actions:
+  - endPoint: simulate
+    verb: POST
+    description: Run a simulation
+    quota:
+      count: 100
+      reset: daily
+    rateLimit:
+      algorithm: token_bucket
+      requests: 10
+      period: minute
+      burst: 20
+    validity:
+      timezone: Europe/Berlin
+      from: "2024-01-01"
+      until: "2024-12-31"
+      weekdays: [mon, tue, wed, thu, fri]
+      hours:
+        - from: "09:00"
+          to: "17:00"
+    conditions:
+      - source: body
+        field: params.mode
+        op: in
+        value: [fast, accurate]
+      - source: user
+        field: attributes.tier
+        op: eq
+        value: research
+      - source: body
+        field: debug
+        op: exists
+        value: false
+  - endPoint: status
+    componentAlias: solver
+    verb: POST
+    description: Solver status
+    quota:
+      count: 2147483647
+      reset: never
+"#,
+    )
+    .unwrap();
+
+    let odrl = export_odrl_policy(&document, model_id);
+    assert_eq!(
+        odrl["permission"][0]["constraint"][0]["leftOperand"],
+        "count"
+    );
+    assert_eq!(
+        odrl["permission"][1]["target"],
+        format!(
+            "urn:dt:model:{}:component:solver:endpoint:POST:status",
+            model_id
+        )
+    );
+    assert!(odrl["permission"][1].get("constraint").is_none());
+
+    let import = import_odrl_policy(&odrl, model_id).unwrap();
+    assert!(import.unsupported.is_empty(), "{:?}", import.unsupported);
+    assert_eq!(import.document, document);
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use digital_twin_mw::helpers::policy_validity_helpers::ValidityWindow;
+use serde_json::json;
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[test]
+fn business_hours_are_evaluated_in_the_window_timezone() {
+    let window = ValidityWindow::from_request(&json!({
+        "timezone": "Europe/Berlin",
+        "weekdays": ["Mon", "tue", "wednesday", "thu", "fri"],
+        "hours": [{ "from": "09:00", "to": "17:00" }],
+    }))
+    .unwrap();
+
+    //Monday 08:30 UTC is 10:30 in Berlin
+    assert!(window.check(at("2024-06-03T08:30:00Z")).is_ok());
+
+    let after_hours = window.check(at("2024-06-03T15:30:00Z")).unwrap_err();
+    assert_eq!(after_hours.reason, "outside_hours");
+    assert_eq!(after_hours.timezone, "Europe/Berlin");
+    assert_eq!(after_hours.local_time, "2024-06-03T17:30:00");
+
+    let weekend = window.check(at("2024-06-08T10:00:00Z")).unwrap_err();
+    assert_eq!(weekend.reason, "weekday_not_allowed");
+}
+
+#[test]
+fn contract_period_includes_its_first_and_last_day() {
+    let window = ValidityWindow::from_request(&json!({
+        "from": "2024-01-01",
+        "until": "2024-12-31",
+    }))
+    .unwrap();
+
+    assert!(window.check(at("2024-01-01T00:00:00Z")).is_ok());
+    assert!(window.check(at("2024-12-31T23:59:59Z")).is_ok());
+    assert_eq!(
+        window.check(at("2023-12-31T23:59:59Z")).unwrap_err().reason,
+        "not_yet_valid"
+    );
+    assert_eq!(
+        window.check(at("2025-01-01T00:00:00Z")).unwrap_err().reason,
+        "expired"
+    );
+}
+
+#[test]
+fn hour_ranges_can_run_past_midnight() {
+    let window = ValidityWindow::from_request(&json!({
+        "hours": [{ "from": "22:00", "to": "06:00" }],
+    }))
+    .unwrap();
+
+    assert!(window.check(at("2024-06-03T23:00:00Z")).is_ok());
+    assert!(window.check(at("2024-06-04T05:59:00Z")).is_ok());
+    assert!(window.check(at("2024-06-04T06:00:00Z")).is_err());
+}
+
+#[test]
+fn invalid_windows_are_refused() {
+    for validity in [
+        json!({ "timezone": "Mars/Olympus_Mons" }),
+        json!({ "from": "2024-12-31", "until": "2024-01-01" }),
+        json!({ "from": "31/12/2024" }),
+        json!({ "weekdays": [] }),
+        json!({ "weekdays": ["someday"] }),
+        json!({ "hours": [{ "from": "9am", "to": "17:00" }] }),
+        json!({ "hours": [{ "from": "09:00", "to": "09:00" }] }),
+    ] {
+        assert!(
+            ValidityWindow::from_request(&validity).is_err(),
+            "{}",
+            validity
+        );
+    }
+}
+
+#[test]
+fn windows_round_trip_through_their_redis_token() {
+    let window = ValidityWindow::from_request(&json!({
+        "timezone": "America/New_York",
+        "weekdays": ["sat", "sun"],
+    }))
+    .unwrap();
+
+    let token = ValidityWindow::to_token(Some(&window));
+    assert_eq!(ValidityWindow::from_token(&token), Some(Some(window)));
+    assert_eq!(
+        ValidityWindow::from_token(&ValidityWindow::to_token(None)),
+        Some(None)
+    );
+    assert_eq!(ValidityWindow::from_token(""), None);
+}
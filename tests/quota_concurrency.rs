@@ -0,0 +1,57 @@
+mod common;
+
+use anyhow::Result;
+use digital_twin_mw::utilities::redis_helper::consume_quota_in_redis;
+use futures_util::future::join_all;
+use uuid::Uuid;
+
+#[tokio::test]
+#[ignore = "requires redis"]
+async fn parallel_calls_never_exceed_action_count() -> Result<()> {
+    let redis_url = common::redis_url();
+    let key = format!("Policy:Users:test:{}", Uuid::new_v4());
+    let action_count = 10;
+    let calls = 100;
+
+    let results = join_all((0..calls).map(|_| {
+        let redis_url = redis_url.clone();
+        let key = key.clone();
+        tokio::spawn(async move { consume_quota_in_redis(redis_url, key, action_count, 60).await })
+    }))
+    .await;
+
+    let mut allowed = 0;
+    for result in results {
+        let usage = result?.map_err(|error| anyhow::anyhow!(error.message().to_string()))?;
+        assert!(usage.used <= action_count);
+        if usage.allowed {
+            allowed += 1;
+        }
+    }
+    assert_eq!(allowed, action_count);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires redis"]
+async fn window_starts_with_first_call_and_is_not_rearmed() -> Result<()> {
+    let redis_url = common::redis_url();
+    let key = format!("Policy:Users:test:{}", Uuid::new_v4());
+
+    let first = consume_quota_in_redis(redis_url.clone(), key.clone(), 5, 120)
+        .await
+        .map_err(|error| anyhow::anyhow!(error.message().to_string()))?;
+    assert!(first.allowed);
+    assert_eq!(first.used, 1);
+    assert!(first.reset_in.is_some_and(|reset_in| reset_in <= 120));
+
+    //A longer window on a later call must not push the reset out
+    let second = consume_quota_in_redis(redis_url, key, 5, 3600)
+        .await
+        .map_err(|error| anyhow::anyhow!(error.message().to_string()))?;
+    assert_eq!(second.used, 2);
+    assert!(second.reset_in.is_some_and(|reset_in| reset_in <= 120));
+
+    Ok(())
+}
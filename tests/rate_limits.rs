@@ -0,0 +1,68 @@
+mod common;
+
+use anyhow::Result;
+use digital_twin_mw::utilities::redis_helper::{
+    consume_sliding_window_in_redis, consume_token_bucket_in_redis,
+};
+use futures_util::future::join_all;
+use uuid::Uuid;
+
+#[tokio::test]
+#[ignore = "requires redis"]
+async fn token_bucket_lets_a_burst_through_then_refuses() -> Result<()> {
+    let redis_url = common::redis_url();
+    let key = format!("RateLimit:Users:test:{}", Uuid::new_v4());
+    let burst = 5;
+
+    //One request per minute refills far too slowly to matter during the test
+    let results = join_all((0..20).map(|_| {
+        let redis_url = redis_url.clone();
+        let key = key.clone();
+        tokio::spawn(
+            async move { consume_token_bucket_in_redis(redis_url, key, burst, 60_000.0).await },
+        )
+    }))
+    .await;
+
+    let mut allowed = 0;
+    for result in results {
+        let usage = result?.map_err(|error| anyhow::anyhow!(error.message().to_string()))?;
+        if usage.allowed {
+            allowed += 1;
+        } else {
+            assert!(usage.retry_after_ms > 0 && usage.retry_after_ms <= 60_000);
+        }
+    }
+    assert_eq!(allowed, burst);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires redis"]
+async fn sliding_window_refuses_past_the_limit_until_calls_age_out() -> Result<()> {
+    let redis_url = common::redis_url();
+    let key = format!("RateLimit:Users:test:{}", Uuid::new_v4());
+
+    for remaining in (0..3).rev() {
+        let usage = consume_sliding_window_in_redis(redis_url.clone(), key.clone(), 3, 1000)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message().to_string()))?;
+        assert!(usage.allowed);
+        assert_eq!(usage.remaining, remaining);
+    }
+
+    let refused = consume_sliding_window_in_redis(redis_url.clone(), key.clone(), 3, 1000)
+        .await
+        .map_err(|error| anyhow::anyhow!(error.message().to_string()))?;
+    assert!(!refused.allowed);
+    assert!(refused.retry_after_ms <= 1000);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let later = consume_sliding_window_in_redis(redis_url, key, 3, 1000)
+        .await
+        .map_err(|error| anyhow::anyhow!(error.message().to_string()))?;
+    assert!(later.allowed);
+
+    Ok(())
+}
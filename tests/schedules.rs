@@ -0,0 +1,59 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use digital_twin_mw::helpers::schedule_helpers::{
+    check_schedule_interval, next_run_after, parse_cron,
+};
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[test]
+fn five_field_expressions_run_on_the_minute() {
+    let schedule = parse_cron("30 8 * * *").unwrap();
+
+    let next_run = next_run_after(&schedule, at("2024-06-03T09:00:00Z"), None);
+    assert_eq!(next_run, Some(at("2024-06-04T08:30:00Z")));
+}
+
+#[test]
+fn six_field_expressions_are_used_as_they_are() {
+    let schedule = parse_cron(" 15 */5 * * * * ").unwrap();
+
+    let next_run = next_run_after(&schedule, at("2024-06-03T09:01:00Z"), None);
+    assert_eq!(next_run, Some(at("2024-06-03T09:05:15Z")));
+}
+
+#[test]
+fn invalid_expressions_are_rejected() {
+    for expression in ["", "every minute", "61 * * * *"] {
+        let error = parse_cron(expression).unwrap_err();
+        assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[test]
+fn no_run_is_planned_after_the_schedule_ends() {
+    let schedule = parse_cron("0 * * * *").unwrap();
+    let after = at("2024-06-03T09:30:00Z");
+
+    assert_eq!(
+        next_run_after(&schedule, after, Some(at("2024-06-03T10:00:00Z"))),
+        Some(at("2024-06-03T10:00:00Z"))
+    );
+    assert_eq!(
+        next_run_after(&schedule, after, Some(at("2024-06-03T09:59:59Z"))),
+        None
+    );
+}
+
+#[test]
+fn schedules_firing_more_often_than_a_minute_are_rejected() {
+    assert!(check_schedule_interval(&parse_cron("* * * * *").unwrap()).is_ok());
+    assert!(check_schedule_interval(&parse_cron("0 0 * * *").unwrap()).is_ok());
+
+    let error = check_schedule_interval(&parse_cron("*/10 * * * * *").unwrap()).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,103 @@
+use axum::http::{HeaderMap, StatusCode};
+use digital_twin_mw::{
+    helpers::schema_validation_helpers::{
+        check_request_contract, check_response_contract, RESPONSE_CONTRACT_HEADER,
+    },
+    utilities::upstream_client::UpstreamResponse,
+};
+use hyper::body::Bytes;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+fn temperature_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["temperature"],
+        "properties": { "temperature": { "type": "number" } },
+    })
+}
+
+fn response(status: StatusCode, body: &'static str) -> UpstreamResponse {
+    UpstreamResponse {
+        status,
+        headers: HeaderMap::new(),
+        body: Bytes::from_static(body.as_bytes()),
+    }
+}
+
+#[test]
+fn request_bodies_are_checked_against_the_schema() {
+    let action_id = Uuid::new_v4();
+    let schema = temperature_schema();
+
+    assert!(check_request_contract(
+        action_id,
+        &schema,
+        &Bytes::from_static(b"{\"temperature\": 21.5}")
+    )
+    .is_ok());
+
+    let error = check_request_contract(
+        action_id,
+        &schema,
+        &Bytes::from_static(b"{\"temperature\": \"warm\"}"),
+    )
+    .unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+
+    let error =
+        check_request_contract(action_id, &schema, &Bytes::from_static(b"not json")).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn a_stored_schema_that_does_not_compile_is_a_server_error() {
+    let schema = json!({ "type": "not-a-type" });
+
+    let error =
+        check_request_contract(Uuid::new_v4(), &schema, &Bytes::from_static(b"{}")).unwrap_err();
+    assert_eq!(error.code(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn schemas_are_compiled_once_per_action() {
+    let action_id = Uuid::new_v4();
+    let body = Bytes::from_static(b"{\"temperature\": 21.5}");
+
+    assert!(check_request_contract(action_id, &temperature_schema(), &body).is_ok());
+
+    //The schema compiled on the first call is reused, the value passed later is not compiled again
+    assert!(check_request_contract(action_id, &json!({ "type": "not-a-type" }), &body).is_ok());
+}
+
+#[test]
+fn successful_responses_are_flagged_or_rejected() {
+    let schema = temperature_schema();
+
+    let mut valid = response(StatusCode::OK, "{\"temperature\": 21.5}");
+    assert!(check_response_contract(Uuid::new_v4(), &schema, true, &mut valid).is_ok());
+    assert_eq!(valid.headers[RESPONSE_CONTRACT_HEADER], "valid");
+
+    let mut flagged = response(StatusCode::OK, "{}");
+    assert!(check_response_contract(Uuid::new_v4(), &schema, false, &mut flagged).is_ok());
+    assert_eq!(flagged.headers[RESPONSE_CONTRACT_HEADER], "violated");
+
+    let mut rejected = response(StatusCode::CREATED, "{}");
+    let error = check_response_contract(Uuid::new_v4(), &schema, true, &mut rejected).unwrap_err();
+    assert_eq!(error.code(), StatusCode::BAD_GATEWAY);
+}
+
+#[test]
+fn twin_errors_are_passed_through_unchecked() {
+    let schema = temperature_schema();
+
+    for status in [StatusCode::NOT_FOUND, StatusCode::INTERNAL_SERVER_ERROR] {
+        let mut error_response = response(status, "{\"error\": \"sensor offline\"}");
+        assert!(
+            check_response_contract(Uuid::new_v4(), &schema, true, &mut error_response).is_ok()
+        );
+        assert!(!error_response
+            .headers
+            .contains_key(RESPONSE_CONTRACT_HEADER));
+    }
+}
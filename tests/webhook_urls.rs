@@ -0,0 +1,48 @@
+use std::net::IpAddr;
+
+use digital_twin_mw::utilities::webhook_client::{is_public_address, parse_webhook_url};
+
+fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+}
+
+#[test]
+fn internal_addresses_are_not_public() {
+    for address in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.17.0.2",
+        "192.168.1.10",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "::ffff:127.0.0.1",
+        "fd00:ec2::254",
+        "fe80::1",
+    ] {
+        assert!(!is_public_address(ip(address)), "{} is internal", address);
+    }
+
+    for address in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+        assert!(is_public_address(ip(address)), "{} is public", address);
+    }
+}
+
+#[tokio::test]
+async fn webhook_urls_must_point_to_public_hosts() {
+    assert!(parse_webhook_url("http://93.184.216.34/hooks/results")
+        .await
+        .is_ok());
+
+    for url in [
+        "http://127.0.0.1:6379/",
+        "http://localhost:8081/",
+        "http://169.254.169.254/latest/meta-data/",
+        "https://[::1]/hooks",
+        "ftp://93.184.216.34/hooks",
+        "/hooks/results",
+    ] {
+        assert!(parse_webhook_url(url).await.is_err(), "{} is refused", url);
+    }
+}